@@ -0,0 +1,62 @@
+use std::{
+    net,
+    sync::{Arc, Mutex},
+};
+
+use tokio::time;
+
+use super::origination::Origination;
+use crate::{
+    interface::Interface,
+    lsa::{CheckAge, LSRefreshTime, MinLSInterval},
+};
+
+/// every CheckAge seconds verify the checksums of the lsas in the area's database.
+pub async fn check_lsa_checksum_raw_handle(area: Arc<Mutex<super::Area>>) {
+    let mut interval = time::interval(time::Duration::from_secs(CheckAge as u64));
+    // the first tick completes immediately.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let mut locked_area = area.lock().unwrap();
+        let corrupted = locked_area.verify_lsa_checksums();
+        if corrupted > 0 {
+            crate::error(&format!(
+                "area {} dropped {} corrupted lsa(s) from its database.",
+                locked_area.get_area_id(),
+                corrupted
+            ));
+        } else {
+            crate::debug(&format!(
+                "area {} lsa checksums verified.",
+                locked_area.get_area_id()
+            ));
+        }
+    }
+}
+
+/// the origination of the router-lsa of the area (RFC 2328 12.4): a new instance is originated
+/// each time the area is triggered, at most once every MinLSInterval, and every LSRefreshTime
+/// when nothing changed.
+pub async fn router_lsa_origination_raw_handle(
+    area: Arc<Mutex<super::Area>>,
+    interfaces: Vec<Arc<Mutex<Interface>>>,
+    router_id: net::Ipv4Addr,
+    is_area_border: bool,
+) {
+    let trigger = area.lock().unwrap().get_router_lsa_trigger();
+    loop {
+        let refresh_at = time::Instant::now() + time::Duration::from_secs(LSRefreshTime as u64);
+        if super::origination::originate_router_lsa(&area, &interfaces, router_id, is_area_border)
+            == Origination::Deferred
+        {
+            time::sleep(time::Duration::from_secs(1)).await;
+            continue;
+        }
+        time::sleep(time::Duration::from_secs(MinLSInterval as u64)).await;
+        tokio::select! {
+            _ = trigger.notified() => {}
+            _ = time::sleep_until(refresh_at) => {}
+        }
+    }
+}
@@ -1,10 +1,23 @@
-use std::net;
+use std::{
+    net,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{sync::Notify, time::Instant};
 
 use crate::{
     interface,
-    lsa::{self},
+    lsa::{self, as_external::AS_EXTERNAL_LINK_STATE_TYPE, LinkStateAdvertisement},
+    lsdb::{LinkStateAdvertisementKey, LinkStateDatabase},
+    packet::lsr::LinkStatusRequest,
+    r#as::AutomonySystem,
 };
 
+pub mod handle;
+pub mod origination;
+
+// the address ranges are configured but not summarized yet.
+#[allow(dead_code)]
 pub struct AddressRange {
     start_ipaddr: net::IpAddr,
     end_ipaddr: net::IpAddr,
@@ -13,15 +26,21 @@ pub struct AddressRange {
 
 pub struct Area {
     area_id: net::Ipv4Addr,
+    // the fields not read yet are kept for the area summaries and the spf calculation.
+    #[allow(dead_code)]
     addr_range_list: Vec<AddressRange>,
+    #[allow(dead_code)]
     interface_list: Vec<interface::Interface>,
-    router_lsa_list: Vec<lsa::router::RouterLinkStateAdvertisement>,
-    network_lsa_list: Vec<lsa::network::NetworkLinkStateAdvertisement>,
-    summary_lsa_list: Vec<lsa::summary::SummaryLinkStateAdvertisement>,
+    database: LinkStateDatabase,
+    autonomous_system: Option<Arc<Mutex<AutomonySystem>>>,
+    #[allow(dead_code)]
     short_path_tree: usize,
+    #[allow(dead_code)]
     transit_capabilty: bool,
     external_routing_capabilty: bool,
+    #[allow(dead_code)]
     stub_default_cost: u32,
+    router_lsa_trigger: Arc<Notify>,
 }
 
 impl Area {
@@ -34,15 +53,175 @@ impl Area {
     ) -> Self {
         Self {
             interface_list: Vec::new(),
-            router_lsa_list: Vec::new(),
-            network_lsa_list: Vec::new(),
-            summary_lsa_list: Vec::new(),
+            database: LinkStateDatabase::new(),
+            autonomous_system: None,
             short_path_tree: 0,
             area_id,
             addr_range_list,
             transit_capabilty,
             external_routing_capabilty,
             stub_default_cost,
+            router_lsa_trigger: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn get_area_id(&self) -> net::Ipv4Addr {
+        self.area_id
+    }
+
+    /// ask for the router-lsa of the area to be originated again, as one of the events
+    /// of RFC 2328 12.4 happened. requests made before the origination runs are merged.
+    pub fn trigger_router_lsa_origination(&self) {
+        self.router_lsa_trigger.notify_one();
+    }
+
+    /// the trigger the router-lsa origination waits on.
+    pub fn get_router_lsa_trigger(&self) -> Arc<Notify> {
+        self.router_lsa_trigger.clone()
+    }
+
+    /// whether as-external-lsas are flooded into the area, false for stub areas.
+    pub fn is_external_routing_capable(&self) -> bool {
+        self.external_routing_capabilty
+    }
+
+    /// attach the area to the AS, whose as-external-lsas it sees unless it is a stub area.
+    pub fn set_autonomous_system(&mut self, autonomous_system: Arc<Mutex<AutomonySystem>>) {
+        self.autonomous_system = Some(autonomous_system);
+    }
+
+    /// the lsas flooded through the area: router, network and summary lsas.
+    pub fn get_database(&self) -> &LinkStateDatabase {
+        &self.database
+    }
+
+    pub fn get_database_mut(&mut self) -> &mut LinkStateDatabase {
+        &mut self.database
+    }
+
+    /// the AS the area may see the as-external-lsas of, none for stub areas.
+    fn get_external_scope(&self) -> Option<&Arc<Mutex<AutomonySystem>>> {
+        self.autonomous_system
+            .as_ref()
+            .filter(|_| self.external_routing_capabilty)
+    }
+
+    /// the headers of every lsa in the area's database, and of the as-external-lsas
+    /// unless the area is a stub area, as they are described to a neighbor of the area.
+    pub fn get_lsa_headers(&self) -> Vec<lsa::LinkStateAdvertisementHeader> {
+        let mut lsa_headers = self.database.get_headers();
+        if let Some(autonomous_system) = self.get_external_scope() {
+            lsa_headers.extend(
+                autonomous_system
+                    .lock()
+                    .unwrap()
+                    .get_database()
+                    .get_headers(),
+            );
         }
+        lsa_headers
+    }
+
+    /// the header of the instance of the lsa `key` the area sees.
+    pub fn get_lsa_header(
+        &self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<lsa::LinkStateAdvertisementHeader> {
+        if key.lsa_type == AS_EXTERNAL_LINK_STATE_TYPE {
+            return self
+                .get_external_scope()?
+                .lock()
+                .unwrap()
+                .get_database()
+                .get_header(key);
+        }
+        self.database.get_header(key)
+    }
+
+    /// a copy of the instance of the lsa `key` the area sees.
+    pub fn get_lsa(
+        &self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        if key.lsa_type == AS_EXTERNAL_LINK_STATE_TYPE {
+            return self
+                .get_external_scope()?
+                .lock()
+                .unwrap()
+                .get_database()
+                .get_copy(key);
+        }
+        self.database.get_copy(key)
+    }
+
+    /// when the instance of the lsa `key` the area sees was installed.
+    pub fn get_lsa_installed_at(&self, key: &LinkStateAdvertisementKey) -> Option<Instant> {
+        if key.lsa_type == AS_EXTERNAL_LINK_STATE_TYPE {
+            return self
+                .get_external_scope()?
+                .lock()
+                .unwrap()
+                .get_database()
+                .get_installed_at(key);
+        }
+        self.database.get_installed_at(key)
+    }
+
+    /// install `lsa` in the database of its flooding scope, the area or the AS,
+    /// returning the instance it replaced.
+    pub fn install_lsa(
+        &mut self,
+        lsa: Box<dyn LinkStateAdvertisement + Send>,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        if lsa.get_header().lsa_type == AS_EXTERNAL_LINK_STATE_TYPE {
+            return match self.get_external_scope() {
+                Some(autonomous_system) => autonomous_system
+                    .lock()
+                    .unwrap()
+                    .get_database_mut()
+                    .insert(lsa),
+                None => None,
+            };
+        }
+        self.database.insert(lsa)
+    }
+
+    /// age the lsa `key` prematurely in the database of its flooding scope,
+    /// returning a copy of it to be flooded.
+    pub fn premature_age_lsa(
+        &mut self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        if key.lsa_type == AS_EXTERNAL_LINK_STATE_TYPE {
+            return self
+                .get_external_scope()?
+                .lock()
+                .unwrap()
+                .get_database_mut()
+                .premature_age(key);
+        }
+        self.database.premature_age(key)
+    }
+
+    /// the header of the instance of the lsa described by `lsa_header` the area sees.
+    pub fn lookup_lsa_header(
+        &self,
+        lsa_header: &lsa::LinkStateAdvertisementHeader,
+    ) -> Option<lsa::LinkStateAdvertisementHeader> {
+        self.get_lsa_header(&LinkStateAdvertisementKey::from_header(lsa_header))
+    }
+
+    /// the instance of the lsa requested by `lsr` the area sees.
+    pub fn lookup_lsa(
+        &self,
+        lsr: &LinkStatusRequest,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        self.get_lsa(&LinkStateAdvertisementKey::from_request(lsr))
+    }
+
+    /// verify the checksum of every lsa in the area's database and drop the corrupted ones,
+    /// a failure means the lsa was damaged in memory. returns how many were dropped.
+    pub fn verify_lsa_checksums(&mut self) -> usize {
+        self.database.verify_checksums()
     }
 }
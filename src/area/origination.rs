@@ -0,0 +1,347 @@
+use std::{
+    net,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    flooding::{self, FloodingScope},
+    interface::{status::InterfaceStatus, Interface},
+    lsa::{
+        self,
+        as_external::AS_EXTERNAL_LINK_STATE_TYPE,
+        network::{NetworkLinkStateAdvertisement, NETWORK_LINK_STATE_TYPE},
+        router::{
+            RouterLinkStateAdvertisement, ROUTER_FEATURE_BIT_B, ROUTER_FEATURE_BIT_E,
+            ROUTER_FEATURE_BIT_V, ROUTER_LINK_STATE_TYPE,
+        },
+        InitialSequenceNumber, LinkStateAdvertisement, LinkStateAdvertisementHeader, MaxAge,
+        MaxSequenceNumber,
+    },
+    lsdb::LinkStateAdvertisementKey,
+    neighbor::status::NeighborStatus,
+    OPTION_BIT_E,
+};
+
+use super::Area;
+
+/// # enum Origination
+/// what an origination attempt of a self-originated lsa came to.
+/// - Originated : a new instance was installed and flooded.
+/// - Withdrawn : the lsa is no longer to be originated, an instance left was flushed.
+/// - Deferred : the instance with MaxSequenceNumber is being flushed (RFC 2328 12.1.6),
+///   nothing can be originated until it left the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origination {
+    Originated,
+    Withdrawn,
+    Deferred,
+}
+
+/// the sequence number of the next instance of our lsa `key` in `area`, none while the
+/// instance with MaxSequenceNumber is being flushed, the flushing is started if needed.
+fn get_next_sequence_number(
+    area: &Arc<Mutex<Area>>,
+    interfaces: &[Arc<Mutex<Interface>>],
+    key: &LinkStateAdvertisementKey,
+) -> Option<u32> {
+    let lsa_header = area.lock().unwrap().get_lsa_header(key);
+    match lsa_header {
+        None => Some(InitialSequenceNumber),
+        Some(lsa_header) if lsa_header.sequence_number == MaxSequenceNumber => {
+            if (lsa_header.age as u32) < MaxAge {
+                flooding::flush_lsa(interfaces, area, key);
+            }
+            None
+        }
+        Some(lsa_header) => Some(lsa_header.sequence_number.wrapping_add(1)),
+    }
+}
+
+/// install `lsa` in `area` and flood it out of `interfaces`.
+fn install_and_flood(
+    area: &Arc<Mutex<Area>>,
+    interfaces: &[Arc<Mutex<Interface>>],
+    lsa: Box<dyn LinkStateAdvertisement + Send>,
+) {
+    let lsa_copy = lsa::copy_lsa(lsa.as_ref());
+    let mut locked_area = area.lock().unwrap();
+    let area_id = locked_area.get_area_id();
+    locked_area.install_lsa(lsa);
+    drop(locked_area);
+    if let Some(lsa) = lsa_copy {
+        flooding::flood_lsa(interfaces, lsa.as_ref(), FloodingScope::Area(area_id), None);
+    }
+}
+
+/// originate a new instance of the router-lsa of `area` (RFC 2328 12.4), describing the
+/// interfaces of `interfaces` in the area, install it and flood it.
+/// none of the interfaces nor the area may be locked by the caller.
+pub fn originate_router_lsa(
+    area: &Arc<Mutex<Area>>,
+    interfaces: &[Arc<Mutex<Interface>>],
+    router_id: net::Ipv4Addr,
+    is_area_border: bool,
+) -> Origination {
+    let key = LinkStateAdvertisementKey::new(
+        ROUTER_LINK_STATE_TYPE,
+        u32::from(router_id),
+        u32::from(router_id),
+    );
+    let locked_area = area.lock().unwrap();
+    let area_id = locked_area.get_area_id();
+    let is_external_routing_capable = locked_area.is_external_routing_capable();
+    let is_as_boundary = locked_area.get_lsa_headers().iter().any(|lsa_header| {
+        lsa_header.lsa_type == AS_EXTERNAL_LINK_STATE_TYPE
+            && lsa_header.advertising_router == u32::from(router_id)
+    });
+    drop(locked_area);
+    let sequence_number = match get_next_sequence_number(area, interfaces, &key) {
+        Some(sequence_number) => sequence_number,
+        None => return Origination::Deferred,
+    };
+    let mut links = Vec::new();
+    let mut is_virtual_link_endpoint = false;
+    for interface in interfaces {
+        let locked_interface = interface.lock().unwrap();
+        is_virtual_link_endpoint |= locked_interface.is_full_virtual_link_through(area_id);
+        if locked_interface.get_area_id() == area_id {
+            links.extend(
+                locked_interface
+                    .get_router_links()
+                    .into_iter()
+                    .map(Box::new),
+            );
+        }
+    }
+    let mut features = 0;
+    if is_area_border {
+        features |= ROUTER_FEATURE_BIT_B;
+    }
+    if is_as_boundary {
+        features |= ROUTER_FEATURE_BIT_E;
+    }
+    if is_virtual_link_endpoint {
+        features |= ROUTER_FEATURE_BIT_V;
+    }
+    let options = if is_external_routing_capable {
+        OPTION_BIT_E
+    } else {
+        0
+    };
+    let mut router_lsa = RouterLinkStateAdvertisement::new(
+        LinkStateAdvertisementHeader::new(
+            0,
+            options,
+            ROUTER_LINK_STATE_TYPE,
+            u32::from(router_id),
+            u32::from(router_id),
+            sequence_number,
+            0,
+            0,
+        ),
+        features,
+        links.len() as u16,
+        links,
+    );
+    router_lsa.calculate_checksum();
+    crate::log(&format!(
+        "originating router-lsa of area {}, sequence number {:#x}, {} link(s).",
+        area_id, sequence_number, router_lsa.link_count
+    ));
+    install_and_flood(area, interfaces, Box::new(router_lsa));
+    Origination::Originated
+}
+
+/// originate a new instance of the network-lsa of the network of `interface` (RFC 2328 12.4.2),
+/// listing the routers fully adjacent to us and ourselves, while we are the DR of the network
+/// and fully adjacent to a router at least. otherwise the instance we originated before is flushed.
+/// the link state id of the lsa is the address of the interface.
+/// none of the interfaces nor the area may be locked by the caller.
+pub fn originate_network_lsa(
+    interface: &Arc<Mutex<Interface>>,
+    interfaces: &[Arc<Mutex<Interface>>],
+) -> Origination {
+    let locked_interface = interface.lock().unwrap();
+    let area = match locked_interface.area.clone() {
+        Some(area) => area,
+        None => return Origination::Withdrawn,
+    };
+    let router_id = locked_interface.router_id;
+    let network_mask = u32::from(locked_interface.network_mask);
+    let options = locked_interface.get_options();
+    let key = LinkStateAdvertisementKey::new(
+        NETWORK_LINK_STATE_TYPE,
+        u32::from(locked_interface.ip_addr),
+        u32::from(router_id),
+    );
+    let mut attached_routers: Vec<_> = locked_interface
+        .neighbors
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|neighbor| neighbor.status == NeighborStatus::Full)
+        .map(|neighbor| neighbor.router_id)
+        .collect();
+    let is_designated_router = locked_interface.status == InterfaceStatus::DR;
+    drop(locked_interface);
+    if !is_designated_router || attached_routers.is_empty() {
+        let lsa_header = area.lock().unwrap().get_lsa_header(&key);
+        if lsa_header.is_some_and(|lsa_header| (lsa_header.age as u32) < MaxAge) {
+            flooding::flush_lsa(interfaces, &area, &key);
+        }
+        return Origination::Withdrawn;
+    }
+    let sequence_number = match get_next_sequence_number(&area, interfaces, &key) {
+        Some(sequence_number) => sequence_number,
+        None => return Origination::Deferred,
+    };
+    attached_routers.insert(0, router_id);
+    let mut network_lsa = NetworkLinkStateAdvertisement::new(
+        LinkStateAdvertisementHeader::new(
+            0,
+            options,
+            NETWORK_LINK_STATE_TYPE,
+            key.link_state_id,
+            key.advertising_router,
+            sequence_number,
+            0,
+            0,
+        ),
+        network_mask,
+        attached_routers,
+    );
+    network_lsa.calculate_checksum();
+    crate::log(&format!(
+        "originating network-lsa of {}, sequence number {:#x}, {} attached router(s).",
+        net::Ipv4Addr::from(key.link_state_id),
+        sequence_number,
+        network_lsa.attached_routers.len()
+    ));
+    install_and_flood(&area, interfaces, Box::new(network_lsa));
+    Origination::Originated
+}
+
+/// a newer instance of our network-lsa `key` of `area` was received (RFC 2328 13.4):
+/// the interface it describes originates it again, or it is flushed when no interface has
+/// the address of its link state id anymore.
+pub fn handle_received_network_lsa(
+    interfaces: &[Arc<Mutex<Interface>>],
+    area: &Arc<Mutex<Area>>,
+    key: &LinkStateAdvertisementKey,
+) {
+    let area_id = area.lock().unwrap().get_area_id();
+    for interface in interfaces {
+        let locked_interface = interface.lock().unwrap();
+        if locked_interface.get_area_id() == area_id
+            && u32::from(locked_interface.ip_addr) == key.link_state_id
+        {
+            locked_interface.trigger_network_lsa_origination();
+            return;
+        }
+    }
+    flooding::flush_lsa(interfaces, area, key);
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time;
+
+    use super::*;
+    use crate::{
+        interface::InterfaceNetworkType,
+        neighbor::{self, event::NeighborEvent},
+        testing,
+    };
+
+    const IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 1);
+    const ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(1, 1, 1, 1);
+    const NEIGHBOR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(198, 51, 100, 2);
+    const NEIGHBOR_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(2, 2, 2, 2);
+    const TRANSIT_AREA_ID: net::Ipv4Addr = net::Ipv4Addr::new(0, 0, 0, 1);
+
+    /// a virtual link through the transit area, in the backbone of its router.
+    fn virtual_link() -> (Arc<Mutex<Interface>>, Arc<Mutex<Area>>) {
+        let (interface, _) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::VirtualLink);
+        let transit_area = Arc::new(Mutex::new(Area::new(
+            false,
+            true,
+            0,
+            TRANSIT_AREA_ID,
+            Vec::new(),
+        )));
+        let mut locked_interface = interface.lock().unwrap();
+        locked_interface.status = InterfaceStatus::PointToPoint;
+        locked_interface.transit_area_id = Some(TRANSIT_AREA_ID);
+        locked_interface.transit_area = Some(transit_area.clone());
+        drop(locked_interface);
+        (interface, transit_area)
+    }
+
+    /// the feature bits of our router-lsa originated in `area`.
+    fn originate_features(area: &Arc<Mutex<Area>>, interfaces: &[Arc<Mutex<Interface>>]) -> u16 {
+        assert_eq!(
+            originate_router_lsa(area, interfaces, ROUTER_ID, true),
+            Origination::Originated
+        );
+        let key = LinkStateAdvertisementKey::new(
+            ROUTER_LINK_STATE_TYPE,
+            u32::from(ROUTER_ID),
+            u32::from(ROUTER_ID),
+        );
+        let lsa = area.lock().unwrap().get_lsa(&key).unwrap();
+        lsa.as_any()
+            .downcast_ref::<RouterLinkStateAdvertisement>()
+            .unwrap()
+            .feature
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_virtual_link_sets_the_v_bit_of_the_transit_area() {
+        let (interface, transit_area) = virtual_link();
+        let backbone = interface.lock().unwrap().area.clone().unwrap();
+        let interfaces = vec![interface.clone()];
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Exchange,
+        );
+        assert_eq!(
+            originate_features(&transit_area, &interfaces) & ROUTER_FEATURE_BIT_V,
+            0
+        );
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        let features = originate_features(&transit_area, &interfaces);
+        assert_eq!(features, ROUTER_FEATURE_BIT_B | ROUTER_FEATURE_BIT_V);
+        // the virtual link itself belongs to the backbone, whose router-lsa has no V bit.
+        assert_eq!(
+            originate_features(&backbone, &interfaces) & ROUTER_FEATURE_BIT_V,
+            0
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_link_adjacency_triggers_the_transit_area() {
+        let (interface, transit_area) = virtual_link();
+        let trigger = transit_area.lock().unwrap().get_router_lsa_trigger();
+        let neighbor_key = testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Exchange,
+        );
+        assert_eq!(
+            neighbor::handle_event(&interface, neighbor_key, NeighborEvent::ExchangeDone),
+            Some(NeighborStatus::Full)
+        );
+        assert!(time::timeout(time::Duration::ZERO, trigger.notified())
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::time;
+
+use crate::lsa::CheckAge;
+
+/// every CheckAge seconds verify the checksums of the as-external-lsas, which are kept
+/// in the database of the AS and not in those of the areas.
+pub async fn check_lsa_checksum_raw_handle(autonomous_system: Arc<Mutex<super::AutomonySystem>>) {
+    let mut interval = time::interval(time::Duration::from_secs(CheckAge as u64));
+    // the first tick completes immediately.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let corrupted = autonomous_system.lock().unwrap().verify_lsa_checksums();
+        if corrupted > 0 {
+            crate::error(&format!(
+                "dropped {} corrupted as-external-lsa(s) from the AS database.",
+                corrupted
+            ));
+        } else {
+            crate::debug("as-external-lsa checksums verified.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net;
+
+    use super::*;
+    use crate::{
+        lsa::{
+            as_external::{AsExternalLinkStateAdvertisement, AS_EXTERNAL_LINK_STATE_TYPE},
+            LinkStateAdvertisement, LinkStateAdvertisementHeader,
+        },
+        lsdb::LinkStateAdvertisementKey,
+        r#as::AutomonySystem,
+        OPTION_BIT_E,
+    };
+
+    fn as_external_lsa(link_state_id: u32) -> Box<dyn LinkStateAdvertisement + Send> {
+        let mut as_external_lsa = AsExternalLinkStateAdvertisement::new(
+            LinkStateAdvertisementHeader::new(
+                1,
+                OPTION_BIT_E,
+                AS_EXTERNAL_LINK_STATE_TYPE,
+                link_state_id,
+                0x01010101,
+                0x80000001,
+                0,
+                0,
+            ),
+            net::Ipv4Addr::new(255, 255, 255, 0),
+            0,
+            [0, 0, 20],
+            net::Ipv4Addr::UNSPECIFIED,
+            0,
+            Vec::new(),
+        );
+        as_external_lsa.calculate_checksum();
+        Box::new(as_external_lsa)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn corrupted_as_external_lsa_is_dropped() {
+        let autonomous_system = Arc::new(Mutex::new(AutomonySystem::new()));
+        let corrupted = as_external_lsa(0xc6336400);
+        let corrupted_key = LinkStateAdvertisementKey::from_header(corrupted.get_header());
+        let intact = as_external_lsa(0xcb007100);
+        let intact_key = LinkStateAdvertisementKey::from_header(intact.get_header());
+        {
+            let mut locked_autonomous_system = autonomous_system.lock().unwrap();
+            let database = locked_autonomous_system.get_database_mut();
+            database.insert(corrupted);
+            database.insert(intact);
+            database
+                .get_mut(&corrupted_key)
+                .unwrap()
+                .get_header_mut()
+                .link_state_id ^= 1;
+        }
+        tokio::spawn(check_lsa_checksum_raw_handle(autonomous_system.clone()));
+        time::sleep(time::Duration::from_secs(CheckAge as u64) - time::Duration::from_secs(1))
+            .await;
+        assert_eq!(autonomous_system.lock().unwrap().get_database().len(), 2);
+        time::sleep(time::Duration::from_secs(2)).await;
+        let locked_autonomous_system = autonomous_system.lock().unwrap();
+        let database = locked_autonomous_system.get_database();
+        assert!(database.get(&corrupted_key).is_none());
+        assert!(database.get(&intact_key).is_some());
+    }
+}
@@ -1,6 +1,33 @@
-pub struct AutomonySystem {}
+use crate::lsdb::LinkStateDatabase;
 
+pub mod handle;
+
+/// # struct AutomonySystem
+/// what the router knows of the whole autonomous system.
+/// - database : the lsas flooded through the whole AS, the as-external-lsas.
+#[derive(Default)]
+pub struct AutomonySystem {
+    database: LinkStateDatabase,
+}
 
 impl AutomonySystem {
-    
-}
\ No newline at end of file
+    pub fn new() -> Self {
+        Self {
+            database: LinkStateDatabase::new(),
+        }
+    }
+
+    pub fn get_database(&self) -> &LinkStateDatabase {
+        &self.database
+    }
+
+    pub fn get_database_mut(&mut self) -> &mut LinkStateDatabase {
+        &mut self.database
+    }
+
+    /// verify the checksum of every lsa in the AS database and drop the corrupted ones,
+    /// a failure means the lsa was damaged in memory. returns how many were dropped.
+    pub fn verify_lsa_checksums(&mut self) -> usize {
+        self.database.verify_checksums()
+    }
+}
@@ -0,0 +1,265 @@
+use hmac::{Mac, SimpleHmac};
+use md5::{Digest, Md5};
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+
+/// the value the digest is preset to before computing a hmac-sha digest (RFC 5709 3.3).
+pub const APAD: [u8; 4] = [0x87, 0x8f, 0xe1, 0xf3];
+
+/// # trait DigestAlgorithm
+/// the message digest appended after a packet with cryptographic authentication.
+/// implement it to plug another algorithm into `CryptographicAuthentication`.
+pub trait DigestAlgorithm: Send + Sync {
+    /// the auth data length carried in the ospf header.
+    fn digest_length(&self) -> usize;
+    /// the digest of the encoded ospf packet, without any trailing digest.
+    fn digest(&self, key: &[u8], ospf_packet: &[u8]) -> Vec<u8>;
+}
+
+/// # enum CryptographicAlgorithm
+/// the algorithms an interface can be configured with: keyed-md5 from RFC 2328 D.3
+/// and the hmac-sha family from RFC 5709.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CryptographicAlgorithm {
+    KeyedMd5,
+    HmacSha1,
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+}
+
+/// prepare the key as RFC 5709 3.3 describes: a key as long as the hash is used as is,
+/// a longer one is hashed and a shorter one is padded with zeros.
+fn prepare_key<D: Digest>(key: &[u8]) -> Vec<u8> {
+    let length = <D as Digest>::output_size();
+    if key.len() > length {
+        return D::digest(key).to_vec();
+    }
+    let mut prepared_key = key.to_vec();
+    prepared_key.resize(length, 0);
+    prepared_key
+}
+
+/// HMAC(Ko, data) with the key prepared as RFC 5709 3.3 asks.
+fn hmac_sha<D>(key: &[u8], data: &[&[u8]]) -> Vec<u8>
+where
+    D: Digest + hmac::digest::core_api::BlockSizeUser,
+{
+    let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(&prepare_key::<D>(key))
+        .expect("hmac accepts keys of any length");
+    for data in data {
+        mac.update(data);
+    }
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// HMAC(Ko, packet || Apad), the result is the digest that replaces Apad.
+fn hmac_sha_digest<D>(key: &[u8], ospf_packet: &[u8]) -> Vec<u8>
+where
+    D: Digest + hmac::digest::core_api::BlockSizeUser,
+{
+    let length = <D as Digest>::output_size();
+    hmac_sha::<D>(key, &[ospf_packet, &APAD.repeat(length / APAD.len())])
+}
+
+impl DigestAlgorithm for CryptographicAlgorithm {
+    fn digest_length(&self) -> usize {
+        match self {
+            CryptographicAlgorithm::KeyedMd5 => 16,
+            CryptographicAlgorithm::HmacSha1 => 20,
+            CryptographicAlgorithm::HmacSha256 => 32,
+            CryptographicAlgorithm::HmacSha384 => 48,
+            CryptographicAlgorithm::HmacSha512 => 64,
+        }
+    }
+
+    fn digest(&self, key: &[u8], ospf_packet: &[u8]) -> Vec<u8> {
+        match self {
+            CryptographicAlgorithm::KeyedMd5 => {
+                // the key is zero padded or truncated to 16 bytes and appended to the packet.
+                let mut padded_key = [0; 16];
+                let key_length = key.len().min(padded_key.len());
+                padded_key[..key_length].copy_from_slice(&key[..key_length]);
+                let mut hasher = Md5::new();
+                hasher.update(ospf_packet);
+                hasher.update(padded_key);
+                hasher.finalize().to_vec()
+            }
+            CryptographicAlgorithm::HmacSha1 => hmac_sha_digest::<Sha1>(key, ospf_packet),
+            CryptographicAlgorithm::HmacSha256 => hmac_sha_digest::<Sha256>(key, ospf_packet),
+            CryptographicAlgorithm::HmacSha384 => hmac_sha_digest::<Sha384>(key, ospf_packet),
+            CryptographicAlgorithm::HmacSha512 => hmac_sha_digest::<Sha512>(key, ospf_packet),
+        }
+    }
+}
+
+impl std::str::FromStr for CryptographicAlgorithm {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "md5" | "keyed-md5" => Ok(CryptographicAlgorithm::KeyedMd5),
+            "hmac-sha-1" | "sha1" => Ok(CryptographicAlgorithm::HmacSha1),
+            "hmac-sha-256" | "sha256" => Ok(CryptographicAlgorithm::HmacSha256),
+            "hmac-sha-384" | "sha384" => Ok(CryptographicAlgorithm::HmacSha384),
+            "hmac-sha-512" | "sha512" => Ok(CryptographicAlgorithm::HmacSha512),
+            _ => Err("unknown cryptographic algorithm"),
+        }
+    }
+}
+
+impl std::fmt::Display for CryptographicAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptographicAlgorithm::KeyedMd5 => write!(f, "keyed-md5"),
+            CryptographicAlgorithm::HmacSha1 => write!(f, "hmac-sha-1"),
+            CryptographicAlgorithm::HmacSha256 => write!(f, "hmac-sha-256"),
+            CryptographicAlgorithm::HmacSha384 => write!(f, "hmac-sha-384"),
+            CryptographicAlgorithm::HmacSha512 => write!(f, "hmac-sha-512"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    const HI_THERE: &[u8] = b"Hi There";
+    const LARGER_THAN_BLOCK: &[u8] = b"Test Using Larger Than Block-Size Key - Hash Key First";
+
+    /// RFC 2202 test cases 1 and 6, the second key is longer than the block size.
+    #[test]
+    fn hmac_sha1_vectors() {
+        assert_eq!(
+            hmac_sha::<Sha1>(&[0x0b; 20], &[HI_THERE]),
+            from_hex("b617318655057264e28bc0b6fb378c8ef146be00")
+        );
+        assert_eq!(
+            hmac_sha::<Sha1>(&[0xaa; 80], &[LARGER_THAN_BLOCK]),
+            from_hex("aa4ae5e15272d00e95705637ce8a3b55ed402112")
+        );
+    }
+
+    /// RFC 4231 test cases 1 and 6, the second key is longer than the block size.
+    #[test]
+    fn hmac_sha2_vectors() {
+        assert_eq!(
+            hmac_sha::<Sha256>(&[0x0b; 20], &[HI_THERE]),
+            from_hex("b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7")
+        );
+        assert_eq!(
+            hmac_sha::<Sha256>(&[0xaa; 131], &[LARGER_THAN_BLOCK]),
+            from_hex("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54")
+        );
+        assert_eq!(
+            hmac_sha::<Sha384>(&[0x0b; 20], &[HI_THERE]),
+            from_hex(concat!(
+                "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec6",
+                "82aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6"
+            ))
+        );
+        assert_eq!(
+            hmac_sha::<Sha384>(&[0xaa; 131], &[LARGER_THAN_BLOCK]),
+            from_hex(concat!(
+                "4ece084485813e9088d2c63a041bc5b44f9ef1012a2b588f",
+                "3cd11f05033ac4c60c2ef6ab4030fe8296248df163f44952"
+            ))
+        );
+        assert_eq!(
+            hmac_sha::<Sha512>(&[0x0b; 20], &[HI_THERE]),
+            from_hex(concat!(
+                "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde",
+                "daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"
+            ))
+        );
+        assert_eq!(
+            hmac_sha::<Sha512>(&[0xaa; 131], &[LARGER_THAN_BLOCK]),
+            from_hex(concat!(
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352",
+                "6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+            ))
+        );
+    }
+
+    fn packet() -> Vec<u8> {
+        (0..48).collect()
+    }
+
+    /// the digest covers the packet followed by Apad repeated to the hash length.
+    #[test]
+    fn digest_appends_apad() {
+        let cases = [
+            (
+                CryptographicAlgorithm::HmacSha1,
+                "69cbe8c3961bb9d5da965d07b704197999de333e",
+            ),
+            (
+                CryptographicAlgorithm::HmacSha256,
+                "7e83dff3ca96a3b2bed8f6526d33cbf6cbec57f5ea985030ab88e86b852d778a",
+            ),
+            (
+                CryptographicAlgorithm::HmacSha384,
+                concat!(
+                    "3e375db2e2ca569d396504cb0f917b754a1d7679ac934dc7",
+                    "4b8213ccc70e69f09147d42c59a3d1b570bd4a8db7228e67"
+                ),
+            ),
+            (
+                CryptographicAlgorithm::HmacSha512,
+                concat!(
+                    "a34b27a5032f0790680accc714b4aa39edb3a8efbeb5cadc8b665826b57ac74a",
+                    "329539ed44a45b467b011fc26f43343ebe1cfd33a4278567fef4c1eff076b4d3"
+                ),
+            ),
+        ];
+        for (algorithm, expected) in cases {
+            let digest = algorithm.digest(b"ospf-key", &packet());
+            assert_eq!(digest.len(), algorithm.digest_length());
+            assert_eq!(digest, from_hex(expected), "{}", algorithm);
+        }
+    }
+
+    /// a key longer than the hash but not than the block is hashed first (RFC 5709 3.3),
+    /// where a plain hmac would use it as is.
+    #[test]
+    fn digest_hashes_keys_longer_than_the_hash() {
+        let cases = [
+            (
+                CryptographicAlgorithm::HmacSha1,
+                24,
+                "1caa54c82a8ab2d1b0df7a1786a285205ea7c993",
+            ),
+            (
+                CryptographicAlgorithm::HmacSha256,
+                36,
+                "8eadf09c3333e4519f3e3a762627b4d45898c86f8f9af19aab1e583f629de6fe",
+            ),
+            (
+                CryptographicAlgorithm::HmacSha384,
+                52,
+                concat!(
+                    "925873abab59f8244140faf5d5dc2e460f6ee30a77212fbc",
+                    "9d4da537c5eb59e340c5f6ad4dea598e3a898267a975eafa"
+                ),
+            ),
+            (
+                CryptographicAlgorithm::HmacSha512,
+                68,
+                concat!(
+                    "12d41600939788c70f54a85d36899a5d8eb9cb68fcf8b51103559fa5e743648a",
+                    "1dc6cacadf70da572f3415dc943d257387771cb9f5dd7e22a8df259fc284cc01"
+                ),
+            ),
+        ];
+        for (algorithm, key_length, expected) in cases {
+            let digest = algorithm.digest(&vec![0x5a; key_length], &packet());
+            assert_eq!(digest, from_hex(expected), "{}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::digest::DigestAlgorithm;
+
+/// # struct KeyLifetime
+/// the window in which a key may be used, start inclusive and end exclusive.
+/// - start : none means the key has been valid since ever.
+/// - end : none means the key never expires.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyLifetime {
+    pub start: Option<SystemTime>,
+    pub end: Option<SystemTime>,
+}
+
+impl KeyLifetime {
+    pub const INFINITE: KeyLifetime = KeyLifetime {
+        start: None,
+        end: None,
+    };
+
+    pub fn new(start: Option<SystemTime>, end: Option<SystemTime>) -> Self {
+        Self { start, end }
+    }
+
+    /// build the lifetime from seconds since the unix epoch, as a config would write it.
+    pub fn from_unix_secs(start: Option<u64>, end: Option<u64>) -> Self {
+        Self {
+            start: start.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            end: end.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+        }
+    }
+
+    pub fn is_valid_at(&self, now: SystemTime) -> bool {
+        self.start.is_none_or(|start| start <= now) && self.end.is_none_or(|end| now < end)
+    }
+}
+
+/// # struct AuthenticationKey
+/// one key of a key chain, see RFC 2328 D.3.
+/// - send_lifetime : when the key may be used to sign the packets we send.
+/// - accept_lifetime : when packets signed with the key are accepted.
+#[derive(Clone)]
+pub struct AuthenticationKey {
+    pub key_id: u8,
+    pub key: Vec<u8>,
+    pub algorithm: Arc<dyn DigestAlgorithm>,
+    pub send_lifetime: KeyLifetime,
+    pub accept_lifetime: KeyLifetime,
+}
+
+impl AuthenticationKey {
+    pub fn new(
+        key_id: u8,
+        key: &[u8],
+        algorithm: Arc<dyn DigestAlgorithm>,
+        send_lifetime: KeyLifetime,
+        accept_lifetime: KeyLifetime,
+    ) -> Self {
+        Self {
+            key_id,
+            key: key.to_vec(),
+            algorithm,
+            send_lifetime,
+            accept_lifetime,
+        }
+    }
+
+    pub fn digest(&self, ospf_packet: &[u8]) -> Vec<u8> {
+        self.algorithm.digest(&self.key, ospf_packet)
+    }
+}
+
+/// # struct KeyChain
+/// a named set of keys shared by the interfaces that refer to it.
+/// while rolling a key over, the old and the new key are both accepted,
+/// and the newest one is used for sending, so no adjacency is lost.
+#[derive(Clone)]
+pub struct KeyChain {
+    pub name: String,
+    keys: Vec<AuthenticationKey>,
+}
+
+impl KeyChain {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// add a key to the chain, replacing the key with the same key id if any.
+    pub fn add_key(&mut self, key: AuthenticationKey) {
+        self.keys.retain(|k| k.key_id != key.key_id);
+        self.keys.push(key);
+    }
+
+    pub fn remove_key(&mut self, key_id: u8) -> Option<AuthenticationKey> {
+        let index = self.keys.iter().position(|k| k.key_id == key_id)?;
+        Some(self.keys.remove(index))
+    }
+
+    pub fn get_keys(&self) -> &[AuthenticationKey] {
+        &self.keys
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// the key to sign outgoing packets with at `now`: the valid key that started
+    /// to be sent last, the highest key id breaking ties.
+    /// none once every key has expired, the packets are then not sent at all, as RFC 2328 D.3
+    /// forbids falling back to no authentication.
+    pub fn send_key(&self, now: SystemTime) -> Option<&AuthenticationKey> {
+        self.keys
+            .iter()
+            .filter(|k| k.send_lifetime.is_valid_at(now))
+            .max_by_key(|k| (k.send_lifetime.start, k.key_id))
+    }
+
+    /// the key a received packet signed with `key_id` is checked against at `now`.
+    pub fn accept_key(&self, key_id: u8, now: SystemTime) -> Option<&AuthenticationKey> {
+        self.keys
+            .iter()
+            .find(|k| k.key_id == key_id && k.accept_lifetime.is_valid_at(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::digest::CryptographicAlgorithm;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn new_key(
+        key_id: u8,
+        send: (Option<u64>, Option<u64>),
+        accept: (Option<u64>, Option<u64>),
+    ) -> AuthenticationKey {
+        AuthenticationKey::new(
+            key_id,
+            &[key_id; 8],
+            Arc::new(CryptographicAlgorithm::HmacSha256),
+            KeyLifetime::from_unix_secs(send.0, send.1),
+            KeyLifetime::from_unix_secs(accept.0, accept.1),
+        )
+    }
+
+    /// key 1 is rolled over to key 2 at 2000, both are accepted from 1900 to 2100.
+    fn rollover_chain() -> KeyChain {
+        let mut key_chain = KeyChain::new("test");
+        key_chain.add_key(new_key(
+            1,
+            (Some(1000), Some(2000)),
+            (Some(1000), Some(2100)),
+        ));
+        key_chain.add_key(new_key(2, (Some(2000), None), (Some(1900), None)));
+        key_chain
+    }
+
+    #[test]
+    fn send_key_picks_the_newest_valid_key() {
+        let key_chain = rollover_chain();
+        assert!(key_chain.send_key(at(500)).is_none());
+        assert_eq!(key_chain.send_key(at(1500)).unwrap().key_id, 1);
+        assert_eq!(key_chain.send_key(at(2000)).unwrap().key_id, 2);
+        assert_eq!(key_chain.send_key(at(5000)).unwrap().key_id, 2);
+    }
+
+    #[test]
+    fn send_key_prefers_the_key_started_last_while_both_are_valid() {
+        let mut key_chain = KeyChain::new("test");
+        key_chain.add_key(new_key(7, (Some(1000), Some(3000)), (None, None)));
+        key_chain.add_key(new_key(3, (Some(2000), Some(3000)), (None, None)));
+        assert_eq!(key_chain.send_key(at(1500)).unwrap().key_id, 7);
+        assert_eq!(key_chain.send_key(at(2500)).unwrap().key_id, 3);
+    }
+
+    #[test]
+    fn accept_key_accepts_both_keys_during_the_overlap() {
+        let key_chain = rollover_chain();
+        assert_eq!(key_chain.accept_key(1, at(1500)).unwrap().key_id, 1);
+        assert!(key_chain.accept_key(2, at(1500)).is_none());
+        for now in [1900, 2000, 2099] {
+            assert_eq!(key_chain.accept_key(1, at(now)).unwrap().key_id, 1);
+            assert_eq!(key_chain.accept_key(2, at(now)).unwrap().key_id, 2);
+        }
+        assert!(key_chain.accept_key(1, at(2100)).is_none());
+        assert_eq!(key_chain.accept_key(2, at(2100)).unwrap().key_id, 2);
+        assert!(key_chain.accept_key(3, at(2000)).is_none());
+    }
+
+    #[test]
+    fn expired_chain_has_no_key() {
+        let mut key_chain = KeyChain::new("test");
+        key_chain.add_key(new_key(1, (None, Some(1000)), (None, Some(1000))));
+        key_chain.add_key(new_key(2, (None, Some(2000)), (None, Some(2000))));
+        assert_eq!(key_chain.send_key(at(1999)).unwrap().key_id, 2);
+        assert!(key_chain.send_key(at(2000)).is_none());
+        assert!(key_chain.send_key(at(3000)).is_none());
+        assert!(key_chain.accept_key(1, at(3000)).is_none());
+        assert!(key_chain.accept_key(2, at(3000)).is_none());
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    collections::HashMap,
+    net,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use keychain::{AuthenticationKey, KeyChain};
+
+use crate::error::AuthenticationError;
+use crate::packet::{
+    OspfPacket, OspfPacketHeader, AUTH_TYPE_CRYPTOGRAPHIC, AUTH_TYPE_NULL,
+    AUTH_TYPE_SIMPLE_PASSWORD,
+};
+
+pub mod digest;
+pub mod keychain;
+
+/// # struct CryptographicAuthentication
+/// the state of RFC 2328 D.3 cryptographic authentication on an interface.
+/// clones share the key chain and the sequence numbers, so every handler of the interface
+/// sees the same ones, and keys added to the chain take effect without a restart.
+/// - key_chain : the keys packets are signed with and checked against.
+/// - sequence_number : the non-decreasing cryptographic sequence number we send.
+/// - neighbor_sequence_numbers : the last sequence number accepted from each neighbor.
+#[derive(Clone)]
+pub struct CryptographicAuthentication {
+    pub key_chain: Arc<Mutex<KeyChain>>,
+    sequence_number: Arc<Mutex<u32>>,
+    neighbor_sequence_numbers: Arc<Mutex<HashMap<net::Ipv4Addr, u32>>>,
+}
+
+impl CryptographicAuthentication {
+    /// the sequence number starts from the current time, so it keeps increasing across restarts.
+    pub fn new(key_chain: Arc<Mutex<KeyChain>>) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as u32)
+            .unwrap_or(0);
+        Self {
+            key_chain,
+            sequence_number: Arc::new(Mutex::new(now)),
+            neighbor_sequence_numbers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn next_sequence_number(&self) -> u32 {
+        let mut sequence_number = self.sequence_number.lock().unwrap();
+        *sequence_number = sequence_number.wrapping_add(1);
+        *sequence_number
+    }
+
+    /// the key outgoing packets are signed with right now.
+    pub fn send_key(&self) -> Option<AuthenticationKey> {
+        self.key_chain
+            .lock()
+            .unwrap()
+            .send_key(SystemTime::now())
+            .cloned()
+    }
+
+    /// the key a packet signed with `key_id` is checked against right now.
+    pub fn accept_key(&self, key_id: u8) -> Option<AuthenticationKey> {
+        self.key_chain
+            .lock()
+            .unwrap()
+            .accept_key(key_id, SystemTime::now())
+            .cloned()
+    }
+
+    /// forget the sequence number of a neighbor that went away, so it may start over.
+    pub fn forget_neighbor(&self, neighbor_addr: net::Ipv4Addr) {
+        self.neighbor_sequence_numbers
+            .lock()
+            .unwrap()
+            .remove(&neighbor_addr);
+    }
+}
+
+/// # enum Authentication
+/// the authentication an interface applies to the packets it sends and expects
+/// on the packets it receives (RFC 2328 appendix D).
+#[derive(Clone)]
+pub enum Authentication {
+    Null,
+    SimplePassword([u8; 8]),
+    Cryptographic(CryptographicAuthentication),
+}
+
+impl Authentication {
+    /// build the authentication from the interface's auth type, the password used by
+    /// simple password authentication and the key chain used by cryptographic authentication.
+    pub fn new(
+        auth_type: u32,
+        auth_key: u64,
+        auth_key_chain: Option<Arc<Mutex<KeyChain>>>,
+    ) -> Result<Self, AuthenticationError> {
+        match auth_type as u16 {
+            AUTH_TYPE_NULL => Ok(Authentication::Null),
+            AUTH_TYPE_SIMPLE_PASSWORD => Ok(Authentication::SimplePassword(auth_key.to_be_bytes())),
+            AUTH_TYPE_CRYPTOGRAPHIC => match auth_key_chain {
+                Some(key_chain) => Ok(Authentication::Cryptographic(
+                    CryptographicAuthentication::new(key_chain),
+                )),
+                None => Err(AuthenticationError::NoSendKey),
+            },
+            _ => Err(AuthenticationError::UnsupportedType(auth_type as u16)),
+        }
+    }
+
+    pub fn get_auth_type(&self) -> u16 {
+        match self {
+            Authentication::Null => AUTH_TYPE_NULL,
+            Authentication::SimplePassword(_) => AUTH_TYPE_SIMPLE_PASSWORD,
+            Authentication::Cryptographic(_) => AUTH_TYPE_CRYPTOGRAPHIC,
+        }
+    }
+
+    /// stamp the authentication into the header of an outgoing packet,
+    /// fill in its length and checksum and encode it.
+    /// with cryptographic authentication the digest computed with the newest key
+    /// of the key chain is appended after the packet.
+    pub fn seal(&self, packet: &mut dyn OspfPacket) -> Result<Vec<u8>, AuthenticationError> {
+        let (authentication, send_key) = match self {
+            Authentication::Null => ([0; 8], None),
+            Authentication::SimplePassword(password) => (*password, None),
+            Authentication::Cryptographic(crypto) => {
+                let key = crypto.send_key().ok_or(AuthenticationError::NoSendKey)?;
+                let sequence_number = crypto.next_sequence_number().to_be_bytes();
+                (
+                    [
+                        0,
+                        0,
+                        key.key_id,
+                        key.algorithm.digest_length() as u8,
+                        sequence_number[0],
+                        sequence_number[1],
+                        sequence_number[2],
+                        sequence_number[3],
+                    ],
+                    Some(key),
+                )
+            }
+        };
+        let header = packet.get_header_mut();
+        header.auth_type = self.get_auth_type();
+        header.authentication = authentication;
+        packet.calculate_checksum();
+        let mut bytes = packet.to_be_bytes();
+        if let Some(key) = send_key {
+            let digest = key.digest(&bytes);
+            bytes.extend_from_slice(&digest);
+        }
+        Ok(bytes)
+    }
+
+    /// authenticate a received packet on its raw bytes, the header must have been
+    /// decoded and the checksum verified before.
+    /// `ospf_packet` is the whole ip payload, so it still holds the appended digest.
+    pub fn verify(
+        &self,
+        ospf_packet: &[u8],
+        source: net::Ipv4Addr,
+    ) -> Result<(), AuthenticationError> {
+        let header = OspfPacketHeader::from_be_bytes(ospf_packet)
+            .map_err(|_| AuthenticationError::Malformed)?;
+        if header.auth_type != self.get_auth_type() {
+            return Err(AuthenticationError::TypeMismatch {
+                expected: self.get_auth_type(),
+                actual: header.auth_type,
+            });
+        }
+        match self {
+            Authentication::Null => Ok(()),
+            Authentication::SimplePassword(password) => {
+                if header.authentication != *password {
+                    return Err(AuthenticationError::KeyMismatch);
+                }
+                Ok(())
+            }
+            Authentication::Cryptographic(crypto) => {
+                let key_id = header.authentication[2];
+                let auth_data_length = header.authentication[3] as usize;
+                let sequence_number = u32::from_be_bytes([
+                    header.authentication[4],
+                    header.authentication[5],
+                    header.authentication[6],
+                    header.authentication[7],
+                ]);
+                let key = crypto
+                    .accept_key(key_id)
+                    .ok_or(AuthenticationError::UnknownKeyId(key_id))?;
+                let packet_length = header.packet_length as usize;
+                if auth_data_length != key.algorithm.digest_length()
+                    || ospf_packet.len() < packet_length + auth_data_length
+                {
+                    return Err(AuthenticationError::Malformed);
+                }
+                let digest = key.digest(&ospf_packet[..packet_length]);
+                if digest[..] != ospf_packet[packet_length..packet_length + auth_data_length] {
+                    return Err(AuthenticationError::DigestMismatch);
+                }
+                let mut neighbor_sequence_numbers =
+                    crypto.neighbor_sequence_numbers.lock().unwrap();
+                if let Some(&last_sequence_number) = neighbor_sequence_numbers.get(&source) {
+                    if sequence_number < last_sequence_number {
+                        return Err(AuthenticationError::Replay {
+                            last: last_sequence_number,
+                            received: sequence_number,
+                        });
+                    }
+                }
+                neighbor_sequence_numbers.insert(source, sequence_number);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auth::{digest::CryptographicAlgorithm, keychain::KeyLifetime},
+        testing,
+    };
+
+    const SOURCE: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 2);
+
+    fn key_chain(key_id: u8, key: &[u8]) -> Arc<Mutex<KeyChain>> {
+        let mut key_chain = KeyChain::new("test");
+        key_chain.add_key(AuthenticationKey::new(
+            key_id,
+            key,
+            Arc::new(CryptographicAlgorithm::KeyedMd5),
+            KeyLifetime::INFINITE,
+            KeyLifetime::INFINITE,
+        ));
+        Arc::new(Mutex::new(key_chain))
+    }
+
+    fn cryptographic(key_chain: Arc<Mutex<KeyChain>>) -> Authentication {
+        Authentication::Cryptographic(CryptographicAuthentication::new(key_chain))
+    }
+
+    fn seal_hello(authentication: &Authentication) -> Vec<u8> {
+        let mut hello_packet = testing::new_hello_packet(
+            net::Ipv4Addr::new(2, 2, 2, 2),
+            1,
+            net::Ipv4Addr::UNSPECIFIED,
+            net::Ipv4Addr::UNSPECIFIED,
+            Vec::new(),
+        );
+        authentication.seal(&mut hello_packet).unwrap()
+    }
+
+    #[test]
+    fn sealed_packet_verifies() {
+        let sender = cryptographic(key_chain(1, b"secret"));
+        let receiver = cryptographic(key_chain(1, b"secret"));
+        let bytes = seal_hello(&sender);
+        let header = OspfPacketHeader::from_be_bytes(&bytes).unwrap();
+        assert_eq!(header.auth_type, AUTH_TYPE_CRYPTOGRAPHIC);
+        assert_eq!(header.authentication[2], 1);
+        assert_eq!(header.authentication[3], 16);
+        // the digest follows the packet, outside of its length.
+        assert_eq!(bytes.len(), header.packet_length as usize + 16);
+        assert_eq!(receiver.verify(&bytes, SOURCE), Ok(()));
+    }
+
+    #[test]
+    fn tampered_packet_is_rejected() {
+        let sender = cryptographic(key_chain(1, b"secret"));
+        let receiver = cryptographic(key_chain(1, b"secret"));
+        let bytes = seal_hello(&sender);
+        let mut tampered_digest = bytes.clone();
+        *tampered_digest.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            receiver.verify(&tampered_digest, SOURCE),
+            Err(AuthenticationError::DigestMismatch)
+        );
+        let mut tampered_body = bytes.clone();
+        tampered_body[OspfPacketHeader::length()] ^= 1;
+        assert_eq!(
+            receiver.verify(&tampered_body, SOURCE),
+            Err(AuthenticationError::DigestMismatch)
+        );
+        let truncated = &bytes[..bytes.len() - 1];
+        assert_eq!(
+            receiver.verify(truncated, SOURCE),
+            Err(AuthenticationError::Malformed)
+        );
+        let other_key = cryptographic(key_chain(1, b"other"));
+        assert_eq!(
+            other_key.verify(&bytes, SOURCE),
+            Err(AuthenticationError::DigestMismatch)
+        );
+    }
+
+    #[test]
+    fn unknown_key_id_is_rejected() {
+        let sender = cryptographic(key_chain(1, b"secret"));
+        let receiver = cryptographic(key_chain(2, b"secret"));
+        assert_eq!(
+            receiver.verify(&seal_hello(&sender), SOURCE),
+            Err(AuthenticationError::UnknownKeyId(1))
+        );
+    }
+
+    #[test]
+    fn sequence_number_may_not_go_backwards() {
+        let sender = cryptographic(key_chain(1, b"secret"));
+        let receiver = cryptographic(key_chain(1, b"secret"));
+        let first = seal_hello(&sender);
+        let second = seal_hello(&sender);
+        assert_eq!(receiver.verify(&second, SOURCE), Ok(()));
+        // the same sequence number is accepted again, hellos may be sent back to back.
+        assert_eq!(receiver.verify(&second, SOURCE), Ok(()));
+        let sequence_number = |bytes: &[u8]| {
+            let header = OspfPacketHeader::from_be_bytes(bytes).unwrap();
+            u32::from_be_bytes(header.authentication[4..8].try_into().unwrap())
+        };
+        assert_eq!(
+            receiver.verify(&first, SOURCE),
+            Err(AuthenticationError::Replay {
+                last: sequence_number(&second),
+                received: sequence_number(&first),
+            })
+        );
+        // every neighbor has its own sequence number.
+        assert_eq!(
+            receiver.verify(&first, net::Ipv4Addr::new(192, 0, 2, 3)),
+            Ok(())
+        );
+        // a neighbor gone and back may start over.
+        if let Authentication::Cryptographic(crypto) = &receiver {
+            crypto.forget_neighbor(SOURCE);
+        }
+        assert_eq!(receiver.verify(&first, SOURCE), Ok(()));
+    }
+
+    #[test]
+    fn expired_chain_seals_nothing() {
+        let mut key_chain = KeyChain::new("test");
+        key_chain.add_key(AuthenticationKey::new(
+            1,
+            b"secret",
+            Arc::new(CryptographicAlgorithm::KeyedMd5),
+            KeyLifetime::from_unix_secs(None, Some(1000)),
+            KeyLifetime::INFINITE,
+        ));
+        let authentication = cryptographic(Arc::new(Mutex::new(key_chain)));
+        let mut hello_packet = testing::new_hello_packet(
+            net::Ipv4Addr::new(2, 2, 2, 2),
+            1,
+            net::Ipv4Addr::UNSPECIFIED,
+            net::Ipv4Addr::UNSPECIFIED,
+            Vec::new(),
+        );
+        assert_eq!(
+            authentication.seal(&mut hello_packet),
+            Err(AuthenticationError::NoSendKey)
+        );
+    }
+
+    #[test]
+    fn authentication_type_must_match() {
+        let sender = cryptographic(key_chain(1, b"secret"));
+        let bytes = seal_hello(&sender);
+        assert_eq!(
+            Authentication::Null.verify(&bytes, SOURCE),
+            Err(AuthenticationError::TypeMismatch {
+                expected: AUTH_TYPE_NULL,
+                actual: AUTH_TYPE_CRYPTOGRAPHIC,
+            })
+        );
+    }
+}
@@ -1,8 +1,20 @@
-use clap::{Arg, ArgMatches, Command};
+use clap::{value_parser, Arg, ArgMatches, Command};
+use ospf_lib::{
+    auth::{
+        digest::CryptographicAlgorithm,
+        keychain::{AuthenticationKey, KeyLifetime},
+    },
+    interface::{self, event::InterfaceEvent, DEFAULT_AUTH_ALGORITHM},
+    lsdb::LinkStateDatabase,
+    router,
+};
 use rustyline::{
     Completer, CompletionType, Config, Editor, Helper, Highlighter, Hinter, Validator,
 };
-use std::net;
+use std::{
+    net::Ipv4Addr,
+    sync::{Arc, Mutex},
+};
 #[derive(Helper, Hinter, Validator, Highlighter, Completer)]
 struct OspfHelper;
 
@@ -16,11 +28,39 @@ lazy_static::lazy_static! {
     .arg(Arg::new("interface").help("Interface name").required(true));
     static ref INTERFACE_LIST_COMMAND : Command = Command::new("list")
     .about("List all interfaces");
+    static ref INTERFACE_EVENT_COMMAND : Command = Command::new("event")
+    .about("Inject an event into the interface state machine")
+    .arg(Arg::new("interface").help("Interface name").required(true))
+    .arg(Arg::new("event").help("up/down/wait-timer/backup-seen/neighbor-change/loop/unloop").required(true));
     static ref INTERFACE_COMMAND : Command =  Command::new("interface")
     .about("Interface commands")
     .subcommand(INTERFACE_UP_COMMAND.clone())
     .subcommand(INTERFACE_DOWN_COMMAND.clone())
-    .subcommand(INTERFACE_LIST_COMMAND.clone());
+    .subcommand(INTERFACE_LIST_COMMAND.clone())
+    .subcommand(INTERFACE_EVENT_COMMAND.clone());
+    static ref KEY_CHAIN_ADD_COMMAND : Command = Command::new("add")
+    .about("Add a key to a key chain, replacing the key with the same id")
+    .arg(Arg::new("chain").help("Key chain name").required(true))
+    .arg(Arg::new("key-id").help("Key id").required(true).value_parser(value_parser!(u8)))
+    .arg(Arg::new("key").help("Key").required(true).value_parser(value_parser!(u64)))
+    .arg(Arg::new("algorithm").long("algorithm").help("md5/hmac-sha-1/hmac-sha-256/hmac-sha-384/hmac-sha-512"))
+    .arg(Arg::new("send-start").long("send-start").help("Send lifetime start in unix seconds").value_parser(value_parser!(u64)))
+    .arg(Arg::new("send-end").long("send-end").help("Send lifetime end in unix seconds").value_parser(value_parser!(u64)))
+    .arg(Arg::new("accept-start").long("accept-start").help("Accept lifetime start in unix seconds").value_parser(value_parser!(u64)))
+    .arg(Arg::new("accept-end").long("accept-end").help("Accept lifetime end in unix seconds").value_parser(value_parser!(u64)));
+    static ref KEY_CHAIN_REMOVE_COMMAND : Command = Command::new("remove")
+    .about("Remove a key from a key chain")
+    .arg(Arg::new("chain").help("Key chain name").required(true))
+    .arg(Arg::new("key-id").help("Key id").required(true).value_parser(value_parser!(u8)));
+    static ref KEY_CHAIN_LIST_COMMAND : Command = Command::new("list")
+    .about("List the keys of all key chains");
+    static ref KEY_CHAIN_COMMAND : Command = Command::new("key-chain")
+    .about("Key chain commands")
+    .subcommand(KEY_CHAIN_ADD_COMMAND.clone())
+    .subcommand(KEY_CHAIN_REMOVE_COMMAND.clone())
+    .subcommand(KEY_CHAIN_LIST_COMMAND.clone());
+    static ref DATABASE_COMMAND : Command = Command::new("database")
+    .about("Show the link state databases");
     static ref EXIT_COMMAND : Command = Command::new("exit")
     .about("Exit the ospf cli");
     static ref OSPF_COMMAND : Command =  Command::new("ospf")
@@ -28,19 +68,25 @@ lazy_static::lazy_static! {
     .author("doggie")
     .about("OSPF CLI")
     .subcommand(INTERFACE_COMMAND.clone())
+    .subcommand(KEY_CHAIN_COMMAND.clone())
+    .subcommand(DATABASE_COMMAND.clone())
     .subcommand(EXIT_COMMAND.clone());
 
 }
 
-fn match_ospf_command(line: &str) {
+fn match_ospf_command(line: &str, router: &Arc<Mutex<router::Router>>) {
     match OSPF_COMMAND
         .clone()
         .try_get_matches_from(line.split_whitespace())
     {
         Ok(matches) => {
             if let Some(sub_command_matches) = matches.subcommand_matches("interface") {
-                match_interface_subcommand(sub_command_matches);
-            } else if let Some(_) = matches.subcommand_matches("exit") {
+                match_interface_subcommand(sub_command_matches, router);
+            } else if let Some(sub_command_matches) = matches.subcommand_matches("key-chain") {
+                match_key_chain_subcommand(sub_command_matches, router);
+            } else if matches.subcommand_matches("database").is_some() {
+                print_databases(router);
+            } else if matches.subcommand_matches("exit").is_some() {
                 println!("Bye");
                 std::process::exit(0);
             } else {
@@ -56,15 +102,76 @@ fn match_ospf_command(line: &str) {
     }
 }
 
-fn match_interface_subcommand(args_match: &ArgMatches) {
+fn print_database(database: &LinkStateDatabase) {
+    println!("Type\tLink State ID\tADV Router\tAge\tSeq#\t\tChecksum");
+    for lsa_type in 1..=5 {
+        for lsa in database.iter_type(lsa_type) {
+            let header = lsa.get_header();
+            println!(
+                "{}\t{}\t\t{}\t\t{}\t{:#010x}\t{:#06x}",
+                header.lsa_type,
+                Ipv4Addr::from(header.link_state_id),
+                Ipv4Addr::from(header.advertising_router),
+                header.age,
+                header.sequence_number,
+                header.checksum
+            );
+        }
+    }
+}
+
+fn print_databases(router: &Arc<Mutex<router::Router>>) {
+    let locked_router = router.lock().unwrap();
+    let areas = locked_router.get_areas();
+    let autonomous_system = locked_router.get_autonomous_system();
+    drop(locked_router);
+    for area in areas {
+        let area = area.lock().unwrap();
+        println!("Area {}:", area.get_area_id());
+        print_database(area.get_database());
+    }
+    println!("AS External:");
+    print_database(autonomous_system.lock().unwrap().get_database());
+}
+
+fn inject_interface_event(
+    router: &Arc<Mutex<router::Router>>,
+    interface_name: &str,
+    event: InterfaceEvent,
+) {
+    let interface = router.lock().unwrap().get_interface(interface_name);
+    match interface {
+        Some(interface) => {
+            let status = interface::handle_event(&interface, event);
+            println!("Interface {}: {:?}", interface_name, status);
+        }
+        None => println!("No such interface: {}", interface_name),
+    }
+}
+
+fn match_interface_subcommand(args_match: &ArgMatches, router: &Arc<Mutex<router::Router>>) {
     if let Some(sub_command_matches) = args_match.subcommand_matches("up") {
         let interface = sub_command_matches.get_one::<String>("interface").unwrap();
-        println!("Interface up: {}", interface);
+        inject_interface_event(router, interface, InterfaceEvent::InterfaceUp);
     } else if let Some(sub_command_matches) = args_match.subcommand_matches("down") {
         let interface = sub_command_matches.get_one::<String>("interface").unwrap();
-        println!("Interface down: {}", interface);
-    } else if let Some(_) = args_match.subcommand_matches("list") {
-        println!("List all interfaces");
+        inject_interface_event(router, interface, InterfaceEvent::InterfaceDown);
+    } else if args_match.subcommand_matches("list").is_some() {
+        let interfaces = router.lock().unwrap().get_interfaces();
+        for interface in interfaces {
+            let interface = interface.lock().unwrap();
+            println!(
+                "{}\t{}\t{}\t{:?}",
+                interface.name, interface.ip_addr, interface.network_type, interface.status
+            );
+        }
+    } else if let Some(sub_command_matches) = args_match.subcommand_matches("event") {
+        let interface = sub_command_matches.get_one::<String>("interface").unwrap();
+        let event = sub_command_matches.get_one::<String>("event").unwrap();
+        match event.parse::<InterfaceEvent>() {
+            Ok(event) => inject_interface_event(router, interface, event),
+            Err(e) => println!("{}: {}", e, event),
+        }
     } else {
         INTERFACE_COMMAND
             .clone()
@@ -73,7 +180,85 @@ fn match_interface_subcommand(args_match: &ArgMatches) {
     }
 }
 
-pub(super) fn cli(router_id: net::Ipv4Addr) -> Result<(), Box<dyn std::error::Error>> {
+fn format_lifetime(lifetime: &KeyLifetime) -> String {
+    let format_time = |time: Option<std::time::SystemTime>, unbounded: &str| match time {
+        Some(time) => time
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs().to_string())
+            .unwrap_or_default(),
+        None => unbounded.to_string(),
+    };
+    format!(
+        "{}..{}",
+        format_time(lifetime.start, "always"),
+        format_time(lifetime.end, "never")
+    )
+}
+
+fn match_key_chain_subcommand(args_match: &ArgMatches, router: &Arc<Mutex<router::Router>>) {
+    if let Some(sub_command_matches) = args_match.subcommand_matches("add") {
+        let chain = sub_command_matches.get_one::<String>("chain").unwrap();
+        let key_id = *sub_command_matches.get_one::<u8>("key-id").unwrap();
+        let key = *sub_command_matches.get_one::<u64>("key").unwrap();
+        let algorithm = match sub_command_matches.get_one::<String>("algorithm") {
+            Some(algorithm) => match algorithm.parse::<CryptographicAlgorithm>() {
+                Ok(algorithm) => algorithm,
+                Err(e) => {
+                    println!("{}: {}", e, algorithm);
+                    return;
+                }
+            },
+            None => DEFAULT_AUTH_ALGORITHM,
+        };
+        let lifetime = |start: &str, end: &str| {
+            KeyLifetime::from_unix_secs(
+                sub_command_matches.get_one::<u64>(start).copied(),
+                sub_command_matches.get_one::<u64>(end).copied(),
+            )
+        };
+        let key_chain = router.lock().unwrap().get_key_chain(chain);
+        key_chain.lock().unwrap().add_key(AuthenticationKey::new(
+            key_id,
+            &key.to_be_bytes(),
+            Arc::new(algorithm),
+            lifetime("send-start", "send-end"),
+            lifetime("accept-start", "accept-end"),
+        ));
+        println!("Key {} added to key chain {}", key_id, chain);
+    } else if let Some(sub_command_matches) = args_match.subcommand_matches("remove") {
+        let chain = sub_command_matches.get_one::<String>("chain").unwrap();
+        let key_id = *sub_command_matches.get_one::<u8>("key-id").unwrap();
+        let key_chain = router.lock().unwrap().get_key_chain(chain);
+        let removed_key = key_chain.lock().unwrap().remove_key(key_id);
+        match removed_key {
+            Some(_) => println!("Key {} removed from key chain {}", key_id, chain),
+            None => println!("No key {} in key chain {}", key_id, chain),
+        }
+    } else if args_match.subcommand_matches("list").is_some() {
+        let key_chains = router.lock().unwrap().get_key_chains();
+        println!("Chain\tKey ID\tSend\t\tAccept");
+        for key_chain in key_chains {
+            let key_chain = key_chain.lock().unwrap();
+            for key in key_chain.get_keys() {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    key_chain.name,
+                    key.key_id,
+                    format_lifetime(&key.send_lifetime),
+                    format_lifetime(&key.accept_lifetime)
+                );
+            }
+        }
+    } else {
+        KEY_CHAIN_COMMAND
+            .clone()
+            .print_help()
+            .expect("print key chain command help failed");
+    }
+}
+
+pub(super) fn cli(router: Arc<Mutex<router::Router>>) -> Result<(), Box<dyn std::error::Error>> {
+    let router_id = router.lock().unwrap().get_router_id();
     let cmdline_config = Config::builder()
         .history_ignore_space(true)
         .completion_type(CompletionType::List)
@@ -85,7 +270,7 @@ pub(super) fn cli(router_id: net::Ipv4Addr) -> Result<(), Box<dyn std::error::Er
         let readline = cmdline_editor.readline(&format!("{}>>", router_id));
         if let Ok(line) = readline {
             cmdline_editor.add_history_entry(line.as_str())?;
-            match_ospf_command(&line);
+            match_ospf_command(&line, &router);
         } else {
             println!("Bye");
             break;
@@ -1,3 +1,5 @@
+use std::net::Ipv4Addr;
+
 pub struct RouterNotSetError;
 
 impl std::error::Error for RouterNotSetError {}
@@ -13,3 +15,258 @@ impl std::fmt::Debug for RouterNotSetError {
         write!(f, "Router not set")
     }
 }
+
+/// # enum OspfDecodeError
+/// the reason why a packet or lsa received from the wire could not be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OspfDecodeError {
+    /// the buffer is shorter than the fixed part of the structure.
+    Truncated {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// the length field is smaller than the fixed part or larger than the buffer.
+    BadLengthField {
+        what: &'static str,
+        length: usize,
+        available: usize,
+    },
+    BadVersion(u8),
+    UnknownPacketType(u8),
+    UnknownLsaType(u8),
+    /// the variable part is not a whole number of entries.
+    LengthMismatch {
+        what: &'static str,
+        length: usize,
+        entry_size: usize,
+    },
+    /// the advertised number of entries does not match what was decoded.
+    CountMismatch {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    NotOspf,
+}
+
+impl OspfDecodeError {
+    /// make sure that `data` holds at least `expected` bytes before indexing it.
+    pub fn ensure_length(
+        what: &'static str,
+        data: &[u8],
+        expected: usize,
+    ) -> Result<(), OspfDecodeError> {
+        if data.len() < expected {
+            return Err(OspfDecodeError::Truncated {
+                what,
+                expected,
+                actual: data.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for OspfDecodeError {}
+
+impl std::fmt::Display for OspfDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OspfDecodeError::Truncated {
+                what,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} truncated: expected at least {} bytes, got {}",
+                what, expected, actual
+            ),
+            OspfDecodeError::BadLengthField {
+                what,
+                length,
+                available,
+            } => write!(
+                f,
+                "{} has bad length field {} ({} bytes available)",
+                what, length, available
+            ),
+            OspfDecodeError::BadVersion(version) => write!(f, "bad ospf version {}", version),
+            OspfDecodeError::UnknownPacketType(packet_type) => {
+                write!(f, "unknown ospf packet type {}", packet_type)
+            }
+            OspfDecodeError::UnknownLsaType(lsa_type) => write!(f, "unknown lsa type {}", lsa_type),
+            OspfDecodeError::LengthMismatch {
+                what,
+                length,
+                entry_size,
+            } => write!(
+                f,
+                "{} variable part of {} bytes is not a multiple of {}",
+                what, length, entry_size
+            ),
+            OspfDecodeError::CountMismatch {
+                what,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} advertises {} entries but {} were decoded",
+                what, expected, actual
+            ),
+            OspfDecodeError::NotOspf => write!(f, "not an ospf packet"),
+        }
+    }
+}
+
+/// # enum AuthenticationError
+/// the reason why a received packet failed authentication and was dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuthenticationError {
+    UnsupportedType(u16),
+    TypeMismatch {
+        expected: u16,
+        actual: u16,
+    },
+    KeyMismatch,
+    UnknownKeyId(u8),
+    /// the key chain holds no key to sign the outgoing packet with.
+    NoSendKey,
+    DigestMismatch,
+    /// the cryptographic sequence number went backwards.
+    Replay {
+        last: u32,
+        received: u32,
+    },
+    Malformed,
+}
+
+impl std::error::Error for AuthenticationError {}
+
+impl std::fmt::Display for AuthenticationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AuthenticationError::UnsupportedType(auth_type) => {
+                write!(f, "unsupported authentication type {}", auth_type)
+            }
+            AuthenticationError::TypeMismatch { expected, actual } => write!(
+                f,
+                "authentication type mismatch: expected {}, got {}",
+                expected, actual
+            ),
+            AuthenticationError::KeyMismatch => write!(f, "authentication key mismatch"),
+            AuthenticationError::UnknownKeyId(key_id) => write!(f, "unknown key id {}", key_id),
+            AuthenticationError::NoSendKey => write!(f, "no key valid for sending"),
+            AuthenticationError::DigestMismatch => write!(f, "message digest mismatch"),
+            AuthenticationError::Replay { last, received } => write!(
+                f,
+                "cryptographic sequence number {} is older than {}",
+                received, last
+            ),
+            AuthenticationError::Malformed => write!(f, "malformed packet"),
+        }
+    }
+}
+
+/// # enum IngressError
+/// the reason why a packet received on an interface was not meant for it (RFC 2328 8.2).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IngressError {
+    /// the destination is neither AllSPFRouters, AllDRouters nor the interface address.
+    WrongDestination(Ipv4Addr),
+    /// sent to AllDRouters while the interface is neither the DR nor the backup DR.
+    NotDesignatedRouter,
+    SourceNotOnSubnet(Ipv4Addr),
+    /// the packet was sent by ourselves.
+    OwnPacket,
+}
+
+impl std::error::Error for IngressError {}
+
+impl std::fmt::Display for IngressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            IngressError::WrongDestination(destination) => {
+                write!(f, "destination {} is not for this interface", destination)
+            }
+            IngressError::NotDesignatedRouter => {
+                write!(f, "sent to AllDRouters but the interface is not DR or BDR")
+            }
+            IngressError::SourceNotOnSubnet(source) => {
+                write!(f, "source {} is not on the interface subnet", source)
+            }
+            IngressError::OwnPacket => write!(f, "packet sent by ourselves"),
+        }
+    }
+}
+
+/// # enum HelloError
+/// the reason why a received hello was dropped, its parameters not matching
+/// the configuration of the receiving interface (RFC 2328 10.5).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HelloError {
+    AreaMismatch(Ipv4Addr),
+    NetworkMaskMismatch(Ipv4Addr),
+    HelloIntervalMismatch(u16),
+    RouterDeadIntervalMismatch(u32),
+    /// the E-bit of the options does not match the external routing capability of the area.
+    ExternalRoutingMismatch(u8),
+}
+
+impl std::error::Error for HelloError {}
+
+impl std::fmt::Display for HelloError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HelloError::AreaMismatch(area_id) => {
+                write!(f, "area {} is not the area of the interface", area_id)
+            }
+            HelloError::NetworkMaskMismatch(network_mask) => {
+                write!(f, "network mask {} does not match", network_mask)
+            }
+            HelloError::HelloIntervalMismatch(hello_interval) => {
+                write!(f, "hello interval {} does not match", hello_interval)
+            }
+            HelloError::RouterDeadIntervalMismatch(router_dead_interval) => {
+                write!(
+                    f,
+                    "router dead interval {} does not match",
+                    router_dead_interval
+                )
+            }
+            HelloError::ExternalRoutingMismatch(options) => {
+                write!(f, "options {:#04x} disagree on the E-bit", options)
+            }
+        }
+    }
+}
+
+/// # enum ExchangeError
+/// the reason why a received DD, LSR, LSU or LSAck packet was rejected (RFC 2328 10.6, 10.7, 13).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExchangeError {
+    /// no neighbor is known for the sender of the packet.
+    UnknownNeighbor(Ipv4Addr),
+    /// the sender's interface mtu is larger than what we can receive.
+    MtuMismatch(u16),
+    /// the neighbor is not far enough in the database exchange to send the packet.
+    NeighborNotReady,
+}
+
+impl std::error::Error for ExchangeError {}
+
+impl std::fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExchangeError::UnknownNeighbor(source) => {
+                write!(f, "no neighbor known at {}", source)
+            }
+            ExchangeError::MtuMismatch(mtu) => {
+                write!(f, "interface mtu {} is too large", mtu)
+            }
+            ExchangeError::NeighborNotReady => {
+                write!(f, "the neighbor is not exchanging its database")
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    cmp::Ordering,
+    net,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    error::ExchangeError,
+    interface::{handle, Interface},
+    lsa::LinkStateAdvertisementHeader,
+    lsdb::LinkStateAdvertisementKey,
+    neighbor::status::NeighborStatus,
+    packet::lsack::{LinkStateAcknowledgementPacket, LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE},
+    MTU,
+};
+
+/// the most lsa headers put in one LSAck packet, once the ip header, the ospf header
+/// and the longest authentication digest are taken out of the mtu.
+pub const MAX_LSACK_HEADERS: usize = (MTU - 20 - 24 - 64) / 20;
+
+/// how long the delayed acks of an interface wait to be bundled, shorter than
+/// RxmtInterval so the neighbors do not retransmit in the meantime (RFC 2328 13.5).
+pub fn get_delayed_ack_interval(rxmt_interval: u32) -> u32 {
+    (rxmt_interval / 2).max(1)
+}
+
+/// acknowledge the lsas of `lsa_headers` to `destination` in as many LSAck packets
+/// as the mtu requires.
+pub(crate) fn send_lsack_packets(
+    locked_interface: &Interface,
+    destination: net::Ipv4Addr,
+    lsa_headers: &[LinkStateAdvertisementHeader],
+) {
+    for lsa_headers in lsa_headers.chunks(MAX_LSACK_HEADERS) {
+        let mut lsack_packet = LinkStateAcknowledgementPacket::new(
+            locked_interface.new_packet_header(LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE),
+            lsa_headers.iter().copied().map(Box::new).collect(),
+        );
+        match locked_interface.create_ip_packet(destination, &mut lsack_packet) {
+            Ok(ip_packet) => {
+                if !locked_interface.send_ip_packet(ip_packet) {
+                    crate::error("send lsack packet failed.");
+                }
+            }
+            Err(e) => crate::error(&format!("create lsack packet failed: {}.", e)),
+        }
+    }
+}
+
+/// queue the ack of the lsa described by `lsa_header` on the interface, the queued acks
+/// are sent together when the delayed ack timer fires.
+pub(crate) fn add_delayed_ack(
+    interface: &Arc<Mutex<Interface>>,
+    locked_interface: &mut Interface,
+    lsa_header: LinkStateAdvertisementHeader,
+) {
+    locked_interface.delayed_acks.push(lsa_header);
+    if locked_interface
+        .delayed_ack_handle
+        .as_ref()
+        .is_none_or(|handle| handle.is_finished())
+    {
+        locked_interface.delayed_ack_handle = Some(tokio::spawn(handle::delayed_ack_raw_handle(
+            interface.clone(),
+            get_delayed_ack_interval(locked_interface.rxmt_interval),
+        )));
+    }
+}
+
+/// send the acks queued on the interface (RFC 2328 13.5), multicast where the network
+/// allows it, else directly to each neighbor in Exchange or a later state.
+pub(crate) fn send_delayed_acks(locked_interface: &mut Interface) {
+    let lsa_headers = std::mem::take(&mut locked_interface.delayed_acks);
+    if lsa_headers.is_empty() {
+        return;
+    }
+    let destinations = match super::get_multicast_destination(locked_interface) {
+        Some(destination) => vec![destination],
+        None => locked_interface
+            .neighbors
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|neighbor| neighbor.status >= NeighborStatus::Exchange)
+            .map(|neighbor| locked_interface.get_neighbor_destination(neighbor))
+            .collect(),
+    };
+    for destination in destinations {
+        send_lsack_packets(locked_interface, destination, &lsa_headers);
+    }
+}
+
+/// the receive procedure of an LSAck packet sent by `source` on the interface (RFC 2328 13.7).
+/// each acknowledged instance is taken off the retransmission list of the neighbor,
+/// an ack for another instance than the one listed is questionable and ignored.
+pub fn handle_lsack_packet(
+    interface: &Arc<Mutex<Interface>>,
+    source: net::Ipv4Addr,
+    lsack_packet: &LinkStateAcknowledgementPacket,
+) -> Result<(), ExchangeError> {
+    let locked_interface = interface.lock().unwrap();
+    let router_id = net::Ipv4Addr::from(lsack_packet.header.router_id);
+    let neighbor_key = locked_interface.get_neighbor_key(source, router_id);
+    let mut locked_neighbors = locked_interface.neighbors.lock().unwrap();
+    let neighbor = locked_neighbors
+        .get_mut(&neighbor_key)
+        .ok_or(ExchangeError::UnknownNeighbor(source))?;
+    if neighbor.status < NeighborStatus::Exchange {
+        return Err(ExchangeError::NeighborNotReady);
+    }
+    for lsa_header in &lsack_packet.lsa_headers {
+        let index = match neighbor
+            .link_state_retransmission_list
+            .iter()
+            .position(|retransmission| retransmission.is_same_lsa(lsa_header))
+        {
+            Some(index) => index,
+            None => continue,
+        };
+        if lsa_header.compare(&neighbor.link_state_retransmission_list[index]) == Ordering::Equal {
+            neighbor.link_state_retransmission_list.remove(index);
+        } else {
+            crate::debug(&format!(
+                "neighbor {} sent a questionable ack for lsa {}.",
+                neighbor_key,
+                LinkStateAdvertisementKey::from_header(lsa_header)
+            ));
+        }
+    }
+    if neighbor.link_state_retransmission_list.is_empty() {
+        if let Some(handle) = neighbor.lsu_retransmission_timer.take() {
+            handle.abort();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{sync::broadcast, time};
+
+    use super::*;
+    use crate::{
+        flooding,
+        interface::{status::InterfaceStatus, InterfaceNetworkType, DEFAULT_RXMT_INTERVAL},
+        packet::{lsu::LINK_STATE_UPDATE_TYPE, OspfPacketHeader},
+        testing, AllDRouters, AllSPFRouters, OSPF_VERSION_2,
+    };
+
+    const IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 1);
+    const ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(1, 1, 1, 1);
+    const NEIGHBOR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 2);
+    const NEIGHBOR_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(2, 2, 2, 2);
+    const ADVERTISING_ROUTER: net::Ipv4Addr = net::Ipv4Addr::new(9, 9, 9, 9);
+
+    fn lsack_packet(
+        lsa_headers: Vec<LinkStateAdvertisementHeader>,
+    ) -> LinkStateAcknowledgementPacket {
+        LinkStateAcknowledgementPacket::new(
+            OspfPacketHeader::new(
+                OSPF_VERSION_2,
+                LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE,
+                0,
+                NEIGHBOR_ROUTER_ID.into(),
+                0,
+                0,
+                0,
+                0,
+            ),
+            lsa_headers.into_iter().map(Box::new).collect(),
+        )
+    }
+
+    /// a full neighbor to which an lsa of the database was flooded, returns its header.
+    fn flooded_interface(
+        status: InterfaceStatus,
+    ) -> (
+        Arc<Mutex<Interface>>,
+        broadcast::Receiver<bytes::Bytes>,
+        LinkStateAdvertisementHeader,
+    ) {
+        let (interface, send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        interface.lock().unwrap().status = status;
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        let lsa_header = *lsa.get_header();
+        let area = interface.lock().unwrap().area.clone().unwrap();
+        let interfaces = vec![interface.clone()];
+        flooding::flood_lsa(
+            &interfaces,
+            lsa.as_ref(),
+            flooding::FloodingScope::Area(net::Ipv4Addr::UNSPECIFIED),
+            None,
+        );
+        area.lock().unwrap().install_lsa(lsa);
+        (interface, send_packet_rx, lsa_header)
+    }
+
+    fn retransmissions(interface: &Arc<Mutex<Interface>>) -> Vec<LinkStateAdvertisementHeader> {
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        let locked_neighbors = neighbors.lock().unwrap();
+        locked_neighbors[&NEIGHBOR_IP_ADDR]
+            .link_state_retransmission_list
+            .clone()
+    }
+
+    fn has_retransmission_timer(interface: &Arc<Mutex<Interface>>) -> bool {
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        let has_timer = neighbors.lock().unwrap()[&NEIGHBOR_IP_ADDR]
+            .lsu_retransmission_timer
+            .is_some();
+        has_timer
+    }
+
+    /// the destinations of the packets of `packet_type` sent since the last call.
+    fn destinations(
+        send_packet_rx: &mut broadcast::Receiver<bytes::Bytes>,
+        packet_type: u8,
+    ) -> Vec<net::Ipv4Addr> {
+        testing::sent_packets(send_packet_rx)
+            .into_iter()
+            .filter(|(_, packet)| packet.get_type() == packet_type)
+            .map(|(destination, _)| destination)
+            .collect()
+    }
+
+    fn rxmt_interval() -> time::Duration {
+        time::Duration::from_secs(DEFAULT_RXMT_INTERVAL as u64) + time::Duration::from_millis(1)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ack_of_the_listed_instance_ends_the_retransmissions() {
+        let (interface, mut send_packet_rx, lsa_header) = flooded_interface(InterfaceStatus::DR);
+        destinations(&mut send_packet_rx, LINK_STATE_UPDATE_TYPE);
+        assert!(has_retransmission_timer(&interface));
+        // the age is not part of the instance.
+        let mut ack = lsa_header;
+        ack.age += 1;
+        handle_lsack_packet(&interface, NEIGHBOR_IP_ADDR, &lsack_packet(vec![ack])).unwrap();
+        assert!(retransmissions(&interface).is_empty());
+        assert!(!has_retransmission_timer(&interface));
+        time::sleep(rxmt_interval()).await;
+        assert!(destinations(&mut send_packet_rx, LINK_STATE_UPDATE_TYPE).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn questionable_ack_keeps_the_lsa_listed() {
+        let (interface, mut send_packet_rx, lsa_header) = flooded_interface(InterfaceStatus::DR);
+        destinations(&mut send_packet_rx, LINK_STATE_UPDATE_TYPE);
+        let mut ack = lsa_header;
+        ack.sequence_number -= 1;
+        handle_lsack_packet(&interface, NEIGHBOR_IP_ADDR, &lsack_packet(vec![ack])).unwrap();
+        assert_eq!(retransmissions(&interface), vec![lsa_header]);
+        assert!(has_retransmission_timer(&interface));
+        time::sleep(rxmt_interval()).await;
+        assert_eq!(
+            destinations(&mut send_packet_rx, LINK_STATE_UPDATE_TYPE),
+            vec![NEIGHBOR_IP_ADDR]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ack_from_a_neighbor_not_exchanging_is_dropped() {
+        let (interface, _send_packet_rx, lsa_header) = flooded_interface(InterfaceStatus::DR);
+        {
+            let neighbors = interface.lock().unwrap().get_neighbors();
+            neighbors
+                .lock()
+                .unwrap()
+                .get_mut(&NEIGHBOR_IP_ADDR)
+                .unwrap()
+                .status = NeighborStatus::ExStart;
+        }
+        assert_eq!(
+            handle_lsack_packet(
+                &interface,
+                NEIGHBOR_IP_ADDR,
+                &lsack_packet(vec![lsa_header])
+            ),
+            Err(ExchangeError::NeighborNotReady)
+        );
+        assert_eq!(retransmissions(&interface), vec![lsa_header]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn delayed_acks_are_multicast_by_role() {
+        for (status, destination) in [
+            (InterfaceStatus::DRother, AllDRouters),
+            (InterfaceStatus::DR, AllSPFRouters),
+            (InterfaceStatus::Backup, AllSPFRouters),
+        ] {
+            let (interface, mut send_packet_rx) =
+                testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+            interface.lock().unwrap().status = status;
+            let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+            {
+                let mut locked_interface = interface.lock().unwrap();
+                add_delayed_ack(&interface, &mut locked_interface, *lsa.get_header());
+                add_delayed_ack(&interface, &mut locked_interface, *lsa.get_header());
+            }
+            let delay = get_delayed_ack_interval(DEFAULT_RXMT_INTERVAL) as u64;
+            time::sleep(time::Duration::from_secs(delay) - time::Duration::from_millis(1)).await;
+            assert!(
+                destinations(&mut send_packet_rx, LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE)
+                    .is_empty()
+            );
+            time::sleep(time::Duration::from_millis(2)).await;
+            // the queued acks are bundled in one packet.
+            assert_eq!(
+                destinations(&mut send_packet_rx, LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE),
+                vec![destination],
+                "{:?}",
+                status
+            );
+            assert!(interface.lock().unwrap().delayed_acks.is_empty());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn delayed_acks_go_to_each_adjacent_neighbor_without_multicast() {
+        let (interface, mut send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::NBMA);
+        interface.lock().unwrap().status = InterfaceStatus::DR;
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Exchange,
+        );
+        testing::add_neighbor(
+            &interface,
+            net::Ipv4Addr::new(192, 0, 2, 3),
+            net::Ipv4Addr::new(3, 3, 3, 3),
+            NeighborStatus::TwoWay,
+        );
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        send_delayed_acks(&mut interface.lock().unwrap());
+        assert!(
+            destinations(&mut send_packet_rx, LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE).is_empty()
+        );
+        interface
+            .lock()
+            .unwrap()
+            .delayed_acks
+            .push(*lsa.get_header());
+        send_delayed_acks(&mut interface.lock().unwrap());
+        assert_eq!(
+            destinations(&mut send_packet_rx, LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE),
+            vec![NEIGHBOR_IP_ADDR]
+        );
+    }
+}
@@ -0,0 +1,837 @@
+use std::{
+    cmp::Ordering,
+    net,
+    sync::{Arc, Mutex},
+};
+
+use tokio::time::Duration;
+
+use crate::{
+    area::{origination, Area},
+    error::ExchangeError,
+    interface::{status::InterfaceStatus, Interface, InterfaceNetworkType},
+    lsa::{
+        self, as_external::AS_EXTERNAL_LINK_STATE_TYPE, network::NETWORK_LINK_STATE_TYPE,
+        router::ROUTER_LINK_STATE_TYPE, LinkStateAdvertisement, LinkStateAdvertisementHeader,
+        MaxAge, MaxSequenceNumber, MinLSArrival,
+    },
+    lsdb::LinkStateAdvertisementKey,
+    neighbor::{self, event::NeighborEvent, request, status::NeighborStatus},
+    packet::lsu::LinkStateUpdatePacket,
+    AllDRouters, AllSPFRouters, OPTION_BIT_E,
+};
+
+pub mod ack;
+
+/// # enum FloodingScope
+/// the interfaces an lsa is flooded out of (RFC 2328 13.3).
+/// - Area : the interfaces of the area, for every lsa but the as-external-lsas.
+/// - AutonomousSystem : the interfaces of every area but the stub areas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloodingScope {
+    Area(net::Ipv4Addr),
+    AutonomousSystem,
+}
+
+impl FloodingScope {
+    pub fn of(lsa_header: &LinkStateAdvertisementHeader, area_id: net::Ipv4Addr) -> Self {
+        match lsa_header.lsa_type {
+            AS_EXTERNAL_LINK_STATE_TYPE => FloodingScope::AutonomousSystem,
+            _ => FloodingScope::Area(area_id),
+        }
+    }
+
+    pub fn contains(&self, locked_interface: &Interface) -> bool {
+        match self {
+            FloodingScope::Area(area_id) => locked_interface.aread_id == *area_id,
+            FloodingScope::AutonomousSystem => locked_interface.get_options() & OPTION_BIT_E != 0,
+        }
+    }
+}
+
+/// where the lsas and the delayed acks sent out of the interface go (RFC 2328 13.3, 13.5):
+/// AllSPFRouters on point-to-point networks and from the DR and the backup DR,
+/// AllDRouters from the other routers of a broadcast network. none on the networks
+/// without multicast, each adjacent neighbor is sent to directly there.
+pub(crate) fn get_multicast_destination(locked_interface: &Interface) -> Option<net::Ipv4Addr> {
+    match locked_interface.network_type {
+        InterfaceNetworkType::PointToPoint => Some(AllSPFRouters),
+        InterfaceNetworkType::Broadcast => match locked_interface.status {
+            InterfaceStatus::DR | InterfaceStatus::Backup => Some(AllSPFRouters),
+            _ => Some(AllDRouters),
+        },
+        _ => None,
+    }
+}
+
+/// whether the lsa described by `lsa_header` was originated by the router itself (RFC 2328 13.4):
+/// it is advertised by our router id, or it is a network-lsa of one of our interface addresses.
+fn is_self_originated(
+    locked_interface: &Interface,
+    lsa_header: &LinkStateAdvertisementHeader,
+) -> bool {
+    lsa_header.advertising_router == u32::from(locked_interface.router_id)
+        || (lsa_header.lsa_type == NETWORK_LINK_STATE_TYPE
+            && lsa_header.link_state_id == u32::from(locked_interface.ip_addr))
+}
+
+/// whether the instance installed at `installed_at` is younger than MinLSArrival,
+/// a newer instance of it must not be accepted yet.
+fn is_within_min_ls_arrival(installed_at: Option<tokio::time::Instant>) -> bool {
+    installed_at.is_some_and(|installed_at| {
+        installed_at.elapsed() < Duration::from_secs(MinLSArrival as u64)
+    })
+}
+
+/// whether a neighbor of any of `interfaces` is exchanging its database, a MaxAge lsa we do
+/// not have is then installed and flooded rather than just acknowledged (RFC 2328 13 (4)).
+/// the interfaces are locked one after the other, none may be locked by the caller.
+fn is_any_neighbor_exchanging(interfaces: &[Arc<Mutex<Interface>>]) -> bool {
+    interfaces.iter().any(|interface| {
+        interface
+            .lock()
+            .unwrap()
+            .neighbors
+            .lock()
+            .unwrap()
+            .values()
+            .any(|neighbor| {
+                matches!(
+                    neighbor.status,
+                    NeighborStatus::Exchange | NeighborStatus::Loading
+                )
+            })
+    })
+}
+
+/// the receive procedure of an LSU packet sent by `source` on the interface (RFC 2328 13).
+/// the lsas newer than ours are installed in the database and flooded out of the interfaces
+/// of their scope, an instance older than ours is answered with ours. a self-originated lsa
+/// received back is either originated again or flushed.
+pub fn handle_lsu_packet(
+    interface: &Arc<Mutex<Interface>>,
+    source: net::Ipv4Addr,
+    lsu_packet: &LinkStateUpdatePacket,
+) -> Result<(), ExchangeError> {
+    let router = interface.lock().unwrap().router.clone();
+    let interfaces = router.lock().unwrap().get_interfaces();
+    let is_any_neighbor_exchanging = is_any_neighbor_exchanging(&interfaces);
+    let mut locked_interface = interface.lock().unwrap();
+    let router_id = net::Ipv4Addr::from(lsu_packet.header.router_id);
+    let neighbor_key = locked_interface.get_neighbor_key(source, router_id);
+    let neighbors = locked_interface.neighbors.clone();
+    let locked_neighbors = neighbors.lock().unwrap();
+    let neighbor = locked_neighbors
+        .get(&neighbor_key)
+        .ok_or(ExchangeError::UnknownNeighbor(source))?;
+    if neighbor.status < NeighborStatus::Exchange {
+        return Err(ExchangeError::NeighborNotReady);
+    }
+    let destination = locked_interface.get_neighbor_destination(neighbor);
+    let is_from_designated_router = neighbor.ipv4_addr == locked_interface.designated_router;
+    let is_backup = locked_interface.status == InterfaceStatus::Backup;
+    drop(locked_neighbors);
+    let area = match locked_interface.area.clone() {
+        Some(area) => area,
+        None => return Ok(()),
+    };
+    let area_id = locked_interface.aread_id;
+    let external_routing_capable = area.lock().unwrap().is_external_routing_capable();
+    let mut installed_lsas = Vec::new();
+    let mut older_lsas = Vec::new();
+    let mut direct_acks = Vec::new();
+    let mut our_network_lsas = Vec::new();
+    for lsa in &lsu_packet.lsas {
+        let lsa_header = *lsa.get_header();
+        if !lsa::is_lsa_type_known(lsa_header.lsa_type, external_routing_capable) {
+            continue;
+        }
+        let key = LinkStateAdvertisementKey::from_header(&lsa_header);
+        let locked_area = area.lock().unwrap();
+        let database_header = locked_area.get_lsa_header(&key);
+        let installed_at = locked_area.get_lsa_installed_at(&key);
+        drop(locked_area);
+        if lsa_header.age as u32 >= MaxAge
+            && database_header.is_none()
+            && !is_any_neighbor_exchanging
+        {
+            direct_acks.push(lsa_header);
+            continue;
+        }
+        match database_header.map(|database_header| lsa_header.compare(&database_header)) {
+            None | Some(Ordering::Greater) => {
+                if is_within_min_ls_arrival(installed_at) {
+                    continue;
+                }
+                let mut lsa = match lsa::copy_lsa(lsa.as_ref()) {
+                    Some(lsa) => lsa,
+                    None => continue,
+                };
+                let is_self_originated = is_self_originated(&locked_interface, &lsa_header);
+                // our network-lsa is originated again by its interface, or flushed,
+                // once it was flooded.
+                let is_our_network_lsa = lsa_header.lsa_type == NETWORK_LINK_STATE_TYPE
+                    && lsa_header.advertising_router == u32::from(locked_interface.router_id);
+                if is_our_network_lsa {
+                    our_network_lsas.push(key);
+                } else if is_self_originated && lsa_header.lsa_type != ROUTER_LINK_STATE_TYPE {
+                    lsa.get_header_mut().age = MaxAge as u16;
+                }
+                let mut locked_area = area.lock().unwrap();
+                let lsa_copy = lsa::copy_lsa(lsa.as_ref());
+                locked_area.install_lsa(lsa);
+                if is_self_originated && lsa_header.lsa_type == ROUTER_LINK_STATE_TYPE {
+                    crate::log(&format!(
+                        "received newer instance of our router-lsa from {}, originating it again.",
+                        neighbor_key
+                    ));
+                    locked_area.trigger_router_lsa_origination();
+                }
+                drop(locked_area);
+                if let Some(lsa_copy) = lsa_copy {
+                    installed_lsas.push((lsa_header, lsa_copy));
+                }
+            }
+            Some(ordering) => {
+                let neighbors = locked_interface.neighbors.clone();
+                let mut locked_neighbors = neighbors.lock().unwrap();
+                let neighbor = match locked_neighbors.get_mut(&neighbor_key) {
+                    Some(neighbor) => neighbor,
+                    None => break,
+                };
+                if neighbor
+                    .link_state_request_list
+                    .iter()
+                    .any(|request| request.is_same_lsa(&lsa_header))
+                {
+                    drop(locked_neighbors);
+                    crate::error(&format!(
+                        "neighbor {} answered our request for lsa {} with an instance not newer than ours.",
+                        neighbor_key, key
+                    ));
+                    neighbor::apply_event(
+                        interface,
+                        &mut locked_interface,
+                        neighbor_key,
+                        NeighborEvent::BadLSReq,
+                    );
+                    // the rest of the packet is dropped, the lsas installed from it
+                    // are still flooded and acknowledged below.
+                    break;
+                }
+                if ordering == Ordering::Equal {
+                    // a duplicate on the retransmission list is an implied ack.
+                    if !neighbor.remove_link_state_retransmission(&lsa_header) {
+                        direct_acks.push(lsa_header);
+                    } else if is_backup && is_from_designated_router {
+                        drop(locked_neighbors);
+                        ack::add_delayed_ack(interface, &mut locked_interface, lsa_header);
+                    }
+                    continue;
+                }
+                let database_header = database_header.unwrap();
+                if database_header.age as u32 >= MaxAge
+                    && database_header.sequence_number == MaxSequenceNumber
+                {
+                    continue;
+                }
+                if !is_within_min_ls_arrival(installed_at) {
+                    if let Some(lsa) = locked_interface.get_lsa(&key) {
+                        older_lsas.push(lsa);
+                    }
+                }
+            }
+        }
+    }
+    if !direct_acks.is_empty() {
+        ack::send_lsack_packets(&locked_interface, destination, &direct_acks);
+    }
+    if !older_lsas.is_empty() {
+        request::send_lsu_packets(&locked_interface, destination, older_lsas);
+    }
+    let installed_headers: Vec<_> = installed_lsas
+        .iter()
+        .map(|(lsa_header, _)| *lsa_header)
+        .collect();
+    request::handle_requested_lsas(
+        interface,
+        &mut locked_interface,
+        neighbor_key,
+        &installed_headers,
+    );
+    drop(locked_interface);
+    let mut delayed_acks = Vec::new();
+    for (lsa_header, lsa) in &installed_lsas {
+        let scope = FloodingScope::of(lsa.get_header(), area_id);
+        let flooded_back = flood_lsa(
+            &interfaces,
+            lsa.as_ref(),
+            scope,
+            Some((interface, neighbor_key)),
+        );
+        if !flooded_back && (!is_backup || is_from_designated_router) {
+            delayed_acks.push(*lsa_header);
+        }
+    }
+    for key in &our_network_lsas {
+        origination::handle_received_network_lsa(&interfaces, &area, key);
+    }
+    if !delayed_acks.is_empty() {
+        let mut locked_interface = interface.lock().unwrap();
+        for lsa_header in delayed_acks {
+            ack::add_delayed_ack(interface, &mut locked_interface, lsa_header);
+        }
+    }
+    Ok(())
+}
+
+/// withdraw the self-originated lsa `key` of `area` by aging it prematurely (RFC 2328 14.1):
+/// its age is set to MaxAge and it is flooded out of `interfaces`, the aging timer removes it
+/// from the database once every neighbor acknowledged it. returns whether the lsa was found.
+pub fn flush_lsa(
+    interfaces: &[Arc<Mutex<Interface>>],
+    area: &Arc<Mutex<Area>>,
+    key: &LinkStateAdvertisementKey,
+) -> bool {
+    let mut locked_area = area.lock().unwrap();
+    let area_id = locked_area.get_area_id();
+    let lsa = match locked_area.premature_age_lsa(key) {
+        Some(lsa) => lsa,
+        None => return false,
+    };
+    drop(locked_area);
+    crate::log(&format!("flushing lsa {} from area {}.", key, area_id));
+    let scope = FloodingScope::of(lsa.get_header(), area_id);
+    flood_lsa(interfaces, lsa.as_ref(), scope, None);
+    true
+}
+
+/// flood `lsa` out of the interfaces of `interfaces` in `scope` (RFC 2328 13.3),
+/// `origin` is the interface and the neighbor it was received from, none when we originated it.
+/// the interfaces are locked one after the other, none may be locked by the caller.
+/// returns whether the lsa was flooded back out of the receiving interface.
+pub fn flood_lsa(
+    interfaces: &[Arc<Mutex<Interface>>],
+    lsa: &(dyn LinkStateAdvertisement + Send),
+    scope: FloodingScope,
+    origin: Option<(&Arc<Mutex<Interface>>, net::Ipv4Addr)>,
+) -> bool {
+    let mut flooded_back = false;
+    for flooding_interface in interfaces {
+        let mut locked_interface = flooding_interface.lock().unwrap();
+        if !scope.contains(&locked_interface) {
+            continue;
+        }
+        let received_from = origin
+            .filter(|(interface, _)| Arc::ptr_eq(interface, flooding_interface))
+            .map(|(_, neighbor_key)| neighbor_key);
+        let flooded = flood_lsa_out_of_interface(
+            flooding_interface,
+            &mut locked_interface,
+            lsa,
+            received_from,
+        );
+        flooded_back |= flooded && received_from.is_some();
+    }
+    flooded_back
+}
+
+/// flood `lsa` out of one interface, `received_from` is the neighbor it was received from
+/// when the interface is the receiving one. the lsa is put on the retransmission list of
+/// each neighbor that should get it, then sent once to all of them.
+/// returns whether the lsa was sent out of the interface.
+pub(crate) fn flood_lsa_out_of_interface(
+    interface: &Arc<Mutex<Interface>>,
+    locked_interface: &mut Interface,
+    lsa: &(dyn LinkStateAdvertisement + Send),
+    received_from: Option<net::Ipv4Addr>,
+) -> bool {
+    let lsa_header = *lsa.get_header();
+    let neighbors = locked_interface.neighbors.clone();
+    let mut locked_neighbors = neighbors.lock().unwrap();
+    let mut loading_done = Vec::new();
+    let mut destinations = Vec::new();
+    let mut sender_addr = None;
+    for (neighbor_key, neighbor) in locked_neighbors.iter_mut() {
+        neighbor.remove_link_state_retransmission(&lsa_header);
+        if Some(*neighbor_key) == received_from {
+            sender_addr = Some(neighbor.ipv4_addr);
+        }
+        if neighbor.status < NeighborStatus::Exchange {
+            continue;
+        }
+        if neighbor.status != NeighborStatus::Full {
+            if let Some(request) = neighbor
+                .link_state_request_list
+                .iter()
+                .find(|request| request.is_same_lsa(&lsa_header))
+            {
+                let ordering = lsa_header.compare(request);
+                if neighbor.satisfy_link_state_request(&lsa_header)
+                    && neighbor.status == NeighborStatus::Loading
+                    && neighbor.link_state_request_list.is_empty()
+                {
+                    loading_done.push(*neighbor_key);
+                }
+                if ordering != Ordering::Greater {
+                    continue;
+                }
+            }
+        }
+        if Some(*neighbor_key) == received_from {
+            continue;
+        }
+        neighbor.add_link_state_retransmission(lsa_header);
+        if neighbor
+            .lsu_retransmission_timer
+            .as_ref()
+            .is_none_or(|handle| handle.is_finished())
+        {
+            neighbor.lsu_retransmission_timer = Some(tokio::spawn(
+                neighbor::handle::lsu_retransmission_raw_handle(
+                    interface.clone(),
+                    *neighbor_key,
+                    locked_interface.rxmt_interval,
+                ),
+            ));
+        }
+        destinations.push(locked_interface.get_neighbor_destination(neighbor));
+    }
+    drop(locked_neighbors);
+    for neighbor_key in loading_done {
+        neighbor::apply_event(
+            interface,
+            locked_interface,
+            neighbor_key,
+            NeighborEvent::LoadingDone,
+        );
+    }
+    if destinations.is_empty() {
+        return false;
+    }
+    if received_from.is_some() {
+        let is_from_designated_router = sender_addr.is_some_and(|sender_addr| {
+            sender_addr == locked_interface.designated_router
+                || sender_addr == locked_interface.backup_designated_router
+        });
+        if is_from_designated_router || locked_interface.status == InterfaceStatus::Backup {
+            return false;
+        }
+    }
+    let destinations = match get_multicast_destination(locked_interface) {
+        Some(destination) => vec![destination],
+        None => destinations,
+    };
+    for destination in destinations {
+        if let Some(lsa) = lsa::copy_lsa(lsa) {
+            request::send_lsu_packets(locked_interface, destination, vec![lsa]);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::{
+        packet::{
+            lsack::LinkStateAcknowledgementPacket,
+            lsu::{LinkStateUpdatePacket, LINK_STATE_UPDATE_TYPE},
+            OspfPacketHeader,
+        },
+        testing, OSPF_VERSION_2,
+    };
+
+    const IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 1);
+    const ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(1, 1, 1, 1);
+    const SENDER_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 7);
+    const SENDER_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(7, 7, 7, 7);
+    const FULL_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 3);
+    const FULL_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(3, 3, 3, 3);
+    const ADVERTISING_ROUTER: net::Ipv4Addr = net::Ipv4Addr::new(9, 9, 9, 9);
+
+    fn lsa_header(sequence_number: u32) -> LinkStateAdvertisementHeader {
+        *testing::new_router_lsa(ADVERTISING_ROUTER, 1, sequence_number).get_header()
+    }
+
+    fn retransmissions(
+        interface: &Arc<Mutex<Interface>>,
+        neighbor_key: net::Ipv4Addr,
+    ) -> Vec<LinkStateAdvertisementHeader> {
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        let locked_neighbors = neighbors.lock().unwrap();
+        locked_neighbors[&neighbor_key]
+            .link_state_retransmission_list
+            .clone()
+    }
+
+    fn neighbor_status(
+        interface: &Arc<Mutex<Interface>>,
+        neighbor_key: net::Ipv4Addr,
+    ) -> NeighborStatus {
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        let status = neighbors.lock().unwrap()[&neighbor_key].status;
+        status
+    }
+
+    fn requests(
+        interface: &Arc<Mutex<Interface>>,
+        neighbor_key: net::Ipv4Addr,
+    ) -> Vec<LinkStateAdvertisementHeader> {
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        let locked_neighbors = neighbors.lock().unwrap();
+        locked_neighbors[&neighbor_key]
+            .link_state_request_list
+            .clone()
+    }
+
+    fn add_request(
+        interface: &Arc<Mutex<Interface>>,
+        neighbor_key: net::Ipv4Addr,
+        lsa_header: LinkStateAdvertisementHeader,
+    ) {
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        neighbors
+            .lock()
+            .unwrap()
+            .get_mut(&neighbor_key)
+            .unwrap()
+            .add_link_state_request(lsa_header);
+    }
+
+    /// the destinations of the LSU packets sent since the last call.
+    fn lsu_destinations(
+        send_packet_rx: &mut broadcast::Receiver<bytes::Bytes>,
+    ) -> Vec<net::Ipv4Addr> {
+        testing::sent_packets(send_packet_rx)
+            .into_iter()
+            .filter(|(_, packet)| packet.get_type() == LINK_STATE_UPDATE_TYPE)
+            .map(|(destination, _)| destination)
+            .collect()
+    }
+
+    fn flood(
+        interface: &Arc<Mutex<Interface>>,
+        lsa: &(dyn LinkStateAdvertisement + Send),
+        received_from: Option<net::Ipv4Addr>,
+    ) -> bool {
+        let mut locked_interface = interface.lock().unwrap();
+        flood_lsa_out_of_interface(interface, &mut locked_interface, lsa, received_from)
+    }
+
+    /// a broadcast interface in `status` with a full neighbor and the neighbor
+    /// the lsas are received from.
+    fn broadcast_interface(
+        status: InterfaceStatus,
+    ) -> (Arc<Mutex<Interface>>, broadcast::Receiver<bytes::Bytes>) {
+        let (interface, send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        interface.lock().unwrap().status = status;
+        testing::add_neighbor(
+            &interface,
+            FULL_IP_ADDR,
+            FULL_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        testing::add_neighbor(
+            &interface,
+            SENDER_IP_ADDR,
+            SENDER_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        (interface, send_packet_rx)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn neighbors_get_the_lsa_by_their_state_and_requests() {
+        let (interface, mut send_packet_rx) = broadcast_interface(InterfaceStatus::DRother);
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        let two_way = testing::add_neighbor(
+            &interface,
+            net::Ipv4Addr::new(192, 0, 2, 2),
+            net::Ipv4Addr::new(2, 2, 2, 2),
+            NeighborStatus::TwoWay,
+        );
+        // an older instance requested: the request is satisfied and the lsa sent.
+        let requests_older = testing::add_neighbor(
+            &interface,
+            net::Ipv4Addr::new(192, 0, 2, 4),
+            net::Ipv4Addr::new(4, 4, 4, 4),
+            NeighborStatus::Exchange,
+        );
+        add_request(&interface, requests_older, lsa_header(0x80000004));
+        // a newer instance requested: the request stays and the lsa is not sent.
+        let requests_newer = testing::add_neighbor(
+            &interface,
+            net::Ipv4Addr::new(192, 0, 2, 5),
+            net::Ipv4Addr::new(5, 5, 5, 5),
+            NeighborStatus::Loading,
+        );
+        add_request(&interface, requests_newer, lsa_header(0x80000006));
+        // the same instance requested: the request is satisfied, nothing is sent.
+        let requests_same = testing::add_neighbor(
+            &interface,
+            net::Ipv4Addr::new(192, 0, 2, 6),
+            net::Ipv4Addr::new(6, 6, 6, 6),
+            NeighborStatus::Exchange,
+        );
+        add_request(&interface, requests_same, lsa_header(0x80000005));
+        // the last request satisfied, the neighbor is done loading.
+        let loading = testing::add_neighbor(
+            &interface,
+            net::Ipv4Addr::new(192, 0, 2, 8),
+            net::Ipv4Addr::new(8, 8, 8, 8),
+            NeighborStatus::Loading,
+        );
+        add_request(&interface, loading, lsa_header(0x80000005));
+
+        assert!(flood(&interface, lsa.as_ref(), Some(SENDER_IP_ADDR)));
+        let header = *lsa.get_header();
+        assert!(retransmissions(&interface, two_way).is_empty());
+        assert_eq!(retransmissions(&interface, requests_older), vec![header]);
+        assert!(requests(&interface, requests_older).is_empty());
+        assert!(retransmissions(&interface, requests_newer).is_empty());
+        assert_eq!(requests(&interface, requests_newer).len(), 1);
+        assert!(retransmissions(&interface, requests_same).is_empty());
+        assert!(requests(&interface, requests_same).is_empty());
+        assert!(retransmissions(&interface, loading).is_empty());
+        assert_eq!(neighbor_status(&interface, loading), NeighborStatus::Full);
+        assert_eq!(retransmissions(&interface, FULL_IP_ADDR), vec![header]);
+        // the sender does not get its lsa back.
+        assert!(retransmissions(&interface, SENDER_IP_ADDR).is_empty());
+        // a DR Other floods to the DR and the BDR only.
+        assert_eq!(lsu_destinations(&mut send_packet_rx), vec![AllDRouters]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn newer_instance_replaces_the_one_to_retransmit() {
+        let (interface, mut send_packet_rx) = broadcast_interface(InterfaceStatus::DR);
+        let old_lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        let new_lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000006);
+        assert!(flood(&interface, old_lsa.as_ref(), None));
+        assert!(flood(&interface, new_lsa.as_ref(), None));
+        assert_eq!(
+            retransmissions(&interface, FULL_IP_ADDR),
+            vec![*new_lsa.get_header()]
+        );
+        // the DR floods to every router of the network.
+        assert_eq!(
+            lsu_destinations(&mut send_packet_rx),
+            vec![AllSPFRouters, AllSPFRouters]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn lsa_from_the_dr_or_the_bdr_is_not_sent_back() {
+        for designated_router in [true, false] {
+            let (interface, mut send_packet_rx) = broadcast_interface(InterfaceStatus::DRother);
+            {
+                let mut locked_interface = interface.lock().unwrap();
+                if designated_router {
+                    locked_interface.designated_router = SENDER_IP_ADDR;
+                } else {
+                    locked_interface.backup_designated_router = SENDER_IP_ADDR;
+                }
+            }
+            let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+            assert!(!flood(&interface, lsa.as_ref(), Some(SENDER_IP_ADDR)));
+            // the other neighbors got it from the DR, it is still retransmitted if they don't ack.
+            assert_eq!(
+                retransmissions(&interface, FULL_IP_ADDR),
+                vec![*lsa.get_header()]
+            );
+            assert!(lsu_destinations(&mut send_packet_rx).is_empty());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backup_does_not_send_back_what_it_received() {
+        let (interface, mut send_packet_rx) = broadcast_interface(InterfaceStatus::Backup);
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        assert!(!flood(&interface, lsa.as_ref(), Some(SENDER_IP_ADDR)));
+        assert_eq!(
+            retransmissions(&interface, FULL_IP_ADDR),
+            vec![*lsa.get_header()]
+        );
+        assert!(lsu_destinations(&mut send_packet_rx).is_empty());
+        // an lsa received on another interface is sent by the backup as by anyone.
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000006);
+        assert!(flood(&interface, lsa.as_ref(), None));
+        assert_eq!(lsu_destinations(&mut send_packet_rx), vec![AllSPFRouters]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn lsa_is_sent_to_each_neighbor_without_multicast() {
+        let (interface, mut send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::NBMA);
+        interface.lock().unwrap().status = InterfaceStatus::DR;
+        testing::add_neighbor(
+            &interface,
+            FULL_IP_ADDR,
+            FULL_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        assert!(flood(&interface, lsa.as_ref(), None));
+        assert_eq!(lsu_destinations(&mut send_packet_rx), vec![FULL_IP_ADDR]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn nothing_is_sent_without_a_neighbor_to_send_to() {
+        let (interface, mut send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        interface.lock().unwrap().status = InterfaceStatus::DR;
+        testing::add_neighbor(
+            &interface,
+            SENDER_IP_ADDR,
+            SENDER_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        assert!(!flood(&interface, lsa.as_ref(), Some(SENDER_IP_ADDR)));
+        assert!(lsu_destinations(&mut send_packet_rx).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flooding_stays_in_the_area() {
+        let (interface, _send_packet_rx) = broadcast_interface(InterfaceStatus::DR);
+        let (other_area_interface, mut other_send_packet_rx) = testing::new_sibling_interface(
+            &interface,
+            net::Ipv4Addr::new(198, 51, 100, 1),
+            InterfaceNetworkType::PointToPoint,
+        );
+        other_area_interface.lock().unwrap().aread_id = net::Ipv4Addr::new(0, 0, 0, 1);
+        let other_neighbor = testing::add_neighbor(
+            &other_area_interface,
+            net::Ipv4Addr::new(198, 51, 100, 2),
+            FULL_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        let interfaces = vec![interface.clone(), other_area_interface.clone()];
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        flood_lsa(
+            &interfaces,
+            lsa.as_ref(),
+            FloodingScope::Area(net::Ipv4Addr::UNSPECIFIED),
+            None,
+        );
+        assert_eq!(retransmissions(&interface, FULL_IP_ADDR).len(), 1);
+        assert!(retransmissions(&other_area_interface, other_neighbor).is_empty());
+        assert!(lsu_destinations(&mut other_send_packet_rx).is_empty());
+    }
+
+    fn lsu_packet(lsas: Vec<Box<dyn LinkStateAdvertisement + Send>>) -> LinkStateUpdatePacket {
+        LinkStateUpdatePacket::new(
+            OspfPacketHeader::new(
+                OSPF_VERSION_2,
+                LINK_STATE_UPDATE_TYPE,
+                0,
+                SENDER_ROUTER_ID.into(),
+                0,
+                0,
+                0,
+                0,
+            ),
+            lsas.len() as u32,
+            lsas,
+        )
+    }
+
+    /// the headers of the LSAck packets sent to `destination` since the last call.
+    fn acks_to(
+        send_packet_rx: &mut broadcast::Receiver<bytes::Bytes>,
+        destination: net::Ipv4Addr,
+    ) -> Vec<LinkStateAdvertisementHeader> {
+        testing::sent_packets(send_packet_rx)
+            .iter()
+            .filter(|(to, _)| *to == destination)
+            .filter_map(|(_, packet)| {
+                packet
+                    .as_any()
+                    .downcast_ref::<LinkStateAcknowledgementPacket>()
+            })
+            .flat_map(|lsack_packet| lsack_packet.lsa_headers.iter().map(|header| **header))
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unknown_max_age_lsa_is_only_acknowledged() {
+        let (interface, mut send_packet_rx) = broadcast_interface(InterfaceStatus::DR);
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, MaxAge as u16, 0x80000005);
+        let lsa_header = *lsa.get_header();
+        handle_lsu_packet(&interface, SENDER_IP_ADDR, &lsu_packet(vec![lsa])).unwrap();
+        assert_eq!(
+            acks_to(&mut send_packet_rx, SENDER_IP_ADDR),
+            vec![lsa_header]
+        );
+        let area = interface.lock().unwrap().area.clone().unwrap();
+        let key = LinkStateAdvertisementKey::from_header(&lsa_header);
+        assert!(area.lock().unwrap().get_lsa_header(&key).is_none());
+        assert!(retransmissions(&interface, FULL_IP_ADDR).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn max_age_lsa_is_flooded_while_a_neighbor_of_another_interface_exchanges() {
+        let (interface, mut send_packet_rx) = broadcast_interface(InterfaceStatus::DR);
+        let (other_interface, _other_send_packet_rx) = testing::new_sibling_interface(
+            &interface,
+            net::Ipv4Addr::new(198, 51, 100, 1),
+            InterfaceNetworkType::PointToPoint,
+        );
+        let exchanging = testing::add_neighbor(
+            &other_interface,
+            net::Ipv4Addr::new(198, 51, 100, 2),
+            net::Ipv4Addr::new(4, 4, 4, 4),
+            NeighborStatus::Exchange,
+        );
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, MaxAge as u16, 0x80000005);
+        let lsa_header = *lsa.get_header();
+        handle_lsu_packet(&interface, SENDER_IP_ADDR, &lsu_packet(vec![lsa])).unwrap();
+        assert!(acks_to(&mut send_packet_rx, SENDER_IP_ADDR).is_empty());
+        let area = interface.lock().unwrap().area.clone().unwrap();
+        let key = LinkStateAdvertisementKey::from_header(&lsa_header);
+        assert!(area.lock().unwrap().get_lsa_header(&key).is_some());
+        assert_eq!(retransmissions(&interface, FULL_IP_ADDR), vec![lsa_header]);
+        assert_eq!(
+            retransmissions(&other_interface, exchanging),
+            vec![lsa_header]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn lsas_installed_before_a_bad_request_are_still_flooded() {
+        let (interface, _send_packet_rx) = broadcast_interface(InterfaceStatus::DRother);
+        // from the DR, the lsas are not flooded back and the acks are delayed.
+        interface.lock().unwrap().designated_router = SENDER_IP_ADDR;
+        let requested_router = net::Ipv4Addr::new(8, 8, 8, 8);
+        let requested_lsa = testing::new_router_lsa(requested_router, 1, 0x80000005);
+        let requested_header = *requested_lsa.get_header();
+        let area = interface.lock().unwrap().area.clone().unwrap();
+        area.lock().unwrap().install_lsa(requested_lsa);
+        {
+            let neighbors = interface.lock().unwrap().get_neighbors();
+            let mut locked_neighbors = neighbors.lock().unwrap();
+            let sender = locked_neighbors.get_mut(&SENDER_IP_ADDR).unwrap();
+            sender.status = NeighborStatus::Loading;
+            sender.add_link_state_request(requested_header);
+        }
+        let new_lsa = testing::new_router_lsa(ADVERTISING_ROUTER, 1, 0x80000005);
+        let new_header = *new_lsa.get_header();
+        // the requested lsa answered with the instance we already have.
+        let lsas = vec![
+            new_lsa,
+            testing::new_router_lsa(requested_router, 1, 0x80000005),
+        ];
+        handle_lsu_packet(&interface, SENDER_IP_ADDR, &lsu_packet(lsas)).unwrap();
+        assert_eq!(
+            neighbor_status(&interface, SENDER_IP_ADDR),
+            NeighborStatus::ExStart
+        );
+        let key = LinkStateAdvertisementKey::from_header(&new_header);
+        assert!(area.lock().unwrap().get_lsa_header(&key).is_some());
+        assert_eq!(retransmissions(&interface, FULL_IP_ADDR), vec![new_header]);
+        assert_eq!(interface.lock().unwrap().delayed_acks, vec![new_header]);
+    }
+}
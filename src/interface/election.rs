@@ -0,0 +1,220 @@
+use std::net;
+
+use crate::neighbor::{status::NeighborStatus, Neighbor};
+
+/// # struct ElectionCandidate
+/// a router taking part in the DR/BDR election of RFC 2328 9.4, identified by its
+/// interface address, with the DR and BDR it declares in its hellos.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElectionCandidate {
+    pub router_id: net::Ipv4Addr,
+    pub ip_addr: net::Ipv4Addr,
+    pub priority: u8,
+    pub designated_router: net::Ipv4Addr,
+    pub backup_designated_router: net::Ipv4Addr,
+}
+
+impl ElectionCandidate {
+    /// neighbors are considered only once bidirectional communication is established.
+    pub fn from_neighbor(neighbor: &Neighbor) -> Option<Self> {
+        if neighbor.status < NeighborStatus::TwoWay {
+            return None;
+        }
+        Some(Self {
+            router_id: neighbor.router_id,
+            ip_addr: neighbor.ipv4_addr,
+            priority: neighbor.priority,
+            designated_router: neighbor.designated_router,
+            backup_designated_router: neighbor.backup_designated_router,
+        })
+    }
+
+    fn declares_dr(&self) -> bool {
+        self.designated_router == self.ip_addr
+    }
+
+    fn declares_bdr(&self) -> bool {
+        self.backup_designated_router == self.ip_addr
+    }
+}
+
+/// the highest priority wins, then the highest router id.
+fn best(candidates: Vec<&ElectionCandidate>) -> Option<&ElectionCandidate> {
+    candidates
+        .into_iter()
+        .max_by_key(|c| (c.priority, u32::from(c.router_id)))
+}
+
+/// steps 2 and 3 of the election, returns the DR and the BDR.
+fn calculate(candidates: &[ElectionCandidate]) -> (net::Ipv4Addr, net::Ipv4Addr) {
+    let eligible: Vec<&ElectionCandidate> = candidates.iter().filter(|c| c.priority > 0).collect();
+    let bdr_pool: Vec<&ElectionCandidate> = eligible
+        .iter()
+        .copied()
+        .filter(|c| !c.declares_dr())
+        .collect();
+    let declared_bdrs: Vec<&ElectionCandidate> = bdr_pool
+        .iter()
+        .copied()
+        .filter(|c| c.declares_bdr())
+        .collect();
+    let backup_designated_router = if declared_bdrs.is_empty() {
+        best(bdr_pool)
+    } else {
+        best(declared_bdrs)
+    }
+    .map_or(net::Ipv4Addr::UNSPECIFIED, |c| c.ip_addr);
+    let declared_drs: Vec<&ElectionCandidate> = eligible
+        .iter()
+        .copied()
+        .filter(|c| c.declares_dr())
+        .collect();
+    let designated_router = best(declared_drs).map_or(backup_designated_router, |c| c.ip_addr);
+    (designated_router, backup_designated_router)
+}
+
+/// the DR/BDR election of RFC 2328 9.4, run by `myself` with the neighbors in state
+/// 2-Way or higher. a DR or BDR in place is kept even if a router with a higher priority
+/// shows up. returns the new DR and BDR.
+pub fn elect(
+    myself: ElectionCandidate,
+    neighbors: &[ElectionCandidate],
+) -> (net::Ipv4Addr, net::Ipv4Addr) {
+    let mut candidates = Vec::with_capacity(neighbors.len() + 1);
+    candidates.push(myself);
+    candidates.extend_from_slice(neighbors);
+    let (designated_router, backup_designated_router) = calculate(&candidates);
+    let becomes_dr = designated_router == myself.ip_addr;
+    let becomes_bdr = backup_designated_router == myself.ip_addr;
+    if becomes_dr == myself.declares_dr() && becomes_bdr == myself.declares_bdr() {
+        return (designated_router, backup_designated_router);
+    }
+    // step 4: our own role changed, run the calculation again with what we now declare.
+    candidates[0].designated_router = designated_router;
+    candidates[0].backup_designated_router = backup_designated_router;
+    calculate(&candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONE: net::Ipv4Addr = net::Ipv4Addr::UNSPECIFIED;
+
+    fn ip(host: u8) -> net::Ipv4Addr {
+        net::Ipv4Addr::new(192, 0, 2, host)
+    }
+
+    /// the router `router_id`.`router_id`.`router_id`.`router_id` at 192.0.2.`host`,
+    /// declaring the DR and BDR at the given hosts, 0 for none.
+    fn candidate(
+        host: u8,
+        router_id: u8,
+        priority: u8,
+        designated_router: u8,
+        backup_designated_router: u8,
+    ) -> ElectionCandidate {
+        let declared = |host| if host == 0 { NONE } else { ip(host) };
+        ElectionCandidate {
+            router_id: net::Ipv4Addr::new(router_id, router_id, router_id, router_id),
+            ip_addr: ip(host),
+            priority,
+            designated_router: declared(designated_router),
+            backup_designated_router: declared(backup_designated_router),
+        }
+    }
+
+    #[test]
+    fn priority_zero_is_never_elected() {
+        let myself = candidate(1, 1, 1, 0, 0);
+        let neighbors = [candidate(9, 9, 0, 0, 0)];
+        assert_eq!(elect(myself, &neighbors), (ip(1), NONE));
+        // a router declaring itself DR is not kept once its priority drops to 0.
+        let neighbors = [candidate(9, 9, 0, 9, 0)];
+        assert_eq!(elect(myself, &neighbors), (ip(1), NONE));
+        let myself = candidate(1, 1, 0, 0, 0);
+        assert_eq!(elect(myself, &neighbors), (NONE, NONE));
+    }
+
+    #[test]
+    fn higher_priority_does_not_preempt() {
+        let myself = candidate(1, 1, 1, 2, 3);
+        let neighbors = [
+            candidate(2, 2, 1, 2, 3),
+            candidate(3, 3, 1, 2, 3),
+            // a newcomer that would win a fresh election.
+            candidate(4, 4, 200, 0, 0),
+        ];
+        assert_eq!(elect(myself, &neighbors), (ip(2), ip(3)));
+    }
+
+    #[test]
+    fn backup_is_promoted_when_the_dr_disappears() {
+        // we are the BDR, the DR at .2 is gone.
+        let myself = candidate(1, 1, 1, 2, 1);
+        let neighbors = [candidate(3, 3, 1, 2, 1), candidate(4, 4, 1, 2, 1)];
+        // promoted, we elect a new BDR in the same run.
+        assert_eq!(elect(myself, &neighbors), (ip(1), ip(4)));
+        // a DR Other promotes the BDR too, the new BDR comes with its next hello.
+        let myself = candidate(3, 3, 1, 2, 1);
+        let neighbors = [candidate(1, 1, 1, 2, 1), candidate(4, 4, 1, 2, 1)];
+        assert_eq!(elect(myself, &neighbors), (ip(1), ip(1)));
+    }
+
+    #[test]
+    fn own_role_change_runs_the_calculation_again() {
+        // a fresh network: the first calculation makes us both BDR and DR, the second
+        // one, with us declaring DR, gives the BDR to the neighbor.
+        let myself = candidate(1, 2, 1, 0, 0);
+        let neighbors = [candidate(2, 1, 1, 0, 0)];
+        assert_eq!(calculate(&[myself, neighbors[0]]), (ip(1), ip(1)));
+        assert_eq!(elect(myself, &neighbors), (ip(1), ip(2)));
+        // no longer eligible, we stop declaring ourselves DR.
+        let myself = candidate(1, 2, 0, 1, 2);
+        let neighbors = [candidate(2, 1, 1, 1, 2)];
+        assert_eq!(elect(myself, &neighbors), (ip(2), ip(2)));
+    }
+
+    #[test]
+    fn ties_are_broken_by_router_id() {
+        // the DR at .8 stays, the higher router id is the BDR whatever the addresses.
+        let myself = candidate(9, 1, 1, 8, 0);
+        let designated_router = candidate(8, 8, 1, 8, 0);
+        let neighbors = [
+            designated_router,
+            candidate(2, 7, 1, 8, 0),
+            candidate(3, 5, 1, 8, 0),
+        ];
+        assert_eq!(elect(myself, &neighbors), (ip(8), ip(2)));
+        // among routers declaring themselves BDR too.
+        let neighbors = [
+            designated_router,
+            candidate(2, 7, 1, 8, 2),
+            candidate(3, 5, 1, 8, 3),
+        ];
+        assert_eq!(elect(myself, &neighbors), (ip(8), ip(2)));
+        // and among routers declaring themselves DR.
+        let neighbors = [designated_router, candidate(6, 9, 1, 6, 0)];
+        assert_eq!(elect(myself, &neighbors).0, ip(6));
+        // the priority comes first.
+        let neighbors = [
+            designated_router,
+            candidate(2, 7, 1, 8, 0),
+            candidate(3, 5, 2, 8, 0),
+        ];
+        assert_eq!(elect(myself, &neighbors), (ip(8), ip(3)));
+    }
+
+    #[test]
+    fn neighbors_below_two_way_do_not_take_part() {
+        let mut neighbor = Neighbor::new(ip(2));
+        neighbor.priority = 1;
+        neighbor.status = NeighborStatus::Init;
+        assert!(ElectionCandidate::from_neighbor(&neighbor).is_none());
+        neighbor.status = NeighborStatus::TwoWay;
+        assert_eq!(
+            ElectionCandidate::from_neighbor(&neighbor).map(|c| c.ip_addr),
+            Some(ip(2))
+        );
+    }
+}
@@ -1,3 +1,4 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum InterfaceEvent {
     InterfaceUp,
     WaitTimer,
@@ -9,3 +10,35 @@ pub enum InterfaceEvent {
 }
 
 impl InterfaceEvent {}
+
+impl std::str::FromStr for InterfaceEvent {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "up" | "interfaceup" => Ok(InterfaceEvent::InterfaceUp),
+            "wait-timer" | "waittimer" => Ok(InterfaceEvent::WaitTimer),
+            "backup-seen" | "backupseen" => Ok(InterfaceEvent::BackupSeen),
+            "neighbor-change" | "neighborchange" => Ok(InterfaceEvent::NeighborChange),
+            "loop" | "loopind" => Ok(InterfaceEvent::LoopInd),
+            "unloop" | "unloopind" => Ok(InterfaceEvent::UnloopInd),
+            "down" | "interfacedown" => Ok(InterfaceEvent::InterfaceDown),
+            _ => Err("unknown interface event"),
+        }
+    }
+}
+
+/// # enum InterfaceAction
+/// what has to be done when the interface state machine takes a transition (RFC 2328 9.3).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterfaceAction {
+    /// start sending hellos every HelloInterval.
+    StartHelloTimer,
+    /// start the one-shot wait timer of RouterDeadInterval.
+    StartWaitTimer,
+    /// run the DR/BDR election, which decides the next state of the interface.
+    ElectDesignatedRouter,
+    /// reset the interface variables and disable its timers.
+    ResetInterface,
+    /// generate KillNbr on every neighbor, which are then destroyed.
+    KillNeighbors,
+}
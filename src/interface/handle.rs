@@ -1,33 +1,25 @@
 use std::{
-    collections::HashMap,
-    net,
     sync::{Arc, Mutex},
     thread::JoinHandle,
 };
 
-use pnet::{
-    packet::{
-        ip::IpNextHeaderProtocol,
-        ipv4::{self, Ipv4Packet},
-        udp, Packet,
-    },
-    transport::{self, ipv4_packet_iter},
-};
+use pnet::packet::{ip::IpNextHeaderProtocol, ipv4::Ipv4Packet, Packet};
 use tokio::{sync::broadcast, time};
 
 use crate::{
-    error,
-    lsa::router,
+    area::{self, origination::Origination},
+    auth, flooding,
+    lsa::{LSRefreshTime, MinLSInterval},
     neighbor,
-    packet::{self, is_ospf_packet_valid, new_ip_packet, OspfPacket},
-    OSPF_IP_PROTOCOL_NUMBER, OSPF_VERSION_2,
+    packet::{self, is_ospf_packet_valid},
+    AllDRouters, MTU, OSPF_IP_PROTOCOL_NUMBER,
 };
 
+use super::socket::OspfSocket;
+
 pub enum OpsfHandleType {
-    SendTcpPacket,
-    RecvTcpPacket,
-    SendUdpPacket,
-    RecvUdpPacket,
+    SendOspfPacket,
+    RecvOspfPacket,
     CreateHelloPacket,
     CreateDataDescriptionPacket,
 }
@@ -46,84 +38,60 @@ impl<T> OspfHandle<T> {
     }
 }
 
-pub async fn send_tcp_packet_raw_handle(
-    mut to_send_tcp_packet_rx: broadcast::Receiver<bytes::Bytes>,
-    mut tcp_ip_packet_tx: transport::TransportSender,
+pub async fn send_ospf_packet_raw_handle(
+    mut to_send_ospf_packet_rx: broadcast::Receiver<bytes::Bytes>,
+    ospf_socket: Arc<OspfSocket>,
 ) {
     loop {
-        if let Ok(packet_bytes) = to_send_tcp_packet_rx.recv().await {
+        if let Ok(packet_bytes) = to_send_ospf_packet_rx.recv().await {
             if let Some(packet) = Ipv4Packet::new(&packet_bytes) {
                 let destination = packet.get_destination();
-                if let Ok(_) = tcp_ip_packet_tx.send_to(packet, net::IpAddr::V4(destination)) {
-                    crate::debug("interface sending the tcp-sending packet success.");
-                } else {
-                    crate::error("interface sending the tcp-sending packet failed.");
+                let total_length = packet.get_total_length() as usize;
+                if total_length > packet_bytes.len() {
+                    crate::error("interface parsing the to-send ospf packet failed.");
+                    continue;
                 }
-            } else {
-                crate::error("interface parsing the tcp-sending packet failed.");
-            }
-        } else {
-            crate::error("interface receiving the tcp-sending packet failed.");
-        }
-    }
-}
-
-pub async fn send_udp_packet_raw_handle(
-    mut to_send_udp_packet_rx: broadcast::Receiver<bytes::Bytes>,
-    mut udp_ip_packet_tx: transport::TransportSender,
-) {
-    loop {
-        if let Ok(packet_bytes) = to_send_udp_packet_rx.recv().await {
-            if let Some(packet) = Ipv4Packet::new(&packet_bytes) {
-                let destination = packet.get_destination();
-                if let Ok(_) = udp_ip_packet_tx.send_to(packet, net::IpAddr::V4(destination)) {
-                    crate::debug("interface sending the udp-sending packet success.");
+                if ospf_socket
+                    .send_to(&packet_bytes[..total_length], destination)
+                    .await
+                    .is_ok()
+                {
+                    crate::debug("interface sending the ospf packet success.");
                 } else {
-                    crate::error("interface sending the udp-sending packet failed.");
+                    crate::error("interface sending the ospf packet failed.");
                 }
             } else {
-                crate::error("interface parsing the udp-sending packet failed.");
+                crate::error("interface parsing the to-send ospf packet failed.");
             }
         } else {
-            crate::error("interface receiving the udp-sending packet failed.");
+            crate::error("interface receiving the to-send ospf packet failed.");
         }
     }
 }
 
-pub async fn recv_tcp_packet_raw_handle(
-    mut to_send_tcp_packet_tx: broadcast::Sender<bytes::Bytes>,
-    mut tcp_ip_packet_rx: transport::TransportReceiver,
-) {
-    let mut ipv4_packet_iter = ipv4_packet_iter(&mut tcp_ip_packet_rx);
-    loop {
-        if let Ok((packet, _ip)) = ipv4_packet_iter.next() {
-            if !packet::is_ip_packet_valid(&packet) {
-                crate::error("interface received invalid ip packet.");
-                continue;
-            } else {
-                crate::debug(&format!(
-                    "interface received valid ip packet from tcp.[{:#?}]",
-                    packet
-                ));
-            }
-        } else {
-            crate::error("interface recv ip packet failed.");
-        }
-    }
-}
 ///
-/// # recv_udp_packet_raw_handle
-///this function is used to handle the received udp packet from the interface.
-///it can receive udp and tcp packet, if received ospf packet, then handle it.
-///otherwise, just forwarding it or receive it.
+/// # recv_ospf_packet_raw_handle
+///this function is used to handle the received ospf packet from the interface.
+///the raw socket only delivers ip protocol 89, anything else is dropped anyway.
+///packets not meant for the ingress interface are dropped before being parsed.
 ///
-pub async fn recv_udp_packet_raw_handle(
-    mut to_send_udp_packet_tx: broadcast::Sender<bytes::Bytes>,
-    mut udp_ip_packet_rx: transport::TransportReceiver,
+pub async fn recv_ospf_packet_raw_handle(
+    ospf_socket: Arc<OspfSocket>,
+    interface: Arc<Mutex<super::Interface>>,
+    ingress: super::IngressInterface,
+    statistics: Arc<Mutex<super::InterfaceStatistics>>,
+    authentication: auth::Authentication,
 ) {
-    let mut ipv4_packet_iter = ipv4_packet_iter(&mut udp_ip_packet_rx);
+    let mut buffer = vec![0u8; MTU];
     loop {
-        if let Ok((packet, _ip)) = ipv4_packet_iter.next() {
+        let length = match ospf_socket.recv(&mut buffer).await {
+            Ok(length) => length,
+            Err(_) => {
+                crate::error("interface recv ip packet failed.");
+                continue;
+            }
+        };
+        if let Some(packet) = Ipv4Packet::new(&buffer[..length]) {
             if !packet::is_ip_packet_valid(&packet) {
                 crate::error("interface received invalid ip packet.");
                 continue;
@@ -131,111 +99,315 @@ pub async fn recv_udp_packet_raw_handle(
             // handle the received ip packet.
             match packet.get_next_level_protocol() {
                 IpNextHeaderProtocol(OSPF_IP_PROTOCOL_NUMBER) => {
-                    crate::debug("interface received ospf udp packet.");
-                    let neighbors = Arc::new(Mutex::new(HashMap::new()));
-                    if let Ok(ospf_packet) =
-                        packet::try_get_from_ipv4_packet(&packet, neighbors.clone())
-                    {
-                        crate::debug("interface received ospf udp packet and parse success.");
-                        if !is_ospf_packet_valid(ospf_packet.as_ref()) {
-                            crate::error("interface received ospf udp packet but checksum failed.");
+                    crate::debug(&format!("interface {} received ospf packet.", ingress.name));
+                    if let Err(e) = ingress.check(
+                        packet.get_source(),
+                        packet.get_destination(),
+                        ospf_socket.is_member_of(AllDRouters),
+                    ) {
+                        statistics.lock().unwrap().ingress_errors += 1;
+                        crate::debug(&format!(
+                            "interface {} discarded ospf packet: {}.",
+                            ingress.name, e
+                        ));
+                        continue;
+                    }
+                    let ospf_packet = match packet::try_get_from_ipv4_packet(&packet) {
+                        Ok(ospf_packet) => ospf_packet,
+                        Err(e) => {
+                            statistics.lock().unwrap().decode_errors += 1;
+                            crate::error(&format!(
+                                "interface received ospf packet but parse failed: {}.",
+                                e
+                            ));
                             continue;
                         }
-                        match ospf_packet.get_type() {
-                            packet::hello::HELLO_PACKET_TYPE => {
-                                crate::debug(
-                                    "interface received hello packet,try to update its neighbors",
-                                );
-                            }
-                            packet::dd::DATA_DESCRIPTION_PACKET_TYPE => {
-                                crate::debug("interface received dd packet.");
+                    };
+                    crate::debug("interface received ospf packet and parse success.");
+                    if !is_ospf_packet_valid(packet.payload()) {
+                        statistics.lock().unwrap().checksum_errors += 1;
+                        crate::error("interface received ospf packet but checksum failed.");
+                        continue;
+                    }
+                    if let Err(e) = authentication.verify(packet.payload(), packet.get_source()) {
+                        statistics.lock().unwrap().auth_errors += 1;
+                        crate::error(&format!(
+                            "interface received ospf packet but authentication failed: {}.",
+                            e
+                        ));
+                        continue;
+                    }
+                    match ospf_packet.get_type() {
+                        packet::hello::HELLO_PACKET_TYPE => {
+                            crate::debug(
+                                "interface received hello packet,try to update its neighbors",
+                            );
+                            if let Some(hello_packet) = ospf_packet
+                                .as_any()
+                                .downcast_ref::<packet::hello::HelloPacket>(
+                            ) {
+                                if let Err(e) = super::handle_hello_packet(
+                                    &interface,
+                                    packet.get_source(),
+                                    hello_packet,
+                                ) {
+                                    crate::error(&format!(
+                                        "interface {} discarded hello packet: {}.",
+                                        ingress.name, e
+                                    ));
+                                }
                             }
-                            packet::lsr::LINK_STATE_REQUEST_PACKET_TYPE => {
-                                crate::debug("interface received lsr packet.");
+                        }
+                        packet::dd::DATA_DESCRIPTION_PACKET_TYPE => {
+                            crate::debug("interface received dd packet.");
+                            if let Some(dd_packet) = ospf_packet
+                                .as_any()
+                                .downcast_ref::<packet::dd::DataDescriptionPacket>(
+                            ) {
+                                if let Err(e) = neighbor::exchange::handle_dd_packet(
+                                    &interface,
+                                    packet.get_source(),
+                                    dd_packet,
+                                ) {
+                                    crate::error(&format!(
+                                        "interface {} discarded dd packet: {}.",
+                                        ingress.name, e
+                                    ));
+                                }
                             }
-                            packet::lsack::LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE => {
-                                crate::debug("interface received lsack packet.");
+                        }
+                        packet::lsr::LINK_STATE_REQUEST_PACKET_TYPE => {
+                            crate::debug("interface received lsr packet.");
+                            if let Some(lsr_packet) = ospf_packet
+                                .as_any()
+                                .downcast_ref::<packet::lsr::LinkStateRequestPacket>(
+                            ) {
+                                if let Err(e) = neighbor::request::handle_lsr_packet(
+                                    &interface,
+                                    packet.get_source(),
+                                    lsr_packet,
+                                ) {
+                                    crate::error(&format!(
+                                        "interface {} discarded lsr packet: {}.",
+                                        ingress.name, e
+                                    ));
+                                }
                             }
-                            packet::lsu::LINK_STATE_UPDATE_TYPE => {
-                                crate::debug("interface received lsu packet.");
+                        }
+                        packet::lsack::LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE => {
+                            crate::debug("interface received lsack packet.");
+                            if let Some(lsack_packet) =
+                                ospf_packet
+                                    .as_any()
+                                    .downcast_ref::<packet::lsack::LinkStateAcknowledgementPacket>()
+                            {
+                                if let Err(e) = flooding::ack::handle_lsack_packet(
+                                    &interface,
+                                    packet.get_source(),
+                                    lsack_packet,
+                                ) {
+                                    crate::error(&format!(
+                                        "interface {} discarded lsack packet: {}.",
+                                        ingress.name, e
+                                    ));
+                                }
                             }
-                            _ => {
-                                crate::error("interface received unknown ospf packet.");
+                        }
+                        packet::lsu::LINK_STATE_UPDATE_TYPE => {
+                            crate::debug("interface received lsu packet.");
+                            if let Some(lsu_packet) = ospf_packet
+                                .as_any()
+                                .downcast_ref::<packet::lsu::LinkStateUpdatePacket>(
+                            ) {
+                                let bad_lsa_count = lsu_packet.bad_checksum_count;
+                                if bad_lsa_count > 0 {
+                                    statistics.lock().unwrap().lsa_checksum_errors +=
+                                        bad_lsa_count as u64;
+                                    crate::error(&format!(
+                                        "interface discarded {} lsa(s) with bad checksum.",
+                                        bad_lsa_count
+                                    ));
+                                }
+                                if let Err(e) = flooding::handle_lsu_packet(
+                                    &interface,
+                                    packet.get_source(),
+                                    lsu_packet,
+                                ) {
+                                    crate::error(&format!(
+                                        "interface {} discarded lsu packet: {}.",
+                                        ingress.name, e
+                                    ));
+                                }
                             }
                         }
-                    } else {
-                        crate::error(
-                            "interface received proto-num 89 udp packet but parse failed.",
-                        );
+                        _ => {
+                            crate::error("interface received unknown ospf packet.");
+                        }
                     }
                 }
                 _ => {
-                    crate::debug("interface received proto-num non-89 udp packet.");
+                    crate::debug("interface received non-ospf ip packet.");
                 }
             }
         } else {
-            crate::error("interface recv ip packet failed.");
+            crate::error("interface received truncated ip packet.");
         }
     }
 }
 
-pub async fn create_hello_packet_raw_handle(
-    send_packet_tx: broadcast::Sender<bytes::Bytes>,
-    hello_interval: u16,
-    network_mask: net::Ipv4Addr,
-    options: u8,
-    router_id: u32,
-    area_id: u32,
-    router_priority: u8,
+/// the hello timer, sends a hello built from the current state of the interface
+/// every HelloInterval.
+pub async fn create_hello_packet_raw_handle(interface: Arc<Mutex<super::Interface>>) {
+    loop {
+        let hello_interval = interface.lock().unwrap().hello_interval;
+        time::sleep(time::Duration::from_secs(hello_interval as u64)).await;
+        interface.lock().unwrap().send_hellos();
+    }
+}
+
+/// the wait timer, fires WaitTimer on the interface after RouterDeadInterval.
+pub async fn wait_timer_raw_handle(
+    interface: Arc<Mutex<super::Interface>>,
     router_dead_interval: u32,
-    designated_router: u32,
-    backup_designated_router: u32,
-    src_ip: net::Ipv4Addr,
-    dst_ip: net::Ipv4Addr,
-    neighbors: Arc<Mutex<HashMap<net::Ipv4Addr, neighbor::Neighbor>>>,
 ) {
-    let duration = time::Duration::from_secs(hello_interval as u64);
-    let ospf_packet_header = packet::OspfPacketHeader::new(
-        OSPF_VERSION_2,
-        packet::hello::HELLO_PACKET_TYPE,
-        packet::OspfPacketHeader::length() as u16,
-        router_id,
-        area_id,
-        0,
-        0,
-        0,
-    );
+    time::sleep(time::Duration::from_secs(router_dead_interval as u64)).await;
+    super::handle_event(&interface, super::event::InterfaceEvent::WaitTimer);
+}
+
+/// the delayed ack timer, sends the acks queued on the interface together
+/// `delay` seconds after the first of them was queued.
+pub async fn delayed_ack_raw_handle(interface: Arc<Mutex<super::Interface>>, delay: u32) {
+    time::sleep(time::Duration::from_secs(delay as u64)).await;
+    flooding::ack::send_delayed_acks(&mut interface.lock().unwrap());
+}
+
+/// the origination of the network-lsa of the interface (RFC 2328 12.4): once triggered,
+/// a new instance is originated at most once every MinLSInterval, on each trigger and every
+/// LSRefreshTime, until the interface is no longer the DR of a network with full adjacencies.
+pub async fn network_lsa_origination_raw_handle(
+    interface: Arc<Mutex<super::Interface>>,
+    interfaces: Vec<Arc<Mutex<super::Interface>>>,
+) {
+    let trigger = interface.lock().unwrap().get_network_lsa_trigger();
     loop {
-        time::sleep(duration).await;
-        let hello_ospf_packet = packet::hello::HelloPacket::new(
-            network_mask,
-            hello_interval,
-            options,
-            router_priority,
-            router_dead_interval,
-            designated_router,
-            backup_designated_router,
-            ospf_packet_header,
-            neighbors.clone(),
-        );
-        let mut ip_packet_buffer = vec![0u8; 1500];
-        let ip_packet = new_ip_packet(
-            ip_packet_buffer.as_mut_slice(),
-            src_ip,
-            dst_ip,
-            hello_ospf_packet.to_bytes(),
-        );
-        if let Ok(ip_packet) = ip_packet {
-            let hello_packet_bytes = bytes::Bytes::copy_from_slice(ip_packet.packet());
-            if let Ok(_) = send_packet_tx.send(hello_packet_bytes) {
-                crate::debug("send hello packet success.");
-            } else {
-                crate::error("send hello packet failed.");
+        trigger.notified().await;
+        loop {
+            let refresh_at = time::Instant::now() + time::Duration::from_secs(LSRefreshTime as u64);
+            match area::origination::originate_network_lsa(&interface, &interfaces) {
+                Origination::Withdrawn => break,
+                Origination::Deferred => {
+                    time::sleep(time::Duration::from_secs(1)).await;
+                    continue;
+                }
+                Origination::Originated => {}
+            }
+            time::sleep(time::Duration::from_secs(MinLSInterval as u64)).await;
+            tokio::select! {
+                _ = trigger.notified() => {}
+                _ = time::sleep_until(refresh_at) => {}
             }
-        } else {
-            crate::error("hello packet to ip packet failed.");
         }
     }
 }
 
-pub async fn create_dd_packet_raw_handle() {}
+#[cfg(test)]
+mod tests {
+    use std::net;
+
+    use tokio::time;
+
+    use crate::{
+        interface::{
+            self, event::InterfaceEvent, status::InterfaceStatus, InterfaceNetworkType,
+            DEFAULT_ROUTER_DEAD_INTERVAL,
+        },
+        testing,
+    };
+
+    const IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 1);
+    const ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(1, 1, 1, 1);
+    const NEIGHBOR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 2);
+    const NEIGHBOR_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(2, 2, 2, 2);
+
+    #[tokio::test(start_paused = true)]
+    async fn wait_timer_elects_after_router_dead_interval() {
+        let (interface, _send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        assert_eq!(
+            interface::handle_event(&interface, InterfaceEvent::InterfaceUp),
+            InterfaceStatus::Waiting
+        );
+        assert!(interface.lock().unwrap().wait_timer_handle.is_some());
+        let router_dead_interval = time::Duration::from_secs(DEFAULT_ROUTER_DEAD_INTERVAL as u64);
+        time::sleep(router_dead_interval - time::Duration::from_millis(1)).await;
+        assert_eq!(interface.lock().unwrap().status, InterfaceStatus::Waiting);
+        time::sleep(time::Duration::from_millis(2)).await;
+        let locked_interface = interface.lock().unwrap();
+        // alone on the network, the router elects itself.
+        assert_eq!(locked_interface.status, InterfaceStatus::DR);
+        assert_eq!(locked_interface.designated_router, IP_ADDR);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backup_seen_ends_waiting_early() {
+        let (interface, _send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        interface::handle_event(&interface, InterfaceEvent::InterfaceUp);
+        time::sleep(time::Duration::from_secs(5)).await;
+        // the neighbor already is the BDR of the network.
+        let hello_packet = testing::new_hello_packet(
+            NEIGHBOR_ROUTER_ID,
+            1,
+            net::Ipv4Addr::UNSPECIFIED,
+            NEIGHBOR_IP_ADDR,
+            vec![ROUTER_ID],
+        );
+        assert!(interface
+            .lock()
+            .unwrap()
+            .is_backup_seen(NEIGHBOR_IP_ADDR, &hello_packet));
+        interface::handle_hello_packet(&interface, NEIGHBOR_IP_ADDR, &hello_packet).unwrap();
+        let status = interface.lock().unwrap().status;
+        assert_ne!(status, InterfaceStatus::Waiting);
+        assert!(interface.lock().unwrap().wait_timer_handle.is_none());
+        // the wait timer no longer fires.
+        time::sleep(time::Duration::from_secs(
+            DEFAULT_ROUTER_DEAD_INTERVAL as u64,
+        ))
+        .await;
+        assert_eq!(interface.lock().unwrap().status, status);
+    }
+
+    #[test]
+    fn backup_seen_only_while_waiting() {
+        let (interface, _send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        let declares_backup = testing::new_hello_packet(
+            NEIGHBOR_ROUTER_ID,
+            1,
+            net::Ipv4Addr::UNSPECIFIED,
+            NEIGHBOR_IP_ADDR,
+            vec![ROUTER_ID],
+        );
+        let declares_dr_without_backup = testing::new_hello_packet(
+            NEIGHBOR_ROUTER_ID,
+            1,
+            NEIGHBOR_IP_ADDR,
+            net::Ipv4Addr::UNSPECIFIED,
+            vec![ROUTER_ID],
+        );
+        let declares_dr_with_backup = testing::new_hello_packet(
+            NEIGHBOR_ROUTER_ID,
+            1,
+            NEIGHBOR_IP_ADDR,
+            IP_ADDR,
+            vec![ROUTER_ID],
+        );
+        let mut locked_interface = interface.lock().unwrap();
+        assert!(!locked_interface.is_backup_seen(NEIGHBOR_IP_ADDR, &declares_backup));
+        locked_interface.status = InterfaceStatus::Waiting;
+        assert!(locked_interface.is_backup_seen(NEIGHBOR_IP_ADDR, &declares_backup));
+        assert!(locked_interface.is_backup_seen(NEIGHBOR_IP_ADDR, &declares_dr_without_backup));
+        assert!(!locked_interface.is_backup_seen(NEIGHBOR_IP_ADDR, &declares_dr_with_backup));
+    }
+}
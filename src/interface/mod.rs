@@ -1,26 +1,44 @@
 use std::{
     collections::HashMap,
-    mem::size_of,
     net,
     sync::{Arc, Mutex},
 };
 
-use handle::recv_tcp_packet_raw_handle;
-use pnet::{
-    datalink,
-    packet::ip::IpNextHeaderProtocols::{Tcp, Udp},
-    transport::{self, TransportReceiver, TransportSender},
+use pnet::{datalink, packet::Packet};
+use tokio::{
+    sync::{broadcast, Notify},
+    task::JoinHandle,
 };
-use tokio::{sync::broadcast, task::JoinHandle, time};
 
 use crate::{
+    area,
+    auth::{
+        self,
+        digest::CryptographicAlgorithm,
+        keychain::{AuthenticationKey, KeyChain, KeyLifetime},
+    },
+    error::{AuthenticationError, HelloError, IngressError},
     interface, ipv4_addr_to_bits,
-    neighbor::Neighbor,
-    packet::{hello::HELLO_PACKET_TYPE, try_get_from_ipv4_packet, OspfPacket, OspfPacketHeader},
-    prompt_and_read, router, AllSPFRouters, OSPF_VERSION_2,
+    lsa::{
+        router::{
+            RouterLink, ROUTER_LINK_TYPE_POINT_TO_POINT, ROUTER_LINK_TYPE_STUB,
+            ROUTER_LINK_TYPE_TRANSIT, ROUTER_LINK_TYPE_VIRTUAL,
+        },
+        LinkStateAdvertisement, LinkStateAdvertisementHeader,
+    },
+    lsdb::LinkStateAdvertisementKey,
+    neighbor::{self, status::NeighborStatus, Neighbor},
+    packet::{
+        hello::{self, HELLO_PACKET_TYPE},
+        lsr::LinkStatusRequest,
+        new_ip_packet, OspfPacket, OspfPacketHeader, AUTH_TYPE_CRYPTOGRAPHIC,
+    },
+    prompt_and_read, router, AllDRouters, AllSPFRouters, MTU, OPTION_BIT_E, OSPF_VERSION_2,
 };
+pub mod election;
 pub mod event;
 pub mod handle;
+pub mod socket;
 pub mod status;
 
 #[derive(Clone, Copy)]
@@ -56,6 +74,62 @@ impl std::fmt::Debug for InterfaceNetworkType {
     }
 }
 
+/// # struct InterfaceStatistics
+/// counters of the packets the interface dropped, shared with its handlers.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct InterfaceStatistics {
+    pub decode_errors: u64,
+    pub checksum_errors: u64,
+    pub lsa_checksum_errors: u64,
+    pub auth_errors: u64,
+    pub ingress_errors: u64,
+    pub hello_errors: u64,
+}
+
+/// # struct IngressInterface
+/// the interface a packet arrived on, the socket of each interface is bound to its device
+/// so whatever its receive handler gets came in through this link.
+#[derive(Clone, Debug)]
+pub struct IngressInterface {
+    pub name: String,
+    pub ip_addr: net::Ipv4Addr,
+    pub network_mask: net::Ipv4Addr,
+    pub network_type: InterfaceNetworkType,
+}
+
+impl IngressInterface {
+    /// the RFC 2328 8.2 checks on the ip header of a packet received on the interface.
+    /// `is_dr_or_backup` tells whether packets sent to AllDRouters are accepted.
+    pub fn check(
+        &self,
+        source: net::Ipv4Addr,
+        destination: net::Ipv4Addr,
+        is_dr_or_backup: bool,
+    ) -> Result<(), IngressError> {
+        if source == self.ip_addr {
+            return Err(IngressError::OwnPacket);
+        }
+        if destination == AllDRouters {
+            if !is_dr_or_backup {
+                return Err(IngressError::NotDesignatedRouter);
+            }
+        } else if destination != AllSPFRouters && destination != self.ip_addr {
+            return Err(IngressError::WrongDestination(destination));
+        }
+        // the subnet is not checked on point-to-point networks and virtual links.
+        if !matches!(
+            self.network_type,
+            InterfaceNetworkType::PointToPoint | InterfaceNetworkType::VirtualLink
+        ) {
+            let mask = ipv4_addr_to_bits(self.network_mask);
+            if ipv4_addr_to_bits(source) & mask != ipv4_addr_to_bits(self.ip_addr) & mask {
+                return Err(IngressError::SourceNotOnSubnet(source));
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct Interface {
     pub name: String,
     pub ip_addr: net::Ipv4Addr,
@@ -69,18 +143,32 @@ pub struct Interface {
     pub router_dead_interval: u32,
     pub auth_type: u32,
     pub auth_key: u64,
+    pub auth_key_chain: Option<Arc<Mutex<KeyChain>>>,
+    pub nbma_neighbors: Vec<net::Ipv4Addr>,
+    pub transit_area_id: Option<net::Ipv4Addr>,
     pub network_type: InterfaceNetworkType,
-    pub trans_rx: TransportReceiver,
-    pub trans_tx: TransportSender,
     pub inner_rx: broadcast::Receiver<Arc<Mutex<dyn crate::packet::OspfPacket + Send>>>,
     pub inner_tx: broadcast::Sender<Arc<Mutex<dyn crate::packet::OspfPacket + Send>>>,
     pub send_packet_handle: Option<JoinHandle<()>>,
     pub recv_packet_handle: Option<JoinHandle<()>>,
     pub produce_hello_packet_handle: Option<JoinHandle<()>>,
+    pub wait_timer_handle: Option<JoinHandle<()>>,
+    pub delayed_ack_handle: Option<JoinHandle<()>>,
+    pub delayed_acks: Vec<LinkStateAdvertisementHeader>,
+    pub network_lsa_trigger: Arc<Notify>,
     pub produce_dd_packet_handle: Option<JoinHandle<()>>,
     pub neighbors: Arc<Mutex<HashMap<net::Ipv4Addr, Neighbor>>>,
     pub router: Arc<Mutex<crate::router::Router>>,
+    pub router_id: net::Ipv4Addr,
+    pub area: Option<Arc<Mutex<area::Area>>>,
+    pub transit_area: Option<Arc<Mutex<area::Area>>>,
     pub status: status::InterfaceStatus,
+    pub designated_router: net::Ipv4Addr,
+    pub backup_designated_router: net::Ipv4Addr,
+    pub send_packet_tx: Option<broadcast::Sender<bytes::Bytes>>,
+    pub authentication: Option<auth::Authentication>,
+    pub ospf_socket: Option<Arc<socket::OspfSocket>>,
+    pub statistics: Arc<Mutex<InterfaceStatistics>>,
 }
 
 pub const DEFAULT_HELLO_INTERVAL: u32 = 10;
@@ -90,12 +178,65 @@ pub const DEFAULT_INF_TRANS_DELAY: u32 = 1;
 pub const DEFAULT_ROUTER_PRIORITY: u32 = 1;
 pub const DEFAULT_ROUTER_DEAD_INTERVAL: u32 = 40;
 pub const DEFAULT_AUTH_TYPE: u32 = 0;
+pub const DEFAULT_AUTH_ALGORITHM: CryptographicAlgorithm = CryptographicAlgorithm::KeyedMd5;
+pub const DEFAULT_AUTH_KEY_ID: u8 = 1;
 pub const DEFAULT_AUTH_KEY: u64 = 0;
+pub const DEFAULT_AUTH_KEY_CHAIN: &str = "default";
 pub const DEFAULT_AREA_ID: u32 = 0;
 
+/// # struct InterfaceConfig
+/// the configurable parameters of an interface (RFC 2328 9), given to `Interface::new`.
+/// - area_id : the area the attached network belongs to.
+/// - output_cost : the cost of sending a packet on the interface.
+/// - rxmt_interval : the seconds between retransmissions to an adjacency.
+/// - inf_trans_delay : the estimated seconds it takes to transmit an LSU packet.
+/// - router_priority : the priority of the router in the DR election.
+/// - hello_interval : the seconds between the hello packets.
+/// - router_dead_interval : the seconds before a silent neighbor is declared down.
+/// - auth_type : the authentication type of the network.
+/// - auth_key : the simple password of the network.
+/// - auth_key_chain : the key chain of the cryptographic authentication.
+/// - nbma_neighbors : the addresses of the neighbors on an NBMA network, which cannot be
+///   discovered by multicast hellos (RFC 2328 9.5.1).
+/// - transit_area_id : the area a virtual link crosses (RFC 2328 15), none on the other networks.
+#[derive(Clone)]
+pub struct InterfaceConfig {
+    pub area_id: net::Ipv4Addr,
+    pub output_cost: u32,
+    pub rxmt_interval: u32,
+    pub inf_trans_delay: u32,
+    pub router_priority: u32,
+    pub hello_interval: u32,
+    pub router_dead_interval: u32,
+    pub auth_type: u32,
+    pub auth_key: u64,
+    pub auth_key_chain: Option<Arc<Mutex<KeyChain>>>,
+    pub nbma_neighbors: Vec<net::Ipv4Addr>,
+    pub transit_area_id: Option<net::Ipv4Addr>,
+}
+
+impl Default for InterfaceConfig {
+    fn default() -> Self {
+        Self {
+            area_id: net::Ipv4Addr::from(DEFAULT_AREA_ID),
+            output_cost: DEFAULT_OUTPUT_COST,
+            rxmt_interval: DEFAULT_RXMT_INTERVAL,
+            inf_trans_delay: DEFAULT_INF_TRANS_DELAY,
+            router_priority: DEFAULT_ROUTER_PRIORITY,
+            hello_interval: DEFAULT_HELLO_INTERVAL,
+            router_dead_interval: DEFAULT_ROUTER_DEAD_INTERVAL,
+            auth_type: DEFAULT_AUTH_TYPE,
+            auth_key: DEFAULT_AUTH_KEY,
+            auth_key_chain: None,
+            nbma_neighbors: Vec::new(),
+            transit_area_id: None,
+        }
+    }
+}
+
 fn detect_pnet_interface() -> Result<Vec<datalink::NetworkInterface>, &'static str> {
     let interfaces = datalink::interfaces();
-    if interfaces.len() == 0 {
+    if interfaces.is_empty() {
         return Err("No interface found");
     }
     Ok(interfaces)
@@ -106,16 +247,98 @@ fn is_valid_pnet_interface(pnet_int: &datalink::NetworkInterface) -> bool {
         return false;
     }
     for ip in &pnet_int.ips {
-        if let net::IpAddr::V4(_) = ip.ip() {
-            if let net::IpAddr::V4(_) = ip.mask() {
-                return true;
-            }
+        if ip.ip().is_ipv4() && ip.mask().is_ipv4() {
+            return true;
         }
     }
     false
 }
 
-pub fn create_interfaces<'a>(
+/// the kind of network the link of `pnet_int` attaches to, from the flags of the device.
+fn get_pnet_network_type(pnet_int: &datalink::NetworkInterface) -> InterfaceNetworkType {
+    if pnet_int.is_point_to_point() {
+        InterfaceNetworkType::PointToPoint
+    } else if pnet_int.is_multicast() {
+        InterfaceNetworkType::PointToMultipoint
+    } else if pnet_int.is_broadcast() {
+        InterfaceNetworkType::Broadcast
+    } else {
+        InterfaceNetworkType::NBMA
+    }
+}
+
+/// ask for the window a key may be used in for `usage`, as seconds since the unix epoch.
+fn prompt_key_lifetime(usage: &str) -> KeyLifetime {
+    let start = prompt_and_read(&format!(
+        "please input the {} lifetime start in unix seconds(default is always):",
+        usage
+    ))
+    .parse()
+    .ok();
+    let end = prompt_and_read(&format!(
+        "please input the {} lifetime end in unix seconds(default is never):",
+        usage
+    ))
+    .parse()
+    .ok();
+    KeyLifetime::from_unix_secs(start, end)
+}
+
+/// ask for the key chain a cryptographically authenticated interface uses.
+/// a chain met for the first time is filled with keys, the auth key being the first one,
+/// each with its own algorithm and send and accept lifetimes so keys can be rolled over.
+fn prompt_key_chain(router: Arc<Mutex<router::Router>>, auth_key: u64) -> Arc<Mutex<KeyChain>> {
+    let name = prompt_and_read(&format!(
+        "please input the auth key chain(default is {}):",
+        DEFAULT_AUTH_KEY_CHAIN
+    ));
+    let name = if name.is_empty() {
+        DEFAULT_AUTH_KEY_CHAIN
+    } else {
+        name.as_str()
+    };
+    let key_chain = router.lock().unwrap().get_key_chain(name);
+    if !key_chain.lock().unwrap().is_empty() {
+        return key_chain;
+    }
+    let mut auth_key = auth_key;
+    let mut default_key_id = DEFAULT_AUTH_KEY_ID;
+    loop {
+        let auth_algorithm = prompt_and_read(&format!(
+            "please input the auth algorithm(default is {},md5/hmac-sha-1/hmac-sha-256/hmac-sha-384/hmac-sha-512):",
+            DEFAULT_AUTH_ALGORITHM
+        ))
+        .parse()
+        .unwrap_or(DEFAULT_AUTH_ALGORITHM);
+        let auth_key_id = prompt_and_read(&format!(
+            "please input the auth key id(default is {}):",
+            default_key_id
+        ))
+        .parse()
+        .unwrap_or(default_key_id);
+        let send_lifetime = prompt_key_lifetime("send");
+        let accept_lifetime = prompt_key_lifetime("accept");
+        key_chain.lock().unwrap().add_key(AuthenticationKey::new(
+            auth_key_id,
+            &auth_key.to_be_bytes(),
+            Arc::new(auth_algorithm),
+            send_lifetime,
+            accept_lifetime,
+        ));
+        auth_key = match prompt_and_read(
+            "please input the next auth key of the chain(press enter to finish):",
+        )
+        .parse()
+        {
+            Ok(auth_key) => auth_key,
+            Err(_) => break,
+        };
+        default_key_id = auth_key_id.wrapping_add(1);
+    }
+    key_chain
+}
+
+pub fn create_interfaces(
     router: Arc<Mutex<router::Router>>,
 ) -> Result<HashMap<String, Arc<Mutex<interface::Interface>>>, &'static str> {
     let pnet_ints = detect_pnet_interface()?;
@@ -180,11 +403,23 @@ pub fn create_interfaces<'a>(
         ))
         .parse()
         .unwrap_or(DEFAULT_AUTH_KEY);
+        let auth_key_chain = if auth_type == AUTH_TYPE_CRYPTOGRAPHIC as u32 {
+            Some(prompt_key_chain(router.clone(), auth_key))
+        } else {
+            None
+        };
+
+        let nbma_neighbors = if matches!(get_pnet_network_type(&int), InterfaceNetworkType::NBMA) {
+            prompt_and_read("please input the neighbors of the NBMA network(separated by ','):")
+                .split(',')
+                .filter_map(|neighbor| neighbor.trim().parse().ok())
+                .collect()
+        } else {
+            Vec::new()
+        };
 
-        if let Some(int) = Interface::from_pnet_interface(
-            router.clone(),
-            &int,
-            net::Ipv4Addr::from(area_id),
+        let config = InterfaceConfig {
+            area_id: net::Ipv4Addr::from(area_id),
             output_cost,
             rxmt_interval,
             inf_trans_delay,
@@ -193,7 +428,11 @@ pub fn create_interfaces<'a>(
             router_dead_interval,
             auth_type,
             auth_key,
-        ) {
+            auth_key_chain,
+            nbma_neighbors,
+            transit_area_id: None,
+        };
+        if let Some(int) = Interface::from_pnet_interface(router.clone(), &int, config) {
             ints.insert(int.name.clone(), Arc::new(Mutex::new(int)));
         }
     }
@@ -201,91 +440,766 @@ pub fn create_interfaces<'a>(
     Ok(ints)
 }
 
-impl Interface {
-    pub const INNER_PACKET_QUEUE_SIZE: u32 = 128;
+/// feed `event` to the state machine of the interface and take the actions of the transition.
+/// the interface must not be locked by the caller. returns the state the interface ends in.
+pub fn handle_event(
+    interface: &Arc<Mutex<Interface>>,
+    event: event::InterfaceEvent,
+) -> status::InterfaceStatus {
+    let mut locked_interface = interface.lock().unwrap();
+    apply_event(interface, &mut locked_interface, event)
+}
 
-    pub fn update_neighbors(&mut self, new_neighbors: Arc<Mutex<HashMap<net::Ipv4Addr, Neighbor>>>) {
-        let mut locked_neighbors = self.neighbors.lock().unwrap();
-        let new_neighbors = new_neighbors.lock().unwrap();
-        for (k, v) in new_neighbors.iter() {
-            // locked_neighbors.insert(*k, v.clone());
+/// like `handle_event`, for callers already holding the lock of the interface.
+pub(crate) fn apply_event(
+    interface: &Arc<Mutex<Interface>>,
+    locked_interface: &mut Interface,
+    event: event::InterfaceEvent,
+) -> status::InterfaceStatus {
+    let old_status = locked_interface.status;
+    let (new_status, actions) = match old_status.transition(
+        event,
+        locked_interface.network_type,
+        locked_interface.router_priority,
+    ) {
+        Some(transition) => transition,
+        None => {
+            crate::debug(&format!(
+                "interface {} ignored event {:?} in state {:?}.",
+                locked_interface.name, event, old_status
+            ));
+            return old_status;
+        }
+    };
+    let old_designated_router = locked_interface.designated_router;
+    locked_interface.set_status(new_status);
+    for action in actions {
+        match action {
+            event::InterfaceAction::StartHelloTimer => {
+                let handle =
+                    tokio::spawn(handle::create_hello_packet_raw_handle(interface.clone()));
+                if let Some(old_handle) =
+                    locked_interface.produce_hello_packet_handle.replace(handle)
+                {
+                    old_handle.abort();
+                }
+            }
+            event::InterfaceAction::StartWaitTimer => {
+                let handle = tokio::spawn(handle::wait_timer_raw_handle(
+                    interface.clone(),
+                    locked_interface.router_dead_interval,
+                ));
+                if let Some(old_handle) = locked_interface.wait_timer_handle.replace(handle) {
+                    old_handle.abort();
+                }
+            }
+            event::InterfaceAction::ElectDesignatedRouter => {
+                // a BDR seen ends the Waiting state before the wait timer fires.
+                if let Some(handle) = locked_interface.wait_timer_handle.take() {
+                    handle.abort();
+                }
+                if locked_interface.elect_designated_router() {
+                    // the adjacencies to keep depend on who the DR and BDR are.
+                    for neighbor_key in locked_interface.get_two_way_neighbor_keys() {
+                        neighbor::apply_event(
+                            interface,
+                            locked_interface,
+                            neighbor_key,
+                            neighbor::event::NeighborEvent::AdjOk,
+                        );
+                    }
+                }
+            }
+            event::InterfaceAction::ResetInterface => locked_interface.reset(),
+            event::InterfaceAction::KillNeighbors => {
+                let neighbor_keys: Vec<net::Ipv4Addr> = locked_interface
+                    .neighbors
+                    .lock()
+                    .unwrap()
+                    .keys()
+                    .copied()
+                    .collect();
+                for neighbor_key in neighbor_keys {
+                    neighbor::apply_event(
+                        interface,
+                        locked_interface,
+                        neighbor_key,
+                        neighbor::event::NeighborEvent::KillNbr,
+                    );
+                }
+                locked_interface.neighbors.lock().unwrap().clear();
+            }
+        }
+    }
+    if old_status == status::InterfaceStatus::Down
+        && locked_interface.status != status::InterfaceStatus::Down
+    {
+        start_nbma_neighbors(interface, locked_interface);
+    }
+    crate::log(&format!(
+        "interface {} {:?}: {:?} -> {:?}.",
+        locked_interface.name, event, old_status, locked_interface.status
+    ));
+    // the router-lsa describes the interface by its state and its DR,
+    // the network-lsa is originated while the interface is the DR (RFC 2328 12.4).
+    if old_status != locked_interface.status
+        || old_designated_router != locked_interface.designated_router
+    {
+        if let Some(area) = &locked_interface.area {
+            area.lock().unwrap().trigger_router_lsa_origination();
         }
     }
+    if old_status != locked_interface.status {
+        locked_interface.trigger_network_lsa_origination();
+    }
+    locked_interface.status
+}
+
+/// raise Start for the configured neighbors of an NBMA interface that just came up,
+/// hellos are then sent to each of them until they answer (RFC 2328 9.5.1).
+fn start_nbma_neighbors(interface: &Arc<Mutex<Interface>>, locked_interface: &mut Interface) {
+    if !matches!(locked_interface.network_type, InterfaceNetworkType::NBMA) {
+        return;
+    }
+    let nbma_neighbors = locked_interface.nbma_neighbors.clone();
+    for ip_addr in nbma_neighbors {
+        locked_interface
+            .neighbors
+            .lock()
+            .unwrap()
+            .entry(ip_addr)
+            .or_insert_with(|| Neighbor::new(ip_addr));
+        neighbor::apply_event(
+            interface,
+            locked_interface,
+            ip_addr,
+            neighbor::event::NeighborEvent::Start,
+        );
+    }
+}
+
+/// the receive procedure of a hello sent by `source` on the interface (RFC 2328 10.5).
+/// the hello is dropped if its parameters do not match those of the interface, otherwise
+/// its sender is learned or updated and the neighbor and interface events it implies are raised.
+pub fn handle_hello_packet(
+    interface: &Arc<Mutex<Interface>>,
+    source: net::Ipv4Addr,
+    hello_packet: &hello::HelloPacket,
+) -> Result<(), HelloError> {
+    let mut locked_interface = interface.lock().unwrap();
+    if let Err(e) = locked_interface.check_hello_packet(hello_packet) {
+        locked_interface.statistics.lock().unwrap().hello_errors += 1;
+        return Err(e);
+    }
+    let router_id = net::Ipv4Addr::from(hello_packet.header.router_id);
+    let neighbor_key = locked_interface.get_neighbor_key(source, router_id);
+    let source_bits = u32::from(source);
+    let declared_designated_router = net::Ipv4Addr::from(hello_packet.designated_router);
+    let declared_backup_designated_router =
+        net::Ipv4Addr::from(hello_packet.backup_designated_router);
+    let neighbors = locked_interface.neighbors.clone();
+    let mut locked_neighbors = neighbors.lock().unwrap();
+    let neighbor = locked_neighbors
+        .entry(neighbor_key)
+        .or_insert_with(|| Neighbor::new(source));
+    let is_new_neighbor = neighbor.router_id.is_unspecified();
+    let old_priority = neighbor.priority;
+    let old_designated_router = neighbor.designated_router;
+    let old_backup_designated_router = neighbor.backup_designated_router;
+    neighbor.ipv4_addr = source;
+    neighbor.router_id = router_id;
+    neighbor.options = hello_packet.options;
+    neighbor.priority = hello_packet.rtr_pri;
+    neighbor.designated_router = declared_designated_router;
+    neighbor.backup_designated_router = declared_backup_designated_router;
+    drop(locked_neighbors);
+    if is_new_neighbor {
+        crate::log(&format!(
+            "interface {} learned neighbor {} at {}.",
+            locked_interface.name, router_id, source
+        ));
+    }
+
+    neighbor::apply_event(
+        interface,
+        &mut locked_interface,
+        neighbor_key,
+        neighbor::event::NeighborEvent::HelloReceived,
+    );
+    if !hello_packet.neighbors.contains(&locked_interface.router_id) {
+        neighbor::apply_event(
+            interface,
+            &mut locked_interface,
+            neighbor_key,
+            neighbor::event::NeighborEvent::OneWayReceived,
+        );
+        return Ok(());
+    }
+    neighbor::apply_event(
+        interface,
+        &mut locked_interface,
+        neighbor_key,
+        neighbor::event::NeighborEvent::TwoWayReceived,
+    );
+
+    let was_designated_router = old_designated_router == source;
+    let is_designated_router = hello_packet.designated_router == source_bits;
+    let was_backup_designated_router = old_backup_designated_router == source;
+    let is_backup_designated_router = hello_packet.backup_designated_router == source_bits;
+    let neighbor_change = (!is_new_neighbor && old_priority != hello_packet.rtr_pri)
+        || was_designated_router != is_designated_router
+        || was_backup_designated_router != is_backup_designated_router;
+    if locked_interface.is_backup_seen(source, hello_packet) {
+        apply_event(
+            interface,
+            &mut locked_interface,
+            event::InterfaceEvent::BackupSeen,
+        );
+    } else if neighbor_change {
+        apply_event(
+            interface,
+            &mut locked_interface,
+            event::InterfaceEvent::NeighborChange,
+        );
+    }
+    Ok(())
+}
+
+impl Interface {
+    pub const INNER_PACKET_QUEUE_SIZE: u32 = 128;
 
     pub fn get_neighbors(&self) -> Arc<Mutex<HashMap<net::Ipv4Addr, Neighbor>>> {
         self.neighbors.clone()
     }
 
+    pub fn get_statistics(&self) -> InterfaceStatistics {
+        *self.statistics.lock().unwrap()
+    }
+
+    pub fn get_authentication(&self) -> Result<auth::Authentication, AuthenticationError> {
+        auth::Authentication::new(self.auth_type, self.auth_key, self.auth_key_chain.clone())
+    }
+
     pub fn get_area_id(&self) -> net::Ipv4Addr {
         self.aread_id
     }
-    /// init the interfaces' handlers
-    pub async fn init_handlers(
-        &mut self,
+
+    pub fn get_ingress(&self) -> IngressInterface {
+        IngressInterface {
+            name: self.name.clone(),
+            ip_addr: self.ip_addr,
+            network_mask: self.network_mask,
+            network_type: self.network_type,
+        }
+    }
+
+    /// ospf packets are multicast on broadcast and point-to-point networks only (RFC 2328 8.1).
+    pub fn is_multicast_capable(&self) -> bool {
+        matches!(
+            self.network_type,
+            InterfaceNetworkType::Broadcast | InterfaceNetworkType::PointToPoint
+        )
+    }
+
+    /// change the state of the interface and keep its multicast memberships in line:
+    /// AllSPFRouters while it is up, AllDRouters while it is the DR or the backup DR.
+    /// the wait timer is stopped once the interface leaves Waiting.
+    pub fn set_status(&mut self, status: status::InterfaceStatus) {
+        self.status = status;
+        if status != status::InterfaceStatus::Waiting {
+            if let Some(handle) = self.wait_timer_handle.take() {
+                handle.abort();
+            }
+        }
+        let ospf_socket = match &self.ospf_socket {
+            Some(ospf_socket) => ospf_socket,
+            None => return,
+        };
+        let multicast_capable = self.is_multicast_capable();
+        let is_up = status != status::InterfaceStatus::Down;
+        let is_dr_or_backup = matches!(
+            status,
+            status::InterfaceStatus::DR | status::InterfaceStatus::Backup
+        );
+        if let Err(e) = ospf_socket.set_group_membership(AllSPFRouters, multicast_capable && is_up)
+        {
+            crate::error(&format!(
+                "interface {} update AllSPFRouters membership failed: {}.",
+                self.name, e
+            ));
+        }
+        if let Err(e) = ospf_socket
+            .set_group_membership(AllDRouters, multicast_capable && is_up && is_dr_or_backup)
+        {
+            crate::error(&format!(
+                "interface {} update AllDRouters membership failed: {}.",
+                self.name, e
+            ));
+        }
+    }
+    /// init the interfaces' handlers: open its socket and start sending and receiving.
+    /// the interface must not be locked by the caller.
+    pub fn init_handlers(
+        interface: &Arc<Mutex<Interface>>,
         router_id: net::Ipv4Addr,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let hello_interval = self.hello_interval as u16;
-        let options = 0;
-        let area_id = self.aread_id;
-        let network_mask = self.network_mask;
-        let router_priority = self.router_priority as u8;
-        let router_dead_interval = self.router_dead_interval;
-        let neighbors = self.neighbors.clone();
-
-        let (udp_tx, udp_rx) =
-            transport::transport_channel(1500, transport::TransportChannelType::Layer3(Udp))
-                .unwrap();
-        let (tcp_tx, tcp_rx) =
-            transport::transport_channel(1500, transport::TransportChannelType::Layer3(Tcp))
-                .unwrap();
-        let (send_tcp_tx, send_tcp_rx) = broadcast::channel::<bytes::Bytes>(128);
-        let (send_udp_tx, send_udp_rx) = broadcast::channel::<bytes::Bytes>(128);
-        tokio::spawn(handle::send_tcp_packet_raw_handle(send_tcp_rx, tcp_tx));
-        tokio::spawn(handle::send_udp_packet_raw_handle(send_udp_rx, udp_tx));
-        tokio::spawn(handle::recv_tcp_packet_raw_handle(
-            send_tcp_tx.clone(),
-            tcp_rx,
-        ));
-        tokio::spawn(handle::recv_udp_packet_raw_handle(
-            send_udp_tx.clone(),
-            udp_rx,
+        let mut locked_interface = interface.lock().unwrap();
+        let authentication = locked_interface.get_authentication()?;
+        locked_interface.router_id = router_id;
+        locked_interface.authentication = Some(authentication.clone());
+
+        let ospf_socket = Arc::new(socket::OspfSocket::new(
+            &locked_interface.name,
+            locked_interface.ip_addr,
+        )?);
+        locked_interface.ospf_socket = Some(ospf_socket.clone());
+        let (send_ospf_tx, send_ospf_rx) = broadcast::channel::<bytes::Bytes>(128);
+        locked_interface.send_packet_tx = Some(send_ospf_tx);
+        locked_interface.send_packet_handle = Some(tokio::spawn(
+            handle::send_ospf_packet_raw_handle(send_ospf_rx, ospf_socket.clone()),
         ));
-        tokio::spawn(handle::create_hello_packet_raw_handle(
-            send_udp_tx.clone(),
-            hello_interval,
-            network_mask,
-            options,
-            router_id.into(),
-            area_id.into(),
-            router_priority,
-            router_dead_interval,
+        locked_interface.recv_packet_handle =
+            Some(tokio::spawn(handle::recv_ospf_packet_raw_handle(
+                ospf_socket,
+                interface.clone(),
+                locked_interface.get_ingress(),
+                locked_interface.statistics.clone(),
+                authentication,
+            )));
+        Ok(())
+    }
+
+    /// whether a hello received in state Waiting shows the segment already has a BDR,
+    /// so the election need not wait any longer (RFC 2328 10.5): its sender declares
+    /// itself BDR, or declares itself DR with no BDR.
+    pub fn is_backup_seen(&self, source: net::Ipv4Addr, hello_packet: &hello::HelloPacket) -> bool {
+        if self.status != status::InterfaceStatus::Waiting {
+            return false;
+        }
+        let source = u32::from(source);
+        hello_packet.backup_designated_router == source
+            || (hello_packet.designated_router == source
+                && hello_packet.backup_designated_router == 0)
+    }
+
+    /// the options the router sets in the packets it sends on the interface.
+    pub fn get_options(&self) -> u8 {
+        let external_routing_capable = self
+            .area
+            .as_ref()
+            .is_none_or(|area| area.lock().unwrap().is_external_routing_capable());
+        if external_routing_capable {
+            OPTION_BIT_E
+        } else {
+            0
+        }
+    }
+
+    /// the router ids to list in the hellos sent on the interface: every neighbor
+    /// a hello has been received from within the RouterDeadInterval.
+    pub fn get_hello_neighbors(&self) -> Vec<net::Ipv4Addr> {
+        self.neighbors
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|neighbor| neighbor.status >= neighbor::status::NeighborStatus::Init)
+            .map(|neighbor| neighbor.router_id)
+            .collect()
+    }
+
+    /// check the parameters of a received hello against those of the interface (RFC 2328 10.5).
+    /// the network mask is not checked on point-to-point networks and virtual links.
+    pub fn check_hello_packet(&self, hello_packet: &hello::HelloPacket) -> Result<(), HelloError> {
+        let area_id = net::Ipv4Addr::from(hello_packet.header.area_id);
+        if area_id != self.aread_id {
+            return Err(HelloError::AreaMismatch(area_id));
+        }
+        if !matches!(
+            self.network_type,
+            InterfaceNetworkType::PointToPoint | InterfaceNetworkType::VirtualLink
+        ) && hello_packet.network_mask != self.network_mask
+        {
+            return Err(HelloError::NetworkMaskMismatch(hello_packet.network_mask));
+        }
+        if hello_packet.hello_interval as u32 != self.hello_interval {
+            return Err(HelloError::HelloIntervalMismatch(
+                hello_packet.hello_interval,
+            ));
+        }
+        if hello_packet.router_dead_interval != self.router_dead_interval {
+            return Err(HelloError::RouterDeadIntervalMismatch(
+                hello_packet.router_dead_interval,
+            ));
+        }
+        if hello_packet.options & OPTION_BIT_E != self.get_options() & OPTION_BIT_E {
+            return Err(HelloError::ExternalRoutingMismatch(hello_packet.options));
+        }
+        Ok(())
+    }
+
+    /// the header of a packet of type `packet_type` sent by the router on the interface,
+    /// its length, checksum and authentication are filled in when sealing.
+    pub fn new_packet_header(&self, packet_type: u8) -> OspfPacketHeader {
+        OspfPacketHeader::new(
+            OSPF_VERSION_2,
+            packet_type,
+            OspfPacketHeader::length() as u16,
+            self.router_id.into(),
+            self.aread_id.into(),
             0,
             0,
+            0,
+        )
+    }
+
+    /// seal `ospf_packet` with the authentication of the interface and wrap it
+    /// in an ip packet to `destination`, ready to go.
+    pub fn create_ip_packet(
+        &self,
+        destination: net::Ipv4Addr,
+        ospf_packet: &mut dyn OspfPacket,
+    ) -> Result<bytes::Bytes, Box<dyn std::error::Error>> {
+        let authentication = self
+            .authentication
+            .as_ref()
+            .ok_or("interface handlers not initialized")?;
+        let ospf_packet_bytes = authentication.seal(ospf_packet)?;
+        let mut ip_packet_buffer = vec![0u8; MTU];
+        let ip_packet = new_ip_packet(
+            ip_packet_buffer.as_mut_slice(),
             self.ip_addr,
-            AllSPFRouters,
-            neighbors,
-        ));
-        Ok(())
+            destination,
+            ospf_packet_bytes,
+        )?;
+        Ok(bytes::Bytes::copy_from_slice(
+            &ip_packet.packet()[..ip_packet.get_total_length() as usize],
+        ))
+    }
+
+    /// hand an ip packet to the send handler of the interface. returns whether it was queued.
+    pub fn send_ip_packet(&self, ip_packet: bytes::Bytes) -> bool {
+        match &self.send_packet_tx {
+            Some(send_packet_tx) => send_packet_tx.send(ip_packet).is_ok(),
+            None => {
+                crate::error("interface has no packet sender, packet not sent.");
+                false
+            }
+        }
+    }
+
+    /// build the hello to send on the interface to `destination`, as an ip packet ready to go.
+    pub fn create_hello_ip_packet(
+        &self,
+        destination: net::Ipv4Addr,
+    ) -> Result<bytes::Bytes, Box<dyn std::error::Error>> {
+        let mut hello_packet = hello::HelloPacket::new(
+            self.network_mask,
+            self.hello_interval as u16,
+            self.get_options(),
+            self.router_priority as u8,
+            self.router_dead_interval,
+            self.designated_router.into(),
+            self.backup_designated_router.into(),
+            self.new_packet_header(HELLO_PACKET_TYPE),
+            self.get_hello_neighbors(),
+        );
+        self.create_ip_packet(destination, &mut hello_packet)
+    }
+
+    /// the DR/BDR election of RFC 2328 9.4, it sets the state of the interface.
+    /// returns whether the DR or the BDR changed.
+    pub fn elect_designated_router(&mut self) -> bool {
+        let myself = election::ElectionCandidate {
+            router_id: self.router_id,
+            ip_addr: self.ip_addr,
+            priority: self.router_priority as u8,
+            designated_router: self.designated_router,
+            backup_designated_router: self.backup_designated_router,
+        };
+        let neighbors: Vec<election::ElectionCandidate> = self
+            .neighbors
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(election::ElectionCandidate::from_neighbor)
+            .collect();
+        let (designated_router, backup_designated_router) = election::elect(myself, &neighbors);
+        let changed = designated_router != self.designated_router
+            || backup_designated_router != self.backup_designated_router;
+        self.designated_router = designated_router;
+        self.backup_designated_router = backup_designated_router;
+        if designated_router == self.ip_addr {
+            self.set_status(status::InterfaceStatus::DR);
+        } else if backup_designated_router == self.ip_addr {
+            self.set_status(status::InterfaceStatus::Backup);
+        } else {
+            self.set_status(status::InterfaceStatus::DRother);
+        }
+        changed
+    }
+
+    /// send the periodic hello of the interface: multicast to AllSPFRouters, except on
+    /// NBMA networks where it goes to each neighbor that is not down (RFC 2328 9.5.1).
+    pub fn send_hellos(&self) {
+        if !matches!(self.network_type, InterfaceNetworkType::NBMA) {
+            self.send_hello(AllSPFRouters);
+            return;
+        }
+        let destinations: Vec<net::Ipv4Addr> = self
+            .neighbors
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|neighbor| neighbor.status != NeighborStatus::Down)
+            .map(|neighbor| neighbor.ipv4_addr)
+            .collect();
+        for destination in destinations {
+            self.send_hello(destination);
+        }
+    }
+
+    /// send a hello to `destination` right away, out of the hello timer.
+    pub fn send_hello(&self, destination: net::Ipv4Addr) {
+        match self.create_hello_ip_packet(destination) {
+            Ok(hello_packet_bytes) => {
+                if self.send_ip_packet(hello_packet_bytes) {
+                    crate::debug("send hello packet success.");
+                } else {
+                    crate::error("send hello packet failed.");
+                }
+            }
+            Err(e) => {
+                crate::error(&format!("create hello packet failed: {}.", e));
+            }
+        }
+    }
+
+    /// where the packets for `neighbor` are sent: AllSPFRouters on point-to-point
+    /// networks, the address of the neighbor otherwise.
+    pub fn get_neighbor_destination(&self, neighbor: &Neighbor) -> net::Ipv4Addr {
+        match self.network_type {
+            InterfaceNetworkType::PointToPoint => AllSPFRouters,
+            _ => neighbor.ipv4_addr,
+        }
+    }
+
+    /// the key of the neighbor sending from `source` with router id `router_id` in the
+    /// neighbors map: neighbors are told apart by their router id on point-to-point links
+    /// and virtual links, and by their address on the other networks (RFC 2328 10.5).
+    pub fn get_neighbor_key(
+        &self,
+        source: net::Ipv4Addr,
+        router_id: net::Ipv4Addr,
+    ) -> net::Ipv4Addr {
+        match self.network_type {
+            InterfaceNetworkType::PointToPoint | InterfaceNetworkType::VirtualLink => router_id,
+            _ => source,
+        }
+    }
+
+    /// the keys of the neighbors with which bidirectional communication is established.
+    pub fn get_two_way_neighbor_keys(&self) -> Vec<net::Ipv4Addr> {
+        self.neighbors
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, neighbor)| neighbor.status.is_two_way())
+            .map(|(neighbor_key, _)| *neighbor_key)
+            .collect()
+    }
+
+    /// whether an adjacency should be formed with `neighbor` (RFC 2328 10.4).
+    pub fn is_adjacency_wanted(&self, neighbor: &Neighbor) -> bool {
+        matches!(
+            self.network_type,
+            InterfaceNetworkType::PointToPoint
+                | InterfaceNetworkType::PointToMultipoint
+                | InterfaceNetworkType::VirtualLink
+        ) || matches!(
+            self.status,
+            status::InterfaceStatus::DR | status::InterfaceStatus::Backup
+        ) || neighbor.ipv4_addr == self.designated_router
+            || neighbor.ipv4_addr == self.backup_designated_router
+    }
+
+    /// ask for the network-lsa of the interface to be originated again, or flushed,
+    /// as the DR role or the full adjacencies changed (RFC 2328 12.4).
+    pub fn trigger_network_lsa_origination(&self) {
+        self.network_lsa_trigger.notify_one();
+    }
+
+    /// the trigger the network-lsa origination waits on.
+    pub fn get_network_lsa_trigger(&self) -> Arc<Notify> {
+        self.network_lsa_trigger.clone()
+    }
+
+    /// whether the interface is a virtual link through `area_id` with a full adjacency,
+    /// the router-lsa of the transit area then has its V bit set (RFC 2328 12.4.1).
+    pub fn is_full_virtual_link_through(&self, area_id: net::Ipv4Addr) -> bool {
+        matches!(self.network_type, InterfaceNetworkType::VirtualLink)
+            && self.transit_area_id == Some(area_id)
+            && self
+                .neighbors
+                .lock()
+                .unwrap()
+                .values()
+                .any(|neighbor| neighbor.status == NeighborStatus::Full)
+    }
+
+    /// the links the interface puts in the router-lsa of its area (RFC 2328 12.4.1).
+    /// a down interface has none, a loopback is a host route, and a network with
+    /// a DR we are fully adjacent to is a transit network, else a stub network.
+    pub fn get_router_links(&self) -> Vec<RouterLink> {
+        let ip_addr = u32::from(self.ip_addr);
+        let network_mask = u32::from(self.network_mask);
+        let cost = self.output_cost.min(u16::MAX as u32) as u16;
+        let stub_link = RouterLink::new(
+            ip_addr & network_mask,
+            network_mask,
+            ROUTER_LINK_TYPE_STUB,
+            cost,
+        );
+        let neighbors = self.neighbors.lock().unwrap();
+        let full_neighbors = neighbors
+            .values()
+            .filter(|neighbor| neighbor.status == NeighborStatus::Full);
+        match self.status {
+            status::InterfaceStatus::Down => return Vec::new(),
+            status::InterfaceStatus::Loopback => {
+                return vec![RouterLink::new(ip_addr, u32::MAX, ROUTER_LINK_TYPE_STUB, 0)]
+            }
+            _ => {}
+        }
+        match self.network_type {
+            InterfaceNetworkType::PointToPoint => {
+                let mut links: Vec<_> = full_neighbors
+                    .map(|neighbor| {
+                        RouterLink::new(
+                            u32::from(neighbor.router_id),
+                            ip_addr,
+                            ROUTER_LINK_TYPE_POINT_TO_POINT,
+                            cost,
+                        )
+                    })
+                    .collect();
+                links.push(stub_link);
+                links
+            }
+            InterfaceNetworkType::PointToMultipoint => {
+                let mut links: Vec<_> = full_neighbors
+                    .map(|neighbor| {
+                        RouterLink::new(
+                            u32::from(neighbor.router_id),
+                            ip_addr,
+                            ROUTER_LINK_TYPE_POINT_TO_POINT,
+                            cost,
+                        )
+                    })
+                    .collect();
+                links.push(RouterLink::new(ip_addr, u32::MAX, ROUTER_LINK_TYPE_STUB, 0));
+                links
+            }
+            InterfaceNetworkType::VirtualLink => full_neighbors
+                .map(|neighbor| {
+                    RouterLink::new(
+                        u32::from(neighbor.router_id),
+                        ip_addr,
+                        ROUTER_LINK_TYPE_VIRTUAL,
+                        cost,
+                    )
+                })
+                .collect(),
+            InterfaceNetworkType::Broadcast | InterfaceNetworkType::NBMA => {
+                let mut full_neighbors = full_neighbors;
+                let is_transit = match self.status {
+                    status::InterfaceStatus::Waiting => false,
+                    status::InterfaceStatus::DR => full_neighbors.next().is_some(),
+                    _ => {
+                        full_neighbors.any(|neighbor| neighbor.ipv4_addr == self.designated_router)
+                    }
+                };
+                if is_transit {
+                    vec![RouterLink::new(
+                        u32::from(self.designated_router),
+                        ip_addr,
+                        ROUTER_LINK_TYPE_TRANSIT,
+                        cost,
+                    )]
+                } else {
+                    vec![stub_link]
+                }
+            }
+        }
+    }
+
+    /// the headers of every lsa in the database of the area the interface belongs to.
+    pub fn get_lsa_headers(&self) -> Vec<LinkStateAdvertisementHeader> {
+        match &self.area {
+            Some(area) => area.lock().unwrap().get_lsa_headers(),
+            None => Vec::new(),
+        }
+    }
+
+    /// the header of the instance of the lsa described by `lsa_header` in the database
+    /// of the area the interface belongs to.
+    pub fn lookup_lsa_header(
+        &self,
+        lsa_header: &LinkStateAdvertisementHeader,
+    ) -> Option<LinkStateAdvertisementHeader> {
+        self.area
+            .as_ref()
+            .and_then(|area| area.lock().unwrap().lookup_lsa_header(lsa_header))
+    }
+
+    /// a copy of the instance of the lsa `key` in the database of the area
+    /// the interface belongs to.
+    pub fn get_lsa(
+        &self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        self.area
+            .as_ref()
+            .and_then(|area| area.lock().unwrap().get_lsa(key))
+    }
+
+    /// the instance of the lsa requested by `lsr` in the database of the area
+    /// the interface belongs to.
+    pub fn lookup_lsa(
+        &self,
+        lsr: &LinkStatusRequest,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        self.area
+            .as_ref()
+            .and_then(|area| area.lock().unwrap().lookup_lsa(lsr))
+    }
+
+    /// reset the variables of the interface and disable its timers.
+    pub fn reset(&mut self) {
+        self.designated_router = net::Ipv4Addr::UNSPECIFIED;
+        self.backup_designated_router = net::Ipv4Addr::UNSPECIFIED;
+        if let Some(handle) = self.produce_hello_packet_handle.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.wait_timer_handle.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.delayed_ack_handle.take() {
+            handle.abort();
+        }
+        self.delayed_acks.clear();
     }
+
     pub fn from_pnet_interface(
         router: Arc<Mutex<router::Router>>,
         pnet_int: &datalink::NetworkInterface,
-        aread_id: net::Ipv4Addr,
-        output_cost: u32,
-        rxmt_interval: u32,
-        inf_trans_delay: u32,
-        router_prioriry: u32,
-        hello_interval: u32,
-        router_dead_interval: u32,
-        auth_type: u32,
-        auth_key: u64,
+        config: InterfaceConfig,
     ) -> Option<Self> {
         if pnet_int.is_loopback() || !pnet_int.is_up() {
             return None;
         }
         let mut ip_addr = net::Ipv4Addr::new(255, 255, 255, 255); //false addr
         let mut network_mask = net::Ipv4Addr::new(255, 255, 255, 255);
-        let mut network_type = InterfaceNetworkType::Broadcast;
+        let network_type = get_pnet_network_type(pnet_int);
         let mut found_ip_flag = false;
         for ip in &pnet_int.ips {
             if let net::IpAddr::V4(taddr) = ip.ip() {
@@ -293,15 +1207,6 @@ impl Interface {
                     ip_addr = taddr;
                     network_mask = tmask;
                     found_ip_flag = true;
-                    if pnet_int.is_point_to_point() {
-                        network_type = InterfaceNetworkType::PointToPoint;
-                    } else if pnet_int.is_multicast() {
-                        network_type = InterfaceNetworkType::PointToMultipoint;
-                    } else if pnet_int.is_broadcast() {
-                        network_type = InterfaceNetworkType::Broadcast;
-                    } else {
-                        network_type = InterfaceNetworkType::NBMA;
-                    }
                     break;
                 }
             }
@@ -314,34 +1219,14 @@ impl Interface {
         println!("interface ipv4 addr: {}", ip_addr);
         println!("interface network mask: {}", network_mask);
 
-        let (inner_tx, inner_rx) = broadcast::channel::<Arc<Mutex<dyn OspfPacket + Send>>>(
-            Interface::INNER_PACKET_QUEUE_SIZE as usize,
-        );
-        let (trans_tx, trans_rx) =
-            transport::transport_channel(1500, transport::TransportChannelType::Layer3(Udp))
-                .unwrap();
-
-        let int = Self::new(
+        Some(Self::new(
+            name,
             ip_addr,
             network_mask,
-            aread_id,
-            output_cost,
-            rxmt_interval,
-            inf_trans_delay,
-            router_prioriry,
-            hello_interval,
-            router_dead_interval,
-            auth_type,
-            auth_key,
-            name,
             network_type,
-            inner_tx,
-            inner_rx,
-            trans_tx,
-            trans_rx,
+            config,
             router,
-        );
-        Some(int)
+        ))
     }
     pub fn set_hello_interval(&mut self, hello_interval: u32) {
         self.hello_interval = hello_interval;
@@ -357,50 +1242,219 @@ impl Interface {
     }
 
     pub fn new(
+        name: String,
         ip_addr: net::Ipv4Addr,
         network_mask: net::Ipv4Addr,
-        aread_id: net::Ipv4Addr,
-        output_cost: u32,
-        rxmt_interval: u32,
-        inf_trans_delay: u32,
-        router_prioriry: u32,
-        hello_interval: u32,
-        router_dead_interval: u32,
-        auth_type: u32,
-        auth_key: u64,
-        name: String,
         network_type: InterfaceNetworkType,
-        inner_tx: broadcast::Sender<Arc<Mutex<dyn crate::packet::OspfPacket + Send>>>,
-        inner_rx: broadcast::Receiver<Arc<Mutex<dyn crate::packet::OspfPacket + Send>>>,
-        trans_tx: transport::TransportSender,
-        trans_rx: transport::TransportReceiver,
+        config: InterfaceConfig,
         router: Arc<Mutex<router::Router>>,
     ) -> Self {
+        let (inner_tx, inner_rx) = broadcast::channel::<Arc<Mutex<dyn OspfPacket + Send>>>(
+            Interface::INNER_PACKET_QUEUE_SIZE as usize,
+        );
         Self {
             name,
             ip_addr,
             network_mask,
-            aread_id,
-            output_cost,
-            rxmt_interval,
-            inf_trans_delay,
-            router_priority: router_prioriry,
-            hello_interval,
-            router_dead_interval,
-            auth_type,
-            auth_key,
+            aread_id: config.area_id,
+            output_cost: config.output_cost,
+            rxmt_interval: config.rxmt_interval,
+            inf_trans_delay: config.inf_trans_delay,
+            router_priority: config.router_priority,
+            hello_interval: config.hello_interval,
+            router_dead_interval: config.router_dead_interval,
+            auth_type: config.auth_type,
+            auth_key: config.auth_key,
+            auth_key_chain: config.auth_key_chain,
+            nbma_neighbors: config.nbma_neighbors,
+            transit_area_id: config.transit_area_id,
             network_type,
             send_packet_handle: None,
             recv_packet_handle: None,
             produce_dd_packet_handle: None,
             produce_hello_packet_handle: None,
+            wait_timer_handle: None,
+            delayed_ack_handle: None,
+            delayed_acks: Vec::new(),
+            network_lsa_trigger: Arc::new(Notify::new()),
             neighbors: Arc::new(Mutex::new(HashMap::new())),
-            router: router,
+            router,
             inner_tx,
             inner_rx,
-            trans_rx,
-            trans_tx,
+            router_id: net::Ipv4Addr::UNSPECIFIED,
+            area: None,
+            transit_area: None,
             status: status::InterfaceStatus::Down,
+            designated_router: net::Ipv4Addr::UNSPECIFIED,
+            backup_designated_router: net::Ipv4Addr::UNSPECIFIED,
+            send_packet_tx: None,
+            authentication: None,
+            ospf_socket: None,
+            statistics: Arc::new(Mutex::new(InterfaceStatistics::default())),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    const IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 1);
+    const ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(1, 1, 1, 1);
+    const NEIGHBOR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 2);
+    const NEIGHBOR_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(2, 2, 2, 2);
+    const DR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 3);
+    const DR_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(3, 3, 3, 3);
+    const SUBNET: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 0);
+
+    /// the links as (link id, link data, link type, metric).
+    fn router_links(
+        interface: &Arc<Mutex<Interface>>,
+    ) -> Vec<(net::Ipv4Addr, net::Ipv4Addr, u8, u16)> {
+        interface
+            .lock()
+            .unwrap()
+            .get_router_links()
+            .into_iter()
+            .map(|link| {
+                (
+                    link.link_id.into(),
+                    link.link_data.into(),
+                    link.link_type,
+                    link.link_metric,
+                )
+            })
+            .collect()
+    }
+
+    fn stub_link() -> (net::Ipv4Addr, net::Ipv4Addr, u8, u16) {
+        (
+            SUBNET,
+            testing::NETWORK_MASK,
+            ROUTER_LINK_TYPE_STUB,
+            DEFAULT_OUTPUT_COST as u16,
+        )
+    }
+
+    fn transit_link(designated_router: net::Ipv4Addr) -> (net::Ipv4Addr, net::Ipv4Addr, u8, u16) {
+        (
+            designated_router,
+            IP_ADDR,
+            ROUTER_LINK_TYPE_TRANSIT,
+            DEFAULT_OUTPUT_COST as u16,
+        )
+    }
+
+    /// a broadcast interface in `status` with a DR at `designated_router`.
+    fn broadcast_interface(
+        status: status::InterfaceStatus,
+        designated_router: net::Ipv4Addr,
+    ) -> Arc<Mutex<Interface>> {
+        let (interface, _) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        let mut locked_interface = interface.lock().unwrap();
+        locked_interface.status = status;
+        locked_interface.designated_router = designated_router;
+        drop(locked_interface);
+        interface
+    }
+
+    #[test]
+    fn point_to_point_links() {
+        let (interface, _) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::PointToPoint);
+        interface.lock().unwrap().status = status::InterfaceStatus::PointToPoint;
+        // the subnet is reachable before the adjacency is full.
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Exchange,
+        );
+        assert_eq!(router_links(&interface), vec![stub_link()]);
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        assert_eq!(
+            router_links(&interface),
+            vec![
+                (
+                    NEIGHBOR_ROUTER_ID,
+                    IP_ADDR,
+                    ROUTER_LINK_TYPE_POINT_TO_POINT,
+                    DEFAULT_OUTPUT_COST as u16
+                ),
+                stub_link(),
+            ]
+        );
+    }
+
+    #[test]
+    fn waiting_network_is_a_stub() {
+        let interface = broadcast_interface(status::InterfaceStatus::Waiting, DR_IP_ADDR);
+        testing::add_neighbor(&interface, DR_IP_ADDR, DR_ROUTER_ID, NeighborStatus::Full);
+        assert_eq!(router_links(&interface), vec![stub_link()]);
+    }
+
+    #[test]
+    fn designated_router_is_transit_with_a_full_neighbor() {
+        let interface = broadcast_interface(status::InterfaceStatus::DR, IP_ADDR);
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Loading,
+        );
+        assert_eq!(router_links(&interface), vec![stub_link()]);
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        assert_eq!(router_links(&interface), vec![transit_link(IP_ADDR)]);
+    }
+
+    #[test]
+    fn dr_other_is_transit_when_full_with_the_designated_router() {
+        let interface = broadcast_interface(status::InterfaceStatus::DRother, DR_IP_ADDR);
+        // a full adjacency with the BDR only does not make the network transit.
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        testing::add_neighbor(
+            &interface,
+            DR_IP_ADDR,
+            DR_ROUTER_ID,
+            NeighborStatus::Exchange,
+        );
+        assert_eq!(router_links(&interface), vec![stub_link()]);
+        testing::add_neighbor(&interface, DR_IP_ADDR, DR_ROUTER_ID, NeighborStatus::Full);
+        assert_eq!(router_links(&interface), vec![transit_link(DR_IP_ADDR)]);
+    }
+
+    #[test]
+    fn loopback_is_a_host_route() {
+        let (interface, _) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        interface.lock().unwrap().status = status::InterfaceStatus::Loopback;
+        assert_eq!(
+            router_links(&interface),
+            vec![(IP_ADDR, net::Ipv4Addr::BROADCAST, ROUTER_LINK_TYPE_STUB, 0)]
+        );
+    }
+
+    #[test]
+    fn down_interface_has_no_link() {
+        let (interface, _) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        assert!(router_links(&interface).is_empty());
+    }
+}
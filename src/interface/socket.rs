@@ -0,0 +1,106 @@
+use std::{
+    collections::HashSet,
+    io::{self, Read},
+    net,
+    sync::Mutex,
+};
+
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use tokio::io::unix::AsyncFd;
+
+use crate::{OSPF_IP_PROTOCOL_NUMBER, OSPF_IP_TTL};
+
+/// # struct OspfSocket
+/// the raw socket of protocol 89 an interface sends and receives its ospf packets on.
+/// the ip header is built by ourselves, multicast goes out of the interface only
+/// and is not looped back to us. the socket is bound to the device of the interface,
+/// so it only receives the packets that arrived on it.
+/// - ip_addr : the address of the interface, used to pick the multicast egress and memberships.
+/// - groups : the multicast groups joined on the interface.
+pub struct OspfSocket {
+    socket: AsyncFd<Socket>,
+    ip_addr: net::Ipv4Addr,
+    groups: Mutex<HashSet<net::Ipv4Addr>>,
+}
+
+impl OspfSocket {
+    /// open the socket of the interface named `name`, must be called inside the tokio runtime.
+    pub fn new(name: &str, ip_addr: net::Ipv4Addr) -> io::Result<Self> {
+        let socket = Socket::new(
+            Domain::IPV4,
+            Type::RAW,
+            Some(Protocol::from(OSPF_IP_PROTOCOL_NUMBER as i32)),
+        )?;
+        socket.bind_device(Some(name.as_bytes()))?;
+        socket.set_header_included(true)?;
+        socket.set_multicast_if_v4(&ip_addr)?;
+        socket.set_multicast_loop_v4(false)?;
+        socket.set_multicast_ttl_v4(OSPF_IP_TTL as u32)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket: AsyncFd::new(socket)?,
+            ip_addr,
+            groups: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// send a whole ip packet, its header included.
+    pub async fn send_to(&self, ip_packet: &[u8], destination: net::Ipv4Addr) -> io::Result<usize> {
+        let destination = SockAddr::from(net::SocketAddrV4::new(destination, 0));
+        loop {
+            let mut guard = self.socket.writable().await?;
+            match guard.try_io(|socket| socket.get_ref().send_to(ip_packet, &destination)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// receive a whole ip packet, its header included.
+    pub async fn recv(&self, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let mut guard = self.socket.readable().await?;
+            match guard.try_io(|socket| socket.get_ref().read(buffer)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    pub fn join_group(&self, group: net::Ipv4Addr) -> io::Result<()> {
+        let mut groups = self.groups.lock().unwrap();
+        if groups.contains(&group) {
+            return Ok(());
+        }
+        self.socket
+            .get_ref()
+            .join_multicast_v4(&group, &self.ip_addr)?;
+        groups.insert(group);
+        Ok(())
+    }
+
+    pub fn leave_group(&self, group: net::Ipv4Addr) -> io::Result<()> {
+        let mut groups = self.groups.lock().unwrap();
+        if !groups.contains(&group) {
+            return Ok(());
+        }
+        self.socket
+            .get_ref()
+            .leave_multicast_v4(&group, &self.ip_addr)?;
+        groups.remove(&group);
+        Ok(())
+    }
+
+    /// join or leave `group`, doing nothing if the membership is already as wanted.
+    pub fn set_group_membership(&self, group: net::Ipv4Addr, member: bool) -> io::Result<()> {
+        if member {
+            self.join_group(group)
+        } else {
+            self.leave_group(group)
+        }
+    }
+
+    pub fn is_member_of(&self, group: net::Ipv4Addr) -> bool {
+        self.groups.lock().unwrap().contains(&group)
+    }
+}
@@ -1,3 +1,9 @@
+use super::{
+    event::{InterfaceAction, InterfaceEvent},
+    InterfaceNetworkType,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum InterfaceStatus {
     Down,
     Loopback,
@@ -8,4 +14,244 @@ pub enum InterfaceStatus {
     DR,
 }
 
-impl InterfaceStatus {}
+impl InterfaceStatus {
+    /// the interface state machine of RFC 2328 9.3.
+    /// returns the next state and the actions to take, or none if `event` means nothing
+    /// in the current state. after `ElectDesignatedRouter` the election decides the state,
+    /// the one returned is only the state it starts from.
+    pub fn transition(
+        self,
+        event: InterfaceEvent,
+        network_type: InterfaceNetworkType,
+        router_priority: u32,
+    ) -> Option<(InterfaceStatus, Vec<InterfaceAction>)> {
+        use InterfaceAction::*;
+        use InterfaceStatus::*;
+        match (self, event) {
+            (Down, InterfaceEvent::InterfaceUp) => match network_type {
+                InterfaceNetworkType::PointToPoint
+                | InterfaceNetworkType::PointToMultipoint
+                | InterfaceNetworkType::VirtualLink => Some((PointToPoint, vec![StartHelloTimer])),
+                _ if router_priority == 0 => Some((DRother, vec![StartHelloTimer])),
+                _ => Some((Waiting, vec![StartHelloTimer, StartWaitTimer])),
+            },
+            (Waiting, InterfaceEvent::BackupSeen) | (Waiting, InterfaceEvent::WaitTimer) => {
+                Some((Waiting, vec![ElectDesignatedRouter]))
+            }
+            (DRother, InterfaceEvent::NeighborChange)
+            | (Backup, InterfaceEvent::NeighborChange)
+            | (DR, InterfaceEvent::NeighborChange) => Some((self, vec![ElectDesignatedRouter])),
+            (Loopback, InterfaceEvent::UnloopInd) => Some((Down, vec![])),
+            (Down, InterfaceEvent::InterfaceDown) | (Loopback, InterfaceEvent::LoopInd) => None,
+            (_, InterfaceEvent::InterfaceDown) => Some((Down, vec![ResetInterface, KillNeighbors])),
+            (_, InterfaceEvent::LoopInd) => Some((Loopback, vec![ResetInterface, KillNeighbors])),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InterfaceStatus::{self, *};
+    use crate::interface::{
+        event::{InterfaceAction::*, InterfaceEvent},
+        InterfaceNetworkType,
+    };
+
+    const STATES: [InterfaceStatus; 7] =
+        [Down, Loopback, Waiting, PointToPoint, DRother, Backup, DR];
+    const EVENTS: [InterfaceEvent; 7] = [
+        InterfaceEvent::InterfaceUp,
+        InterfaceEvent::WaitTimer,
+        InterfaceEvent::BackupSeen,
+        InterfaceEvent::NeighborChange,
+        InterfaceEvent::LoopInd,
+        InterfaceEvent::UnloopInd,
+        InterfaceEvent::InterfaceDown,
+    ];
+
+    /// every (state, event) pair of RFC 2328 9.3 on a broadcast network where the router
+    /// is eligible, a pair not listed is ignored.
+    #[test]
+    fn transition_table() {
+        let table = [
+            (
+                Down,
+                InterfaceEvent::InterfaceUp,
+                Waiting,
+                vec![StartHelloTimer, StartWaitTimer],
+            ),
+            (
+                Waiting,
+                InterfaceEvent::BackupSeen,
+                Waiting,
+                vec![ElectDesignatedRouter],
+            ),
+            (
+                Waiting,
+                InterfaceEvent::WaitTimer,
+                Waiting,
+                vec![ElectDesignatedRouter],
+            ),
+            (
+                DRother,
+                InterfaceEvent::NeighborChange,
+                DRother,
+                vec![ElectDesignatedRouter],
+            ),
+            (
+                Backup,
+                InterfaceEvent::NeighborChange,
+                Backup,
+                vec![ElectDesignatedRouter],
+            ),
+            (
+                DR,
+                InterfaceEvent::NeighborChange,
+                DR,
+                vec![ElectDesignatedRouter],
+            ),
+            (Loopback, InterfaceEvent::UnloopInd, Down, vec![]),
+            (
+                Loopback,
+                InterfaceEvent::InterfaceDown,
+                Down,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                Waiting,
+                InterfaceEvent::InterfaceDown,
+                Down,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                PointToPoint,
+                InterfaceEvent::InterfaceDown,
+                Down,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                DRother,
+                InterfaceEvent::InterfaceDown,
+                Down,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                Backup,
+                InterfaceEvent::InterfaceDown,
+                Down,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                DR,
+                InterfaceEvent::InterfaceDown,
+                Down,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                Down,
+                InterfaceEvent::LoopInd,
+                Loopback,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                Waiting,
+                InterfaceEvent::LoopInd,
+                Loopback,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                PointToPoint,
+                InterfaceEvent::LoopInd,
+                Loopback,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                DRother,
+                InterfaceEvent::LoopInd,
+                Loopback,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                Backup,
+                InterfaceEvent::LoopInd,
+                Loopback,
+                vec![ResetInterface, KillNeighbors],
+            ),
+            (
+                DR,
+                InterfaceEvent::LoopInd,
+                Loopback,
+                vec![ResetInterface, KillNeighbors],
+            ),
+        ];
+        for state in STATES {
+            for event in EVENTS {
+                let expected = table
+                    .iter()
+                    .find(|(from, on, _, _)| *from == state && *on == event)
+                    .map(|(_, _, to, actions)| (*to, actions.clone()));
+                assert_eq!(
+                    state.transition(event, InterfaceNetworkType::Broadcast, 1),
+                    expected,
+                    "{:?} on {:?}",
+                    state,
+                    event
+                );
+            }
+        }
+    }
+
+    /// InterfaceUp leads to Point-to-point on the networks without a DR, and to DR Other
+    /// when the router is not eligible to become DR.
+    #[test]
+    fn interface_up_depends_on_the_network() {
+        let table = [
+            (
+                InterfaceNetworkType::PointToPoint,
+                1,
+                PointToPoint,
+                vec![StartHelloTimer],
+            ),
+            (
+                InterfaceNetworkType::PointToMultipoint,
+                1,
+                PointToPoint,
+                vec![StartHelloTimer],
+            ),
+            (
+                InterfaceNetworkType::VirtualLink,
+                1,
+                PointToPoint,
+                vec![StartHelloTimer],
+            ),
+            (
+                InterfaceNetworkType::Broadcast,
+                0,
+                DRother,
+                vec![StartHelloTimer],
+            ),
+            (
+                InterfaceNetworkType::NBMA,
+                0,
+                DRother,
+                vec![StartHelloTimer],
+            ),
+            (
+                InterfaceNetworkType::NBMA,
+                1,
+                Waiting,
+                vec![StartHelloTimer, StartWaitTimer],
+            ),
+        ];
+        for (network_type, router_priority, to, actions) in table {
+            assert_eq!(
+                Down.transition(InterfaceEvent::InterfaceUp, network_type, router_priority),
+                Some((to, actions)),
+                "{:?} with priority {}",
+                network_type,
+                router_priority
+            );
+        }
+    }
+}
@@ -5,13 +5,20 @@ use std::{
 };
 pub mod area;
 pub mod r#as;
+pub mod auth;
 pub mod error;
+pub mod flooding;
 pub mod interface;
 pub mod lsa;
+pub mod lsdb;
 pub mod neighbor;
 pub mod packet;
 pub mod router;
 pub mod rtable;
+/// the fixtures shared by the unit tests: interfaces registered in a router of their own,
+/// without a socket, whose sent packets are read back from their send channel.
+#[cfg(test)]
+mod testing;
 
 #[allow(non_upper_case_globals)]
 pub const AllSPFRouters: net::Ipv4Addr = crate::bits_to_ipv4_addr(0xe0000005);
@@ -20,8 +27,18 @@ pub const AllDRouters: net::Ipv4Addr = crate::bits_to_ipv4_addr(0xe0000006);
 
 pub const OSPF_VERSION_2: u8 = 2;
 pub const OSPF_IP_PROTOCOL_NUMBER: u8 = 89;
+/// ospf packets are sent with ip precedence internetwork control (RFC 2328 A.1),
+/// which is the class selector 6 of the dscp field.
+pub const OSPF_IP_DSCP: u8 = 0b110000;
+pub const OSPF_IP_TTL: u8 = 1;
 pub const MTU: usize = 1500;
 
+/// the bits of the options field carried by hellos, DD packets and lsas (RFC 2328 A.2).
+pub const OPTION_BIT_E: u8 = 1 << 1;
+pub const OPTION_BIT_MC: u8 = 1 << 2;
+pub const OPTION_BIT_NP: u8 = 1 << 3;
+pub const OPTION_BIT_EA: u8 = 1 << 4;
+pub const OPTION_BIT_DC: u8 = 1 << 5;
 
 pub fn prompt_and_read(prompt: &str) -> String {
     print!("{}", prompt);
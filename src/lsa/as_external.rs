@@ -1,8 +1,24 @@
 use super::{LinkStateAdvertisement, LinkStateAdvertisementHeader};
+use crate::error::OspfDecodeError;
 use std::net;
 
 pub const AS_EXTERNAL_LINK_STATE_TYPE: u8 = 5;
 
+/// # struct AsExternalTosEntry
+/// a tos-specific route following the tos 0 route of an as external lsa.
+pub struct AsExternalTosEntry {
+    pub feature: u8,
+    pub metric: [u8; 3],
+    pub forwarding_addr: net::Ipv4Addr,
+    pub external_route_tag: u32,
+}
+
+impl AsExternalTosEntry {
+    pub fn length() -> usize {
+        12
+    }
+}
+
 pub struct AsExternalLinkStateAdvertisement {
     pub header: LinkStateAdvertisementHeader,
     pub network_mask: net::Ipv4Addr,
@@ -10,9 +26,7 @@ pub struct AsExternalLinkStateAdvertisement {
     pub metric: [u8; 3],
     pub forwarding_addr: net::Ipv4Addr,
     pub external_route_tag: u32,
-    pub tos_feature: u8,
-    pub tos_metric: [u8; 3],
-    pub tos_forwarding_addr: net::Ipv4Addr,
+    pub tos_entries: Vec<AsExternalTosEntry>,
 }
 
 unsafe impl Send for AsExternalLinkStateAdvertisement {}
@@ -26,9 +40,7 @@ impl LinkStateAdvertisement for AsExternalLinkStateAdvertisement {
         length += 3;
         length += 4;
         length += 4;
-        length += 1;
-        length += 3;
-        length += 4;
+        length += AsExternalTosEntry::length() * self.tos_entries.len();
         length
     }
 
@@ -40,9 +52,12 @@ impl LinkStateAdvertisement for AsExternalLinkStateAdvertisement {
         result.extend(&self.metric);
         result.extend(self.forwarding_addr.octets().iter());
         result.extend(self.external_route_tag.to_be_bytes());
-        result.push(self.tos_feature);
-        result.extend(&self.tos_metric);
-        result.extend(self.tos_forwarding_addr.octets().iter());
+        for tos_entry in &self.tos_entries {
+            result.push(tos_entry.feature);
+            result.extend(&tos_entry.metric);
+            result.extend(tos_entry.forwarding_addr.octets().iter());
+            result.extend(tos_entry.external_route_tag.to_be_bytes());
+        }
         result
     }
 
@@ -54,14 +69,29 @@ impl LinkStateAdvertisement for AsExternalLinkStateAdvertisement {
         result.extend(&self.metric);
         result.extend(self.forwarding_addr.octets().iter());
         result.extend(self.external_route_tag.to_be_bytes());
-        result.push(self.tos_feature);
-        result.extend(&self.tos_metric);
-        result.extend(self.tos_forwarding_addr.octets().iter());
+        for tos_entry in &self.tos_entries {
+            result.push(tos_entry.feature);
+            result.extend(&tos_entry.metric);
+            result.extend(tos_entry.forwarding_addr.octets().iter());
+            result.extend(tos_entry.external_route_tag.to_be_bytes());
+        }
         result
     }
+    fn get_header(&self) -> &LinkStateAdvertisementHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut LinkStateAdvertisementHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 impl AsExternalLinkStateAdvertisement {
+    /// the length of an as external lsa without any tos entry.
+    pub const MIN_LENGTH: usize = 36;
+
     pub fn new(
         header: LinkStateAdvertisementHeader,
         network_mask: net::Ipv4Addr,
@@ -69,9 +99,7 @@ impl AsExternalLinkStateAdvertisement {
         metric: [u8; 3],
         forwarding_addr: net::Ipv4Addr,
         external_route_tag: u32,
-        tos_feature: u8,
-        tos_metric: [u8; 3],
-        tos_forwarding_addr: net::Ipv4Addr,
+        tos_entries: Vec<AsExternalTosEntry>,
     ) -> Self {
         Self {
             header,
@@ -80,32 +108,42 @@ impl AsExternalLinkStateAdvertisement {
             metric,
             forwarding_addr,
             external_route_tag,
-            tos_feature,
-            tos_metric,
-            tos_forwarding_addr,
+            tos_entries,
         }
     }
 
-    pub fn from_be_bytes(data: &[u8]) -> Self {
-        let header = LinkStateAdvertisementHeader::from_be_bytes(&data[0..20]);
+    pub fn from_be_bytes(data: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = LinkStateAdvertisementHeader::from_be_bytes(data)?;
+        OspfDecodeError::ensure_length("as external lsa", data, Self::MIN_LENGTH)?;
+        if !(data.len() - Self::MIN_LENGTH).is_multiple_of(AsExternalTosEntry::length()) {
+            return Err(OspfDecodeError::LengthMismatch {
+                what: "as external lsa tos entries",
+                length: data.len() - Self::MIN_LENGTH,
+                entry_size: AsExternalTosEntry::length(),
+            });
+        }
         let network_mask = net::Ipv4Addr::new(data[20], data[21], data[22], data[23]);
         let feature = data[24];
         let metric = [data[25], data[26], data[27]];
         let forwarding_addr = net::Ipv4Addr::new(data[28], data[29], data[30], data[31]);
         let external_route_tag = u32::from_be_bytes([data[32], data[33], data[34], data[35]]);
-        let tos_feature = data[36];
-        let tos_metric = [data[37], data[38], data[39]];
-        let tos_forwarding_addr = net::Ipv4Addr::new(data[40], data[41], data[42], data[43]);
-        Self {
+        let tos_entries = data[Self::MIN_LENGTH..]
+            .chunks_exact(AsExternalTosEntry::length())
+            .map(|chunk| AsExternalTosEntry {
+                feature: chunk[0],
+                metric: [chunk[1], chunk[2], chunk[3]],
+                forwarding_addr: net::Ipv4Addr::new(chunk[4], chunk[5], chunk[6], chunk[7]),
+                external_route_tag: u32::from_be_bytes([chunk[8], chunk[9], chunk[10], chunk[11]]),
+            })
+            .collect();
+        Ok(Self {
             header,
             network_mask,
             feature,
             metric,
             forwarding_addr,
             external_route_tag,
-            tos_feature,
-            tos_metric,
-            tos_forwarding_addr,
-        }
+            tos_entries,
+        })
     }
 }
@@ -1,11 +1,11 @@
 use core::net;
 
+use crate::bits_to_ipv4_addr;
+use crate::error::OspfDecodeError;
 use as_external::AS_EXTERNAL_LINK_STATE_TYPE;
 use network::NETWORK_LINK_STATE_TYPE;
 use router::ROUTER_LINK_STATE_TYPE;
 use summary::{SUMMARY_LINK_STATE_TYPE_3, SUMMARY_LINK_STATE_TYPE_4};
-use crate::bits_to_ipv4_addr;
-
 
 pub mod as_external;
 pub mod network;
@@ -19,10 +19,10 @@ pub const LSRefreshTime: u32 = 1800;
 pub const MinLSInterval: u32 = 5;
 
 #[allow(non_upper_case_globals)]
-pub const MaxAge: u32 = 1;
+pub const MaxAge: u32 = 3600;
 
 #[allow(non_upper_case_globals)]
-pub const CheckAge: u32 = 3600;
+pub const CheckAge: u32 = 300;
 #[allow(non_upper_case_globals)]
 pub const MaxAgeDiff: u32 = 900;
 #[allow(non_upper_case_globals)]
@@ -30,8 +30,13 @@ pub const LSInfinity: u32 = 0xffffff;
 #[allow(non_upper_case_globals)]
 pub const DefaultDesination: net::Ipv4Addr = bits_to_ipv4_addr(0);
 #[allow(non_upper_case_globals)]
-pub const InitialSequenceNumber: u32 = 0x7fffffff;
+pub const InitialSequenceNumber: u32 = 0x80000001;
+#[allow(non_upper_case_globals)]
+pub const MaxSequenceNumber: u32 = 0x7fffffff;
+#[allow(non_upper_case_globals)]
+pub const MinLSArrival: u32 = 1;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct LinkStateAdvertisementHeader {
     pub age: u16,
     pub options: u8,
@@ -46,6 +51,7 @@ pub struct LinkStateAdvertisementHeader {
 unsafe impl Send for LinkStateAdvertisementHeader {}
 
 impl LinkStateAdvertisementHeader {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         age: u16,
         options: u8,
@@ -70,8 +76,9 @@ impl LinkStateAdvertisementHeader {
     pub fn length() -> usize {
         20
     }
-    pub fn from_be_bytes(bytes: &[u8]) -> Self {
-        Self {
+    pub fn from_be_bytes(bytes: &[u8]) -> Result<Self, OspfDecodeError> {
+        OspfDecodeError::ensure_length("lsa header", bytes, Self::length())?;
+        Ok(Self {
             age: u16::from_be_bytes([bytes[0], bytes[1]]),
             options: bytes[2],
             lsa_type: bytes[3],
@@ -80,7 +87,7 @@ impl LinkStateAdvertisementHeader {
             sequence_number: u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
             checksum: u16::from_be_bytes([bytes[16], bytes[17]]),
             length: u16::from_be_bytes([bytes[18], bytes[19]]),
-        }
+        })
     }
     pub fn to_be_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::with_capacity(20);
@@ -94,6 +101,40 @@ impl LinkStateAdvertisementHeader {
         bytes.extend_from_slice(&self.length.to_be_bytes());
         bytes
     }
+    /// whether the header is of the same lsa as `other`, its type, link state id
+    /// and advertising router being the same.
+    pub fn is_same_lsa(&self, other: &Self) -> bool {
+        self.lsa_type == other.lsa_type
+            && self.link_state_id == other.link_state_id
+            && self.advertising_router == other.advertising_router
+    }
+
+    /// which of two instances of the same lsa is the more recent (RFC 2328 13.1),
+    /// greater meaning `self` is more recent than `other`.
+    pub fn compare(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        // sequence numbers are signed, 0x80000001 being the smallest one.
+        let ordering = (self.sequence_number as i32).cmp(&(other.sequence_number as i32));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        let ordering = self.checksum.cmp(&other.checksum);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        let (age, other_age) = (self.age as u32, other.age as u32);
+        match (age == MaxAge, other_age == MaxAge) {
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            _ => {}
+        }
+        if age.abs_diff(other_age) > MaxAgeDiff {
+            // the younger instance is the more recent.
+            return other_age.cmp(&age);
+        }
+        Ordering::Equal
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::with_capacity(20);
         bytes.extend_from_slice(&self.age.to_be_bytes());
@@ -108,25 +149,98 @@ impl LinkStateAdvertisementHeader {
     }
 }
 
-pub fn get_lsa_from_be_bytes(data: &[u8]) -> Box<dyn LinkStateAdvertisement> {
-    let header = LinkStateAdvertisementHeader::from_be_bytes(&data[0..20]);
-    match header.lsa_type {
+/// the fletcher checksum (ISO 8473 annex C) of an encoded lsa as RFC 2328 12.1.7 requires.
+/// it covers the whole lsa except the ls age field, the checksum field is taken as zero.
+pub fn fletcher_checksum(lsa: &[u8]) -> u16 {
+    // the checksum field is at offset 16 of the lsa, 14 once the age is skipped.
+    const CHECKSUM_OFFSET: usize = 14;
+    let data = &lsa[2..];
+    let (mut c0, mut c1) = (0i64, 0i64);
+    for (index, &byte) in data.iter().enumerate() {
+        let byte = match index {
+            CHECKSUM_OFFSET | 15 => 0,
+            _ => byte as i64,
+        };
+        c0 = (c0 + byte) % 255;
+        c1 = (c1 + c0) % 255;
+    }
+    let position = data.len() as i64 - CHECKSUM_OFFSET as i64 - 1;
+    let mut x = (position * c0 - c1).rem_euclid(255);
+    let mut y = (c1 - (position + 1) * c0).rem_euclid(255);
+    if x == 0 {
+        x = 255;
+    }
+    if y == 0 {
+        y = 255;
+    }
+    ((x as u16) << 8) | y as u16
+}
+
+/// check the fletcher checksum of an encoded lsa, both running sums of a valid lsa are zero.
+pub fn is_lsa_checksum_valid(lsa: &[u8]) -> bool {
+    if lsa.len() < LinkStateAdvertisementHeader::length() {
+        return false;
+    }
+    let (mut c0, mut c1) = (0u32, 0u32);
+    for &byte in &lsa[2..] {
+        c0 = (c0 + byte as u32) % 255;
+        c1 = (c1 + c0) % 255;
+    }
+    c0 == 0 && c1 == 0
+}
+
+/// decode the lsa at the start of `data`, the lsa header's length field decides
+/// where it ends, so the rest of `data` is left for the following lsas.
+pub fn get_lsa_from_be_bytes(
+    data: &[u8],
+) -> Result<Box<dyn LinkStateAdvertisement + Send>, OspfDecodeError> {
+    let header = LinkStateAdvertisementHeader::from_be_bytes(data)?;
+    let length = header.length as usize;
+    if length < LinkStateAdvertisementHeader::length() || length > data.len() {
+        return Err(OspfDecodeError::BadLengthField {
+            what: "lsa header",
+            length,
+            available: data.len(),
+        });
+    }
+    let data = &data[..length];
+    Ok(match header.lsa_type {
         ROUTER_LINK_STATE_TYPE => {
-            Box::new(router::RouterLinkStateAdvertisement::from_be_bytes(data))
+            Box::new(router::RouterLinkStateAdvertisement::from_be_bytes(data)?)
         }
         NETWORK_LINK_STATE_TYPE => {
-            Box::new(network::NetworkLinkStateAdvertisement::from_be_bytes(data))
+            Box::new(network::NetworkLinkStateAdvertisement::from_be_bytes(data)?)
         }
         SUMMARY_LINK_STATE_TYPE_3 => {
-            Box::new(summary::SummaryLinkStateAdvertisement::from_be_bytes(data))
+            Box::new(summary::SummaryLinkStateAdvertisement::from_be_bytes(data)?)
         }
         SUMMARY_LINK_STATE_TYPE_4 => {
-            Box::new(summary::SummaryLinkStateAdvertisement::from_be_bytes(data))
+            Box::new(summary::SummaryLinkStateAdvertisement::from_be_bytes(data)?)
         }
         AS_EXTERNAL_LINK_STATE_TYPE => {
-            Box::new(as_external::AsExternalLinkStateAdvertisement::from_be_bytes(data))
+            Box::new(as_external::AsExternalLinkStateAdvertisement::from_be_bytes(data)?)
         }
-        _ => panic!("Unknown LSA type"),
+        lsa_type => return Err(OspfDecodeError::UnknownLsaType(lsa_type)),
+    })
+}
+
+/// a copy of `lsa`, made by encoding and decoding it again.
+pub fn copy_lsa(
+    lsa: &(dyn LinkStateAdvertisement + Send),
+) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+    get_lsa_from_be_bytes(&lsa.to_be_bytes()).ok()
+}
+
+/// whether an lsa of type `lsa_type` may be seen in an area, as-external-lsas
+/// are not flooded into stub areas.
+pub fn is_lsa_type_known(lsa_type: u8, external_routing_capable: bool) -> bool {
+    match lsa_type {
+        ROUTER_LINK_STATE_TYPE
+        | NETWORK_LINK_STATE_TYPE
+        | SUMMARY_LINK_STATE_TYPE_3
+        | SUMMARY_LINK_STATE_TYPE_4 => true,
+        AS_EXTERNAL_LINK_STATE_TYPE => external_routing_capable,
+        _ => false,
     }
 }
 
@@ -134,4 +248,131 @@ pub trait LinkStateAdvertisement {
     fn to_be_bytes(&self) -> Vec<u8>;
     fn to_bytes(&self) -> Vec<u8>;
     fn length(&self) -> usize;
+    fn get_header(&self) -> &LinkStateAdvertisementHeader;
+    fn get_header_mut(&mut self) -> &mut LinkStateAdvertisementHeader;
+    /// used by the spf calculation to get the concrete lsa back.
+    fn as_any(&self) -> &dyn std::any::Any;
+    /// fill in the length and the checksum of the header when originating the lsa.
+    fn calculate_checksum(&mut self) {
+        let length = self.to_be_bytes().len() as u16;
+        let header = self.get_header_mut();
+        header.length = length;
+        header.checksum = 0;
+        let checksum = fletcher_checksum(&self.to_be_bytes());
+        self.get_header_mut().checksum = checksum;
+    }
+    /// check the checksum of the lsa as it is encoded now, the received lsas are checked
+    /// on their raw bytes when the lsu packet is decoded.
+    fn is_checksum_valid(&self) -> bool {
+        is_lsa_checksum_valid(&self.to_be_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    fn header(age: u32, sequence_number: u32, checksum: u16) -> LinkStateAdvertisementHeader {
+        LinkStateAdvertisementHeader::new(
+            age as u16,
+            0,
+            ROUTER_LINK_STATE_TYPE,
+            0x01010101,
+            0x01010101,
+            sequence_number,
+            checksum,
+            24,
+        )
+    }
+
+    #[test]
+    fn compare_follows_rfc_2328_13_1() {
+        // (age, sequence number, checksum) of the received and the database instances.
+        let cases = [
+            // the sequence numbers are signed.
+            ((1, 0x80000002, 0), (1, 0x80000001, 0), Ordering::Greater),
+            (
+                (1, InitialSequenceNumber, 0),
+                (1, MaxSequenceNumber, 0),
+                Ordering::Less,
+            ),
+            ((1, 0x00000001, 0), (1, 0xffffffff, 0), Ordering::Greater),
+            // then the larger checksum, whatever the ages.
+            (
+                (1, 0x80000001, 0x1235),
+                (1, 0x80000001, 0x1234),
+                Ordering::Greater,
+            ),
+            (
+                (MaxAge, 0x80000001, 0x1234),
+                (1, 0x80000001, 0x1235),
+                Ordering::Less,
+            ),
+            // then the instance at MaxAge.
+            (
+                (MaxAge, 0x80000001, 0),
+                (MaxAge - 1, 0x80000001, 0),
+                Ordering::Greater,
+            ),
+            ((1, 0x80000001, 0), (MaxAge, 0x80000001, 0), Ordering::Less),
+            (
+                (MaxAge, 0x80000001, 0),
+                (MaxAge, 0x80000001, 0),
+                Ordering::Equal,
+            ),
+            // then the younger one if the ages differ by more than MaxAgeDiff.
+            (
+                (1, 0x80000001, 0),
+                (MaxAgeDiff + 2, 0x80000001, 0),
+                Ordering::Greater,
+            ),
+            (
+                (MaxAgeDiff + 2, 0x80000001, 0),
+                (1, 0x80000001, 0),
+                Ordering::Less,
+            ),
+            // within MaxAgeDiff the two are the same instance.
+            (
+                (1, 0x80000001, 0),
+                (MaxAgeDiff + 1, 0x80000001, 0),
+                Ordering::Equal,
+            ),
+            (
+                (MaxAgeDiff + 1, 0x80000001, 0),
+                (1, 0x80000001, 0),
+                Ordering::Equal,
+            ),
+        ];
+        for (
+            (age, sequence_number, checksum),
+            (other_age, other_sequence_number, other_checksum),
+            ordering,
+        ) in cases
+        {
+            let received = header(age, sequence_number, checksum);
+            let database = header(other_age, other_sequence_number, other_checksum);
+            assert_eq!(
+                received.compare(&database),
+                ordering,
+                "{:?} against {:?}",
+                received,
+                database
+            );
+            assert_eq!(database.compare(&received), ordering.reverse());
+        }
+    }
+
+    #[test]
+    fn same_lsa_ignores_the_instance() {
+        let lsa_header = header(1, 0x80000001, 0x1234);
+        assert!(lsa_header.is_same_lsa(&header(MaxAge, 0x80000005, 0)));
+        let mut other_lsa_header = lsa_header;
+        other_lsa_header.link_state_id += 1;
+        assert!(!lsa_header.is_same_lsa(&other_lsa_header));
+        other_lsa_header = lsa_header;
+        other_lsa_header.lsa_type = NETWORK_LINK_STATE_TYPE;
+        assert!(!lsa_header.is_same_lsa(&other_lsa_header));
+    }
 }
@@ -1,4 +1,5 @@
 use super::{LinkStateAdvertisement, LinkStateAdvertisementHeader};
+use crate::error::OspfDecodeError;
 use std::net;
 
 pub const NETWORK_LINK_STATE_TYPE: u8 = 2;
@@ -37,9 +38,21 @@ impl LinkStateAdvertisement for NetworkLinkStateAdvertisement {
         }
         result
     }
+    fn get_header(&self) -> &LinkStateAdvertisementHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut LinkStateAdvertisementHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 impl NetworkLinkStateAdvertisement {
+    /// the length of a network lsa without any attached router.
+    pub const MIN_LENGTH: usize = 24;
+
     pub fn new(
         header: LinkStateAdvertisementHeader,
         network_mask: u32,
@@ -51,24 +64,25 @@ impl NetworkLinkStateAdvertisement {
             attached_routers,
         }
     }
-    pub fn from_be_bytes(data: &[u8]) -> Self {
-        let header = LinkStateAdvertisementHeader::from_be_bytes(&data[0..20]);
+    pub fn from_be_bytes(data: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = LinkStateAdvertisementHeader::from_be_bytes(data)?;
+        OspfDecodeError::ensure_length("network lsa", data, Self::MIN_LENGTH)?;
+        if !(data.len() - Self::MIN_LENGTH).is_multiple_of(4) {
+            return Err(OspfDecodeError::LengthMismatch {
+                what: "network lsa attached routers",
+                length: data.len() - Self::MIN_LENGTH,
+                entry_size: 4,
+            });
+        }
         let network_mask = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
         let mut attached_routers = vec![];
-        let mut index = 24;
-        while index < data.len() {
-            attached_routers.push(net::Ipv4Addr::new(
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-            ));
-            index += 4;
+        for chunk in data[Self::MIN_LENGTH..].chunks_exact(4) {
+            attached_routers.push(net::Ipv4Addr::new(chunk[0], chunk[1], chunk[2], chunk[3]));
         }
-        Self {
+        Ok(Self {
             header,
             network_mask,
             attached_routers,
-        }
+        })
     }
 }
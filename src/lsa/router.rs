@@ -1,6 +1,18 @@
 use super::{LinkStateAdvertisement, LinkStateAdvertisementHeader};
+use crate::error::OspfDecodeError;
 
-pub const ROUTER_LINK_STATE_TYPE: u8  = 1;  
+pub const ROUTER_LINK_STATE_TYPE: u8 = 1;
+
+/// the link types of RFC 2328 A.4.2.
+pub const ROUTER_LINK_TYPE_POINT_TO_POINT: u8 = 1;
+pub const ROUTER_LINK_TYPE_TRANSIT: u8 = 2;
+pub const ROUTER_LINK_TYPE_STUB: u8 = 3;
+pub const ROUTER_LINK_TYPE_VIRTUAL: u8 = 4;
+
+/// the V, E and B bits of the feature field.
+pub const ROUTER_FEATURE_BIT_B: u16 = 1 << 8;
+pub const ROUTER_FEATURE_BIT_E: u16 = 1 << 9;
+pub const ROUTER_FEATURE_BIT_V: u16 = 1 << 10;
 
 pub struct RouterLink {
     pub link_id: u32,
@@ -8,10 +20,27 @@ pub struct RouterLink {
     pub link_type: u8,
     pub link_tos: u8,
     pub link_metric: u16,
+    /// the raw tos-specific metrics following the link, `link_tos` of them.
+    pub link_tos_metrics: Vec<u32>,
+}
+
+impl RouterLink {
+    /// a link without tos-specific metrics.
+    pub fn new(link_id: u32, link_data: u32, link_type: u8, link_metric: u16) -> Self {
+        Self {
+            link_id,
+            link_data,
+            link_type,
+            link_tos: 0,
+            link_metric,
+            link_tos_metrics: Vec::new(),
+        }
+    }
 }
 
 /// # struct RouterLinkStateAdvertisement
-/// doc to be implemented
+/// the router-lsa (RFC 2328 A.4.2), the links of a router into one area.
+/// - feature : the V, E and B bits, in the high byte.
 pub struct RouterLinkStateAdvertisement {
     pub header: LinkStateAdvertisementHeader,
     pub feature: u16,
@@ -22,12 +51,13 @@ pub struct RouterLinkStateAdvertisement {
 unsafe impl Send for RouterLinkStateAdvertisement {}
 
 impl LinkStateAdvertisement for RouterLinkStateAdvertisement {
-    /// # wrong
     fn length(&self) -> usize {
         let mut length = 0;
         length += LinkStateAdvertisementHeader::length();
         length += 4;
-        length += 4 * self.link_count as usize;
+        for link in &self.links {
+            length += 12 + 4 * link.link_tos_metrics.len();
+        }
         length
     }
     fn to_be_bytes(&self) -> Vec<u8> {
@@ -41,6 +71,9 @@ impl LinkStateAdvertisement for RouterLinkStateAdvertisement {
             result.push(link.link_type);
             result.push(link.link_tos);
             result.extend(link.link_metric.to_be_bytes());
+            for tos_metric in &link.link_tos_metrics {
+                result.extend(tos_metric.to_be_bytes());
+            }
         }
         result
     }
@@ -55,19 +88,39 @@ impl LinkStateAdvertisement for RouterLinkStateAdvertisement {
             result.push(link.link_type);
             result.push(link.link_tos);
             result.extend(link.link_metric.to_be_bytes());
+            for tos_metric in &link.link_tos_metrics {
+                result.extend(tos_metric.to_be_bytes());
+            }
         }
         result
     }
+    fn get_header(&self) -> &LinkStateAdvertisementHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut LinkStateAdvertisementHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 impl RouterLinkStateAdvertisement {
-    pub fn from_be_bytes(data: &[u8]) -> Self {
-        let header = LinkStateAdvertisementHeader::from_be_bytes(&data[0..20]);
+    /// the length of a router lsa without any link.
+    pub const MIN_LENGTH: usize = 24;
+
+    pub fn from_be_bytes(data: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = LinkStateAdvertisementHeader::from_be_bytes(data)?;
+        OspfDecodeError::ensure_length("router lsa", data, Self::MIN_LENGTH)?;
         let feature = u16::from_be_bytes([data[20], data[21]]);
         let link_count = u16::from_be_bytes([data[22], data[23]]);
         let mut links = vec![];
-        let mut index = 24;
+        let mut index = Self::MIN_LENGTH;
         for _ in 0..link_count {
+            OspfDecodeError::ensure_length("router lsa link", data, index + 12)?;
+            let link_tos = data[index + 9];
+            let tos_end = index + 12 + 4 * link_tos as usize;
+            OspfDecodeError::ensure_length("router lsa link tos", data, tos_end)?;
             links.push(Box::new(RouterLink {
                 link_id: u32::from_be_bytes([
                     data[index],
@@ -82,17 +135,28 @@ impl RouterLinkStateAdvertisement {
                     data[index + 7],
                 ]),
                 link_type: data[index + 8],
-                link_tos: data[index + 9],
+                link_tos,
                 link_metric: u16::from_be_bytes([data[index + 10], data[index + 11]]),
+                link_tos_metrics: data[index + 12..tos_end]
+                    .chunks_exact(4)
+                    .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect(),
             }));
-            index += 12;
+            index = tos_end;
         }
-        Self {
+        if index != data.len() {
+            return Err(OspfDecodeError::LengthMismatch {
+                what: "router lsa links",
+                length: data.len() - Self::MIN_LENGTH,
+                entry_size: 12,
+            });
+        }
+        Ok(Self {
             header,
             feature,
             link_count,
             links,
-        }
+        })
     }
     pub fn new(
         header: LinkStateAdvertisementHeader,
@@ -108,3 +172,54 @@ impl RouterLinkStateAdvertisement {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OPTION_BIT_E;
+
+    fn router_lsa(links: Vec<RouterLink>) -> RouterLinkStateAdvertisement {
+        RouterLinkStateAdvertisement::new(
+            LinkStateAdvertisementHeader::new(
+                0,
+                OPTION_BIT_E,
+                ROUTER_LINK_STATE_TYPE,
+                0x01010101,
+                0x01010101,
+                0x80000001,
+                0,
+                0,
+            ),
+            0,
+            links.len() as u16,
+            links.into_iter().map(Box::new).collect(),
+        )
+    }
+
+    #[test]
+    fn length_without_links() {
+        let lsa = router_lsa(Vec::new());
+        assert_eq!(lsa.length(), RouterLinkStateAdvertisement::MIN_LENGTH);
+        assert_eq!(lsa.to_be_bytes().len(), lsa.length());
+    }
+
+    #[test]
+    fn length_counts_the_tos_metrics() {
+        let mut tos_link = RouterLink::new(0xc0000200, 0xffffff00, ROUTER_LINK_TYPE_STUB, 10);
+        tos_link.link_tos = 2;
+        tos_link.link_tos_metrics = vec![0x08000005, 0x10000007];
+        let lsa = router_lsa(vec![
+            RouterLink::new(0x02020202, 0xc0000201, ROUTER_LINK_TYPE_POINT_TO_POINT, 10),
+            tos_link,
+        ]);
+        assert_eq!(lsa.length(), 24 + 12 + 12 + 2 * 4);
+        let bytes = lsa.to_be_bytes();
+        assert_eq!(bytes.len(), lsa.length());
+        let decoded = RouterLinkStateAdvertisement::from_be_bytes(&bytes).unwrap();
+        assert_eq!(decoded.length(), lsa.length());
+        assert_eq!(
+            decoded.links[1].link_tos_metrics,
+            vec![0x08000005, 0x10000007]
+        );
+    }
+}
@@ -1,22 +1,21 @@
 use super::{LinkStateAdvertisement, LinkStateAdvertisementHeader};
+use crate::error::OspfDecodeError;
 
 pub const SUMMARY_LINK_STATE_TYPE_3: u8 = 3;
 pub const SUMMARY_LINK_STATE_TYPE_4: u8 = 4;
 
-/// please read the doc agian and change it.
+/// # struct SummaryLinkStateAdvertisement
+/// - type 3 describes a route to a network, type 4 a route to an as boundary router.
+/// - tos_metrics : the raw tos entries following the metric, one tos byte and a 24-bit metric each.
 pub struct SummaryLinkStateAdvertisement {
     pub header: LinkStateAdvertisementHeader,
     pub network_mask: u32,
     pub zero_pad: u8,
     pub metric: [u8; 3],
-    pub tos: u8,
-    pub tos_metric: [u8; 3],
-    pub tos_additional_info: Option<u32>,
+    pub tos_metrics: Vec<u32>,
 }
 
-unsafe impl  Send for SummaryLinkStateAdvertisement{
-    
-}
+unsafe impl Send for SummaryLinkStateAdvertisement {}
 
 impl LinkStateAdvertisement for SummaryLinkStateAdvertisement {
     fn length(&self) -> usize {
@@ -25,11 +24,7 @@ impl LinkStateAdvertisement for SummaryLinkStateAdvertisement {
         length += 4;
         length += 1;
         length += 3;
-        length += 1;
-        length += 3;
-        if let Some(_) = self.tos_additional_info {
-            length += 4;
-        }
+        length += 4 * self.tos_metrics.len();
         length
     }
     fn to_bytes(&self) -> Vec<u8> {
@@ -38,10 +33,8 @@ impl LinkStateAdvertisement for SummaryLinkStateAdvertisement {
         result.extend(self.network_mask.to_be_bytes());
         result.push(self.zero_pad);
         result.extend(&self.metric);
-        result.push(self.tos);
-        result.extend(&self.tos_metric);
-        if let Some(tos_additional_info) = self.tos_additional_info {
-            result.extend(tos_additional_info.to_be_bytes());
+        for tos_metric in &self.tos_metrics {
+            result.extend(tos_metric.to_be_bytes());
         }
         result
     }
@@ -51,55 +44,64 @@ impl LinkStateAdvertisement for SummaryLinkStateAdvertisement {
         result.extend(self.network_mask.to_be_bytes());
         result.push(self.zero_pad);
         result.extend(&self.metric);
-        result.push(self.tos);
-        result.extend(&self.tos_metric);
-        if let Some(tos_additional_info) = self.tos_additional_info {
-            result.extend(tos_additional_info.to_be_bytes());
+        for tos_metric in &self.tos_metrics {
+            result.extend(tos_metric.to_be_bytes());
         }
         result
     }
+    fn get_header(&self) -> &LinkStateAdvertisementHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut LinkStateAdvertisementHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 impl SummaryLinkStateAdvertisement {
-    pub fn from_be_bytes(data: &[u8]) -> Self {
-        let header = LinkStateAdvertisementHeader::from_be_bytes(&data[0..20]);
+    /// the length of a summary lsa without any tos entry.
+    pub const MIN_LENGTH: usize = 28;
+
+    pub fn from_be_bytes(data: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = LinkStateAdvertisementHeader::from_be_bytes(data)?;
+        OspfDecodeError::ensure_length("summary lsa", data, Self::MIN_LENGTH)?;
+        if !(data.len() - Self::MIN_LENGTH).is_multiple_of(4) {
+            return Err(OspfDecodeError::LengthMismatch {
+                what: "summary lsa tos entries",
+                length: data.len() - Self::MIN_LENGTH,
+                entry_size: 4,
+            });
+        }
         let network_mask = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
         let zero_pad = data[24];
         let metric = [data[25], data[26], data[27]];
-        let tos = data[28];
-        let tos_metric = [data[29], data[30], data[31]];
-        let tos_additional_info = if data.len() > 32 {
-            Some(u32::from_be_bytes([data[32], data[33], data[34], data[35]]))
-        } else {
-            None
-        };
-        Self {
+        let tos_metrics = data[Self::MIN_LENGTH..]
+            .chunks_exact(4)
+            .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok(Self {
             header,
             network_mask,
             zero_pad,
             metric,
-            tos,
-            tos_metric,
-            tos_additional_info,
-        }
+            tos_metrics,
+        })
     }
     pub fn new(
         header: LinkStateAdvertisementHeader,
         network_mask: u32,
         zero_pad: u8,
         metric: [u8; 3],
-        tos: u8,
-        tos_metric: [u8; 3],
-        tos_additional_info: Option<u32>,
+        tos_metrics: Vec<u32>,
     ) -> Self {
         Self {
             header,
             network_mask,
             zero_pad,
             metric,
-            tos,
-            tos_metric,
-            tos_additional_info,
+            tos_metrics,
         }
     }
 }
@@ -0,0 +1,216 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use tokio::time;
+
+use crate::{
+    area::Area,
+    flooding::{self, FloodingScope},
+    interface::Interface,
+    neighbor::status::NeighborStatus,
+    r#as::AutomonySystem,
+};
+
+use super::LinkStateAdvertisementKey;
+
+/// the lsas still on the retransmission list of a neighbor of `interfaces`, and whether
+/// a neighbor is exchanging its database, a MaxAge lsa cannot be removed while either holds.
+fn get_pending_lsas(
+    interfaces: &[Arc<Mutex<Interface>>],
+) -> (HashSet<LinkStateAdvertisementKey>, bool) {
+    let mut pending_lsas = HashSet::new();
+    let mut is_any_neighbor_exchanging = false;
+    for interface in interfaces {
+        let locked_interface = interface.lock().unwrap();
+        for neighbor in locked_interface.neighbors.lock().unwrap().values() {
+            is_any_neighbor_exchanging |= matches!(
+                neighbor.status,
+                NeighborStatus::Exchange | NeighborStatus::Loading
+            );
+            pending_lsas.extend(
+                neighbor
+                    .link_state_retransmission_list
+                    .iter()
+                    .map(LinkStateAdvertisementKey::from_header),
+            );
+        }
+    }
+    (pending_lsas, is_any_neighbor_exchanging)
+}
+
+/// the aging of the databases (RFC 2328 14), a single timer for all the lsas: every second
+/// their ages are brought up to date, the lsas reaching MaxAge are flooded, and the MaxAge
+/// lsas every neighbor acknowledged are removed.
+pub async fn lsa_aging_raw_handle(
+    interfaces: Vec<Arc<Mutex<Interface>>>,
+    areas: Vec<Arc<Mutex<Area>>>,
+    autonomous_system: Arc<Mutex<AutomonySystem>>,
+) {
+    let mut interval = time::interval(time::Duration::from_secs(1));
+    // the first tick completes immediately.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        for area in &areas {
+            let mut locked_area = area.lock().unwrap();
+            let scope = FloodingScope::Area(locked_area.get_area_id());
+            let max_aged_lsas = locked_area.get_database_mut().age();
+            drop(locked_area);
+            for lsa in max_aged_lsas {
+                flooding::flood_lsa(&interfaces, lsa.as_ref(), scope, None);
+            }
+        }
+        let max_aged_lsas = autonomous_system.lock().unwrap().get_database_mut().age();
+        for lsa in max_aged_lsas {
+            flooding::flood_lsa(
+                &interfaces,
+                lsa.as_ref(),
+                FloodingScope::AutonomousSystem,
+                None,
+            );
+        }
+        let (pending_lsas, is_any_neighbor_exchanging) = get_pending_lsas(&interfaces);
+        if is_any_neighbor_exchanging {
+            continue;
+        }
+        for area in &areas {
+            let mut locked_area = area.lock().unwrap();
+            for key in locked_area
+                .get_database_mut()
+                .remove_flushed(|key| pending_lsas.contains(key))
+            {
+                crate::debug(&format!(
+                    "area {} removed MaxAge lsa {}.",
+                    locked_area.get_area_id(),
+                    key
+                ));
+            }
+        }
+        for key in autonomous_system
+            .lock()
+            .unwrap()
+            .get_database_mut()
+            .remove_flushed(|key| pending_lsas.contains(key))
+        {
+            crate::debug(&format!("removed MaxAge as-external-lsa {}.", key));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net;
+
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::{
+        interface::{status::InterfaceStatus, InterfaceNetworkType},
+        lsa::MaxAge,
+        packet::lsu::LINK_STATE_UPDATE_TYPE,
+        testing, AllSPFRouters,
+    };
+
+    const IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 1);
+    const ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(1, 1, 1, 1);
+    const NEIGHBOR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 2);
+    const NEIGHBOR_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(2, 2, 2, 2);
+    const OTHER_NEIGHBOR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(198, 51, 100, 2);
+    const ADVERTISING_ROUTER: net::Ipv4Addr = net::Ipv4Addr::new(9, 9, 9, 9);
+
+    fn set_neighbor_status(
+        interface: &Arc<Mutex<Interface>>,
+        neighbor_key: net::Ipv4Addr,
+        status: NeighborStatus,
+    ) {
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        neighbors
+            .lock()
+            .unwrap()
+            .get_mut(&neighbor_key)
+            .unwrap()
+            .status = status;
+    }
+
+    fn lsu_destinations(
+        send_packet_rx: &mut broadcast::Receiver<bytes::Bytes>,
+    ) -> Vec<net::Ipv4Addr> {
+        testing::sent_packets(send_packet_rx)
+            .into_iter()
+            .filter(|(_, packet)| packet.get_type() == LINK_STATE_UPDATE_TYPE)
+            .map(|(destination, _)| destination)
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn max_age_lsa_is_flushed_once_acknowledged_and_no_exchange_runs() {
+        let (interface, mut send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        interface.lock().unwrap().status = InterfaceStatus::DR;
+        testing::add_neighbor(
+            &interface,
+            NEIGHBOR_IP_ADDR,
+            NEIGHBOR_ROUTER_ID,
+            NeighborStatus::Full,
+        );
+        let (other_interface, _other_send_packet_rx) = testing::new_sibling_interface(
+            &interface,
+            net::Ipv4Addr::new(198, 51, 100, 1),
+            InterfaceNetworkType::PointToPoint,
+        );
+        let other_neighbor = testing::add_neighbor(
+            &other_interface,
+            OTHER_NEIGHBOR_IP_ADDR,
+            net::Ipv4Addr::new(3, 3, 3, 3),
+            NeighborStatus::Exchange,
+        );
+        let area = interface.lock().unwrap().area.clone().unwrap();
+        let lsa = testing::new_router_lsa(ADVERTISING_ROUTER, MaxAge as u16 - 2, 0x80000005);
+        let key = LinkStateAdvertisementKey::from_header(lsa.get_header());
+        area.lock().unwrap().install_lsa(lsa);
+        let interfaces = vec![interface.clone(), other_interface.clone()];
+        tokio::spawn(lsa_aging_raw_handle(
+            interfaces,
+            vec![area.clone()],
+            Arc::new(Mutex::new(AutomonySystem::new())),
+        ));
+
+        time::sleep(time::Duration::from_millis(1500)).await;
+        assert!(lsu_destinations(&mut send_packet_rx).is_empty());
+        // reaching MaxAge, the lsa is flooded to be flushed everywhere.
+        time::sleep(time::Duration::from_secs(1)).await;
+        assert_eq!(lsu_destinations(&mut send_packet_rx), vec![AllSPFRouters]);
+        let lsa_header = area.lock().unwrap().get_lsa_header(&key).unwrap();
+        assert_eq!(lsa_header.age as u32, MaxAge);
+        // still waiting for the neighbor to acknowledge it.
+        time::sleep(time::Duration::from_secs(2)).await;
+        assert!(area.lock().unwrap().get_lsa_header(&key).is_some());
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        assert!(neighbors
+            .lock()
+            .unwrap()
+            .get_mut(&NEIGHBOR_IP_ADDR)
+            .unwrap()
+            .remove_link_state_retransmission(&lsa_header));
+        // acknowledged on the other interface too, whose neighbor is still exchanging.
+        other_interface
+            .lock()
+            .unwrap()
+            .neighbors
+            .lock()
+            .unwrap()
+            .get_mut(&other_neighbor)
+            .unwrap()
+            .link_state_retransmission_list
+            .clear();
+        time::sleep(time::Duration::from_secs(2)).await;
+        assert!(area.lock().unwrap().get_lsa_header(&key).is_some());
+        set_neighbor_status(&other_interface, other_neighbor, NeighborStatus::Full);
+        time::sleep(time::Duration::from_secs(1)).await;
+        assert!(area.lock().unwrap().get_lsa_header(&key).is_none());
+        // flushed once, the lsa is not flooded again.
+        assert!(lsu_destinations(&mut send_packet_rx).is_empty());
+    }
+}
@@ -0,0 +1,441 @@
+use std::{cmp::Ordering, collections::BTreeMap, fmt, net};
+
+use tokio::time::Instant;
+
+use crate::{
+    lsa::{self, LinkStateAdvertisement, LinkStateAdvertisementHeader, MaxAge},
+    packet::lsr::LinkStatusRequest,
+};
+
+pub mod handle;
+
+/// # struct LinkStateAdvertisementKey
+/// what tells an lsa apart from the others (RFC 2328 12.1): its type, link state id
+/// and advertising router. the instances of one lsa all share the same key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LinkStateAdvertisementKey {
+    pub lsa_type: u8,
+    pub link_state_id: u32,
+    pub advertising_router: u32,
+}
+
+impl LinkStateAdvertisementKey {
+    pub fn new(lsa_type: u8, link_state_id: u32, advertising_router: u32) -> Self {
+        Self {
+            lsa_type,
+            link_state_id,
+            advertising_router,
+        }
+    }
+
+    pub fn from_header(lsa_header: &LinkStateAdvertisementHeader) -> Self {
+        Self::new(
+            lsa_header.lsa_type,
+            lsa_header.link_state_id,
+            lsa_header.advertising_router,
+        )
+    }
+
+    pub fn from_request(lsr: &LinkStatusRequest) -> Self {
+        Self::new(lsr.ls_type as u8, lsr.link_state_id, lsr.advertising_router)
+    }
+}
+
+impl fmt::Display for LinkStateAdvertisementKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "type {} id {} adv {}",
+            self.lsa_type,
+            net::Ipv4Addr::from(self.link_state_id),
+            net::Ipv4Addr::from(self.advertising_router)
+        )
+    }
+}
+
+/// # struct LinkStateDatabaseEntry
+/// an lsa in the database, its age grows from the age it was installed with.
+/// - installed_at : when the instance was installed, for the MinLSArrival check of RFC 2328 13.
+/// - installed_age : the age of the instance when it was installed.
+/// - is_flushing : whether the instance reached MaxAge and was flooded to be flushed.
+struct LinkStateDatabaseEntry {
+    lsa: Box<dyn LinkStateAdvertisement + Send>,
+    installed_at: Instant,
+    installed_age: u16,
+    is_flushing: bool,
+}
+
+impl LinkStateDatabaseEntry {
+    fn new(lsa: Box<dyn LinkStateAdvertisement + Send>) -> Self {
+        let installed_age = lsa.get_header().age.min(MaxAge as u16);
+        Self {
+            lsa,
+            installed_at: Instant::now(),
+            installed_age,
+            is_flushing: installed_age as u32 == MaxAge,
+        }
+    }
+
+    /// the age of the instance now, never past MaxAge.
+    fn get_age(&self) -> u16 {
+        let elapsed = self.installed_at.elapsed().as_secs();
+        (self.installed_age as u64 + elapsed).min(MaxAge as u64) as u16
+    }
+
+    fn get_header(&self) -> LinkStateAdvertisementHeader {
+        let mut lsa_header = *self.lsa.get_header();
+        lsa_header.age = self.get_age();
+        lsa_header
+    }
+
+    fn get_copy(&self) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        let mut lsa = lsa::copy_lsa(self.lsa.as_ref())?;
+        lsa.get_header_mut().age = self.get_age();
+        Some(lsa)
+    }
+
+    /// set the age the header of the instance carries to its age now.
+    fn update_age(&mut self) {
+        self.lsa.get_header_mut().age = self.get_age();
+    }
+}
+
+/// # struct LinkStateDatabase
+/// the lsas of one flooding scope, an area or the whole AS, one instance per key.
+/// the lsas are ordered by type first, so the lsas of one type can be walked alone.
+#[derive(Default)]
+pub struct LinkStateDatabase {
+    lsas: BTreeMap<LinkStateAdvertisementKey, LinkStateDatabaseEntry>,
+}
+
+impl LinkStateDatabase {
+    pub fn new() -> Self {
+        Self {
+            lsas: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lsas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lsas.is_empty()
+    }
+
+    pub fn get(
+        &self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<&(dyn LinkStateAdvertisement + Send)> {
+        self.lsas.get(key).map(|entry| entry.lsa.as_ref())
+    }
+
+    pub fn get_mut(
+        &mut self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<&mut Box<dyn LinkStateAdvertisement + Send>> {
+        self.lsas.get_mut(key).map(|entry| &mut entry.lsa)
+    }
+
+    pub fn get_header(
+        &self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<LinkStateAdvertisementHeader> {
+        self.lsas.get(key).map(|entry| entry.get_header())
+    }
+
+    /// when the instance of the lsa `key` in the database was installed.
+    pub fn get_installed_at(&self, key: &LinkStateAdvertisementKey) -> Option<Instant> {
+        self.lsas.get(key).map(|entry| entry.installed_at)
+    }
+
+    /// a copy of the instance of the lsa `key` in the database, to be sent out.
+    pub fn get_copy(
+        &self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        self.lsas.get(key)?.get_copy()
+    }
+
+    /// how the instance described by `lsa_header` compares to ours (RFC 2328 13.1),
+    /// greater meaning it is more recent, or none if we have no instance of the lsa.
+    pub fn compare(&self, lsa_header: &LinkStateAdvertisementHeader) -> Option<Ordering> {
+        self.get_header(&LinkStateAdvertisementKey::from_header(lsa_header))
+            .map(|our_header| lsa_header.compare(&our_header))
+    }
+
+    /// install `lsa`, replacing the instance of the same lsa which is returned.
+    pub fn insert(
+        &mut self,
+        lsa: Box<dyn LinkStateAdvertisement + Send>,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        let key = LinkStateAdvertisementKey::from_header(lsa.get_header());
+        self.lsas
+            .insert(key, LinkStateDatabaseEntry::new(lsa))
+            .map(|entry| entry.lsa)
+    }
+
+    pub fn remove(
+        &mut self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        self.lsas.remove(key).map(|entry| entry.lsa)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(dyn LinkStateAdvertisement + Send)> {
+        self.lsas.values().map(|entry| entry.lsa.as_ref())
+    }
+
+    /// the lsas of type `lsa_type`, by link state id then advertising router.
+    pub fn iter_type(
+        &self,
+        lsa_type: u8,
+    ) -> impl Iterator<Item = &(dyn LinkStateAdvertisement + Send)> {
+        let start = LinkStateAdvertisementKey::new(lsa_type, 0, 0);
+        let end = LinkStateAdvertisementKey::new(lsa_type, u32::MAX, u32::MAX);
+        self.lsas
+            .range(start..=end)
+            .map(|(_, entry)| entry.lsa.as_ref())
+    }
+
+    pub fn get_headers(&self) -> Vec<LinkStateAdvertisementHeader> {
+        self.lsas.values().map(|entry| entry.get_header()).collect()
+    }
+
+    /// bring the age of every lsa up to date (RFC 2328 14), returns copies of the lsas
+    /// which just reached MaxAge, to be flooded so they are flushed everywhere.
+    pub fn age(&mut self) -> Vec<Box<dyn LinkStateAdvertisement + Send>> {
+        let mut max_aged_lsas = Vec::new();
+        for entry in self.lsas.values_mut() {
+            entry.update_age();
+            if entry.is_flushing || entry.get_age() as u32 != MaxAge {
+                continue;
+            }
+            entry.is_flushing = true;
+            if let Some(lsa) = entry.get_copy() {
+                max_aged_lsas.push(lsa);
+            }
+        }
+        max_aged_lsas
+    }
+
+    /// set the age of the lsa `key` to MaxAge before its time (RFC 2328 14.1),
+    /// returns a copy of it to be flooded.
+    pub fn premature_age(
+        &mut self,
+        key: &LinkStateAdvertisementKey,
+    ) -> Option<Box<dyn LinkStateAdvertisement + Send>> {
+        let entry = self.lsas.get_mut(key)?;
+        entry.installed_age = MaxAge as u16;
+        entry.is_flushing = true;
+        entry.update_age();
+        entry.get_copy()
+    }
+
+    /// remove the MaxAge lsas which were flooded and are no longer `is_pending`,
+    /// acknowledged by every neighbor. returns the keys of the removed lsas.
+    pub fn remove_flushed(
+        &mut self,
+        is_pending: impl Fn(&LinkStateAdvertisementKey) -> bool,
+    ) -> Vec<LinkStateAdvertisementKey> {
+        let keys: Vec<_> = self
+            .lsas
+            .iter()
+            .filter(|(key, entry)| {
+                entry.is_flushing && entry.get_age() as u32 == MaxAge && !is_pending(key)
+            })
+            .map(|(key, _)| *key)
+            .collect();
+        for key in &keys {
+            self.lsas.remove(key);
+        }
+        keys
+    }
+
+    /// drop the lsas whose checksum does not verify, returns how many were dropped.
+    pub fn verify_checksums(&mut self) -> usize {
+        let before = self.lsas.len();
+        self.lsas.retain(|_, entry| entry.lsa.is_checksum_valid());
+        before - self.lsas.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time;
+
+    use super::*;
+    use crate::{
+        lsa::{
+            network::{NetworkLinkStateAdvertisement, NETWORK_LINK_STATE_TYPE},
+            router::ROUTER_LINK_STATE_TYPE,
+        },
+        testing,
+    };
+
+    fn router_lsa(
+        advertising_router: u8,
+        sequence_number: u32,
+    ) -> Box<dyn LinkStateAdvertisement + Send> {
+        let advertising_router = net::Ipv4Addr::new(
+            advertising_router,
+            advertising_router,
+            advertising_router,
+            advertising_router,
+        );
+        testing::new_router_lsa(advertising_router, 1, sequence_number)
+    }
+
+    fn network_lsa(link_state_id: u32) -> Box<dyn LinkStateAdvertisement + Send> {
+        let mut network_lsa = NetworkLinkStateAdvertisement::new(
+            LinkStateAdvertisementHeader::new(
+                1,
+                0,
+                NETWORK_LINK_STATE_TYPE,
+                link_state_id,
+                0x01010101,
+                0x80000001,
+                0,
+                0,
+            ),
+            0xffffff00,
+            Vec::new(),
+        );
+        network_lsa.calculate_checksum();
+        Box::new(network_lsa)
+    }
+
+    fn key(lsa: &(dyn LinkStateAdvertisement + Send)) -> LinkStateAdvertisementKey {
+        LinkStateAdvertisementKey::from_header(lsa.get_header())
+    }
+
+    #[test]
+    fn keys_are_ordered_by_type_first() {
+        let mut keys = vec![
+            LinkStateAdvertisementKey::new(2, 1, 1),
+            LinkStateAdvertisementKey::new(1, 2, 1),
+            LinkStateAdvertisementKey::new(1, 1, 2),
+            LinkStateAdvertisementKey::new(1, 1, 1),
+        ];
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                LinkStateAdvertisementKey::new(1, 1, 1),
+                LinkStateAdvertisementKey::new(1, 1, 2),
+                LinkStateAdvertisementKey::new(1, 2, 1),
+                LinkStateAdvertisementKey::new(2, 1, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn insert_replaces_the_instance_of_the_same_lsa() {
+        let mut database = LinkStateDatabase::new();
+        assert!(database.insert(router_lsa(2, 0x80000001)).is_none());
+        assert!(database.insert(router_lsa(3, 0x80000001)).is_none());
+        let replaced = database.insert(router_lsa(2, 0x80000002)).unwrap();
+        assert_eq!(replaced.get_header().sequence_number, 0x80000001);
+        assert_eq!(database.len(), 2);
+        let lsa = router_lsa(2, 0x80000002);
+        let key = key(lsa.as_ref());
+        assert_eq!(
+            database.get_header(&key).unwrap().sequence_number,
+            0x80000002
+        );
+        assert_eq!(database.compare(lsa.get_header()), Some(Ordering::Equal));
+        assert_eq!(
+            database.compare(router_lsa(2, 0x80000001).get_header()),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            database.compare(router_lsa(4, 0x80000001).get_header()),
+            None
+        );
+        // another type with the same link state id and advertising router is another lsa.
+        let mut other_type = key;
+        other_type.lsa_type = NETWORK_LINK_STATE_TYPE;
+        assert!(database.get(&other_type).is_none());
+        assert!(database.remove(&key).is_some());
+        assert!(database.get(&key).is_none());
+        assert_eq!(database.len(), 1);
+    }
+
+    #[test]
+    fn iter_type_walks_one_type_in_key_order() {
+        let mut database = LinkStateDatabase::new();
+        database.insert(network_lsa(0xc0000201));
+        database.insert(router_lsa(3, 0x80000001));
+        database.insert(router_lsa(2, 0x80000001));
+        database.insert(network_lsa(0xc0000101));
+        let router_ids: Vec<u32> = database
+            .iter_type(ROUTER_LINK_STATE_TYPE)
+            .map(|lsa| lsa.get_header().link_state_id)
+            .collect();
+        assert_eq!(router_ids, vec![0x02020202, 0x03030303]);
+        let network_ids: Vec<u32> = database
+            .iter_type(NETWORK_LINK_STATE_TYPE)
+            .map(|lsa| lsa.get_header().link_state_id)
+            .collect();
+        assert_eq!(network_ids, vec![0xc0000101, 0xc0000201]);
+        assert_eq!(database.iter_type(3).count(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn age_grows_from_the_installed_age() {
+        let mut database = LinkStateDatabase::new();
+        let lsa = router_lsa(2, 0x80000001);
+        let key = key(lsa.as_ref());
+        database.insert(lsa);
+        time::advance(time::Duration::from_secs(10)).await;
+        assert_eq!(database.get_header(&key).unwrap().age, 11);
+        assert!(database.age().is_empty());
+        assert_eq!(database.get(&key).unwrap().get_header().age, 11);
+        time::advance(time::Duration::from_secs(MaxAge as u64)).await;
+        // reaching MaxAge, the lsa is handed out once to be flooded.
+        let max_aged_lsas = database.age();
+        assert_eq!(max_aged_lsas.len(), 1);
+        assert_eq!(max_aged_lsas[0].get_header().age as u32, MaxAge);
+        assert!(database.age().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn remove_flushed_keeps_the_pending_lsas() {
+        let mut database = LinkStateDatabase::new();
+        let flushed = router_lsa(2, 0x80000001);
+        let flushed_key = key(flushed.as_ref());
+        let pending = router_lsa(3, 0x80000001);
+        let pending_key = key(pending.as_ref());
+        let alive = router_lsa(4, 0x80000001);
+        let alive_key = key(alive.as_ref());
+        database.insert(flushed);
+        database.insert(pending);
+        database.insert(alive);
+        database.premature_age(&flushed_key).unwrap();
+        database.premature_age(&pending_key).unwrap();
+        let removed = database.remove_flushed(|key| *key == pending_key);
+        assert_eq!(removed, vec![flushed_key]);
+        assert!(database.get(&pending_key).is_some());
+        assert!(database.get(&alive_key).is_some());
+        assert_eq!(database.remove_flushed(|_| false), vec![pending_key]);
+        assert_eq!(database.len(), 1);
+    }
+
+    #[test]
+    fn verify_checksums_drops_the_corrupted_lsas() {
+        let mut database = LinkStateDatabase::new();
+        let lsa = router_lsa(2, 0x80000001);
+        let key = key(lsa.as_ref());
+        database.insert(lsa);
+        database.insert(router_lsa(3, 0x80000001));
+        assert_eq!(database.verify_checksums(), 0);
+        database
+            .get_mut(&key)
+            .unwrap()
+            .get_header_mut()
+            .sequence_number += 1;
+        assert_eq!(database.verify_checksums(), 1);
+        assert!(database.get(&key).is_none());
+        assert_eq!(database.len(), 1);
+    }
+}
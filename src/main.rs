@@ -1,4 +1,3 @@
-use ospf_lib::interface;
 use ospf_lib::prompt_and_read;
 use ospf_lib::router;
 use std::net;
@@ -20,7 +19,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap();
     let router = Arc::new(Mutex::new(router::Router::new(router_id)));
 
-    let _ = cli::cli(router_id);
+    let _ = cli::cli(router.clone());
 
     // let router_id = prompt_and_read("please enter router id:")
     //     .parse::<net::Ipv4Addr>()
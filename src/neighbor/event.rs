@@ -1,3 +1,4 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum NeighborEvent {
     HelloReceived,
     Start,
@@ -13,3 +14,22 @@ pub enum NeighborEvent {
     InactivityTimer,
     LLDown,
 }
+
+/// # enum NeighborAction
+/// what has to be done when the neighbor state machine takes a transition (RFC 2328 10.3).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NeighborAction {
+    /// send a hello to the neighbor directly, on NBMA networks.
+    SendHello,
+    /// start or restart the inactivity timer of RouterDeadInterval.
+    StartInactivityTimer,
+    StopInactivityTimer,
+    /// become master with a new DD sequence number and start sending empty DD packets.
+    StartDatabaseExchange,
+    /// list the headers of the whole link state database in the database summary list.
+    ListDatabaseSummary,
+    /// start requesting the LSAs of the link state request list.
+    SendLinkStateRequest,
+    /// clear the link state retransmission, database summary and link state request lists.
+    ClearLists,
+}
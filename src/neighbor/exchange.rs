@@ -0,0 +1,515 @@
+use std::{
+    cmp::Ordering,
+    net,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    error::ExchangeError,
+    interface::Interface,
+    lsa,
+    packet::dd::{
+        DataDescriptionPacket, DATA_DESCRIPTION_PACKET_TYPE, FEATURE_BIT_I, FEATURE_BIT_M,
+        FEATURE_BIT_MS,
+    },
+    MTU,
+};
+
+use super::{
+    event::NeighborEvent, handle, status::NeighborStatus, LastDatabaseDescription, Neighbor,
+};
+
+/// the most lsa headers put in one DD packet, so that it fits in the mtu
+/// along with the ip header and the longest authentication digest.
+pub const MAX_DD_LSA_HEADERS: usize = (MTU - 20 - DataDescriptionPacket::MIN_LENGTH - 64) / 20;
+
+/// what to do with a received DD packet once checked against the state of the neighbor.
+enum Reception {
+    Accept,
+    Ignore,
+    /// a duplicate of the last packet received, the slave answers it again.
+    Duplicate,
+    SeqNumberMismatch,
+}
+
+/// build the next DD packet of the exchange with `neighbor` and send it (RFC 2328 10.8).
+/// in ExStart it is the empty packet with the I, M and MS bits set, otherwise it describes
+/// the next lsa headers of the database summary list, which leave the list.
+/// the master retransmits the packet every RxmtInterval until it is answered.
+pub(crate) fn send_next_dd_packet(
+    interface: &Arc<Mutex<Interface>>,
+    locked_interface: &Interface,
+    neighbor_key: net::Ipv4Addr,
+    neighbor: &mut Neighbor,
+) {
+    let (features, lsa_headers) = if neighbor.status == NeighborStatus::ExStart {
+        (FEATURE_BIT_I | FEATURE_BIT_M | FEATURE_BIT_MS, Vec::new())
+    } else {
+        let count = neighbor.database_summary_list.len().min(MAX_DD_LSA_HEADERS);
+        let lsa_headers: Vec<_> = neighbor.database_summary_list.drain(..count).collect();
+        let mut features = 0;
+        if !neighbor.database_summary_list.is_empty() {
+            features |= FEATURE_BIT_M;
+        }
+        if neighbor.is_master {
+            features |= FEATURE_BIT_MS;
+        }
+        (features, lsa_headers)
+    };
+    let mut dd_packet = DataDescriptionPacket::new(
+        locked_interface.new_packet_header(DATA_DESCRIPTION_PACKET_TYPE),
+        MTU as u16,
+        locked_interface.get_options(),
+        features,
+        neighbor.dd_sequence_number,
+        lsa_headers,
+    );
+    let destination = locked_interface.get_neighbor_destination(neighbor);
+    let ip_packet = match locked_interface.create_ip_packet(destination, &mut dd_packet) {
+        Ok(ip_packet) => ip_packet,
+        Err(e) => {
+            crate::error(&format!("create dd packet failed: {}.", e));
+            return;
+        }
+    };
+    neighbor.last_sent_dd_features = features;
+    neighbor.last_sent_dd = Some(ip_packet.clone());
+    if !locked_interface.send_ip_packet(ip_packet) {
+        crate::error("send dd packet failed.");
+    }
+    if neighbor.is_master {
+        let handle = tokio::spawn(handle::dd_retransmission_raw_handle(
+            interface.clone(),
+            neighbor_key,
+            locked_interface.rxmt_interval,
+        ));
+        if let Some(old_handle) = neighbor.dd_retransmission_timer.replace(handle) {
+            old_handle.abort();
+        }
+    }
+}
+
+/// send the last DD packet to `neighbor` again, unchanged.
+pub(crate) fn resend_last_dd_packet(locked_interface: &Interface, neighbor: &Neighbor) {
+    if let Some(ip_packet) = &neighbor.last_sent_dd {
+        if !locked_interface.send_ip_packet(ip_packet.clone()) {
+            crate::error("resend dd packet failed.");
+        }
+    }
+}
+
+/// the receive procedure of a DD packet sent by `source` on the interface (RFC 2328 10.6).
+/// it negotiates the master and the slave in ExStart, then walks the exchange: the lsas
+/// newer than ours go to the link state request list, and the next packet is sent.
+/// any packet out of sequence restarts the exchange with SeqNumberMismatch.
+pub fn handle_dd_packet(
+    interface: &Arc<Mutex<Interface>>,
+    source: net::Ipv4Addr,
+    dd_packet: &DataDescriptionPacket,
+) -> Result<(), ExchangeError> {
+    let mut locked_interface = interface.lock().unwrap();
+    let router_id = net::Ipv4Addr::from(dd_packet.header.router_id);
+    let neighbor_key = locked_interface.get_neighbor_key(source, router_id);
+    if dd_packet.interface_mtu as usize > MTU {
+        return Err(ExchangeError::MtuMismatch(dd_packet.interface_mtu));
+    }
+    let neighbors = locked_interface.neighbors.clone();
+    let status = neighbors
+        .lock()
+        .unwrap()
+        .get(&neighbor_key)
+        .map(|neighbor| neighbor.status)
+        .ok_or(ExchangeError::UnknownNeighbor(source))?;
+    let status = match status {
+        NeighborStatus::Down | NeighborStatus::Attempt => {
+            return Err(ExchangeError::NeighborNotReady)
+        }
+        NeighborStatus::TwoWay => return Ok(()),
+        NeighborStatus::Init => {
+            let status = super::apply_event(
+                interface,
+                &mut locked_interface,
+                neighbor_key,
+                NeighborEvent::TwoWayReceived,
+            );
+            if status != Some(NeighborStatus::ExStart) {
+                return Ok(());
+            }
+            NeighborStatus::ExStart
+        }
+        status => status,
+    };
+
+    let received = LastDatabaseDescription::from_packet(dd_packet);
+    let mut locked_neighbors = neighbors.lock().unwrap();
+    let neighbor = locked_neighbors
+        .get_mut(&neighbor_key)
+        .ok_or(ExchangeError::UnknownNeighbor(source))?;
+    let is_duplicate = neighbor.last_received_dd == Some(received);
+    let reception = match status {
+        NeighborStatus::ExStart => {
+            let initialize = FEATURE_BIT_I | FEATURE_BIT_M | FEATURE_BIT_MS;
+            if dd_packet.features & initialize == initialize
+                && dd_packet.lsa_headers.is_empty()
+                && router_id > locked_interface.router_id
+            {
+                // the neighbor is the master, we follow its sequence number.
+                neighbor.is_master = false;
+                neighbor.dd_sequence_number = dd_packet.dd_sequence_number;
+            } else if dd_packet.features & (FEATURE_BIT_I | FEATURE_BIT_MS) == 0
+                && dd_packet.dd_sequence_number == neighbor.dd_sequence_number
+                && router_id < locked_interface.router_id
+            {
+                neighbor.is_master = true;
+            } else {
+                return Ok(());
+            }
+            neighbor.options = dd_packet.options;
+            drop(locked_neighbors);
+            super::apply_event(
+                interface,
+                &mut locked_interface,
+                neighbor_key,
+                NeighborEvent::NegotiationDone,
+            );
+            locked_neighbors = neighbors.lock().unwrap();
+            Reception::Accept
+        }
+        NeighborStatus::Exchange => {
+            let sent_by_master = dd_packet.features & FEATURE_BIT_MS != 0;
+            let expected_sequence_number = if neighbor.is_master {
+                neighbor.dd_sequence_number
+            } else {
+                neighbor.dd_sequence_number.wrapping_add(1)
+            };
+            if is_duplicate {
+                Reception::Duplicate
+            } else if sent_by_master == neighbor.is_master
+                || dd_packet.features & FEATURE_BIT_I != 0
+                || dd_packet.options != neighbor.options
+                || dd_packet.dd_sequence_number != expected_sequence_number
+            {
+                Reception::SeqNumberMismatch
+            } else {
+                Reception::Accept
+            }
+        }
+        NeighborStatus::Loading | NeighborStatus::Full => {
+            if is_duplicate {
+                Reception::Duplicate
+            } else {
+                Reception::SeqNumberMismatch
+            }
+        }
+        _ => Reception::Ignore,
+    };
+    let neighbor = match locked_neighbors.get_mut(&neighbor_key) {
+        Some(neighbor) => neighbor,
+        None => return Ok(()),
+    };
+    match reception {
+        Reception::Ignore => return Ok(()),
+        Reception::Duplicate => {
+            if !neighbor.is_master {
+                resend_last_dd_packet(&locked_interface, neighbor);
+            }
+            return Ok(());
+        }
+        Reception::SeqNumberMismatch => {
+            drop(locked_neighbors);
+            super::apply_event(
+                interface,
+                &mut locked_interface,
+                neighbor_key,
+                NeighborEvent::SeqNumberMismatch,
+            );
+            return Ok(());
+        }
+        Reception::Accept => {}
+    }
+
+    neighbor.last_received_dd = Some(received);
+    let external_routing_capable = locked_interface.get_options() & crate::OPTION_BIT_E != 0;
+    for lsa_header in &dd_packet.lsa_headers {
+        if !lsa::is_lsa_type_known(lsa_header.lsa_type, external_routing_capable) {
+            drop(locked_neighbors);
+            super::apply_event(
+                interface,
+                &mut locked_interface,
+                neighbor_key,
+                NeighborEvent::SeqNumberMismatch,
+            );
+            return Ok(());
+        }
+        let is_newer = locked_interface
+            .lookup_lsa_header(lsa_header)
+            .is_none_or(|our_header| lsa_header.compare(&our_header) == Ordering::Greater);
+        if is_newer {
+            neighbor.add_link_state_request(*lsa_header);
+        }
+    }
+    let more = dd_packet.features & FEATURE_BIT_M != 0;
+    let exchange_done = if neighbor.is_master {
+        neighbor.dd_sequence_number = neighbor.dd_sequence_number.wrapping_add(1);
+        let all_sent = neighbor.last_sent_dd_features & FEATURE_BIT_M == 0;
+        if all_sent && !more {
+            if let Some(handle) = neighbor.dd_retransmission_timer.take() {
+                handle.abort();
+            }
+            true
+        } else {
+            send_next_dd_packet(interface, &locked_interface, neighbor_key, neighbor);
+            false
+        }
+    } else {
+        neighbor.dd_sequence_number = dd_packet.dd_sequence_number;
+        send_next_dd_packet(interface, &locked_interface, neighbor_key, neighbor);
+        !more && neighbor.last_sent_dd_features & FEATURE_BIT_M == 0
+    };
+    drop(locked_neighbors);
+    if exchange_done {
+        super::apply_event(
+            interface,
+            &mut locked_interface,
+            neighbor_key,
+            NeighborEvent::ExchangeDone,
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::{
+        interface::{self, event::InterfaceEvent, InterfaceNetworkType},
+        packet::OspfPacketHeader,
+        testing, OPTION_BIT_E, OSPF_VERSION_2,
+    };
+
+    const IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 1);
+    const NEIGHBOR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 2);
+    const LOW_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(1, 1, 1, 1);
+    const HIGH_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(2, 2, 2, 2);
+    const INITIALIZE: u8 = FEATURE_BIT_I | FEATURE_BIT_M | FEATURE_BIT_MS;
+
+    /// a point-to-point interface of `router_id` whose neighbor `neighbor_id` is in ExStart,
+    /// the initial DD packet we sent is already taken out of the receiver.
+    fn exstart(
+        router_id: net::Ipv4Addr,
+        neighbor_id: net::Ipv4Addr,
+    ) -> (Arc<Mutex<Interface>>, broadcast::Receiver<bytes::Bytes>) {
+        let (interface, mut send_packet_rx) =
+            testing::new_interface(IP_ADDR, router_id, InterfaceNetworkType::PointToPoint);
+        interface::handle_event(&interface, InterfaceEvent::InterfaceUp);
+        let hello_packet = testing::new_hello_packet(
+            neighbor_id,
+            1,
+            net::Ipv4Addr::UNSPECIFIED,
+            net::Ipv4Addr::UNSPECIFIED,
+            vec![router_id],
+        );
+        interface::handle_hello_packet(&interface, NEIGHBOR_IP_ADDR, &hello_packet).unwrap();
+        assert_eq!(status(&interface, neighbor_id), NeighborStatus::ExStart);
+        let sent = sent_dd_packets(&mut send_packet_rx);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, INITIALIZE);
+        (interface, send_packet_rx)
+    }
+
+    fn dd_packet(
+        router_id: net::Ipv4Addr,
+        options: u8,
+        features: u8,
+        sequence_number: u32,
+    ) -> DataDescriptionPacket {
+        DataDescriptionPacket::new(
+            OspfPacketHeader::new(
+                OSPF_VERSION_2,
+                DATA_DESCRIPTION_PACKET_TYPE,
+                0,
+                router_id.into(),
+                0,
+                0,
+                0,
+                0,
+            ),
+            MTU as u16,
+            options,
+            features,
+            sequence_number,
+            Vec::new(),
+        )
+    }
+
+    fn receive(interface: &Arc<Mutex<Interface>>, dd_packet: &DataDescriptionPacket) {
+        handle_dd_packet(interface, NEIGHBOR_IP_ADDR, dd_packet).unwrap();
+    }
+
+    /// the features and the sequence number of the DD packets sent since the last call.
+    fn sent_dd_packets(send_packet_rx: &mut broadcast::Receiver<bytes::Bytes>) -> Vec<(u8, u32)> {
+        testing::sent_packets(send_packet_rx)
+            .iter()
+            .filter_map(|(_, packet)| packet.as_any().downcast_ref::<DataDescriptionPacket>())
+            .map(|dd_packet| (dd_packet.features, dd_packet.dd_sequence_number))
+            .collect()
+    }
+
+    fn neighbor<T>(
+        interface: &Arc<Mutex<Interface>>,
+        neighbor_id: net::Ipv4Addr,
+        f: impl FnOnce(&Neighbor) -> T,
+    ) -> T {
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        let locked_neighbors = neighbors.lock().unwrap();
+        f(&locked_neighbors[&neighbor_id])
+    }
+
+    fn status(interface: &Arc<Mutex<Interface>>, neighbor_id: net::Ipv4Addr) -> NeighborStatus {
+        neighbor(interface, neighbor_id, |neighbor| neighbor.status)
+    }
+
+    /// we are the slave of HIGH_ROUTER_ID, in Exchange after answering its sequence number 5000.
+    fn exchange_as_slave() -> (Arc<Mutex<Interface>>, broadcast::Receiver<bytes::Bytes>) {
+        let (interface, mut send_packet_rx) = exstart(LOW_ROUTER_ID, HIGH_ROUTER_ID);
+        receive(
+            &interface,
+            &dd_packet(HIGH_ROUTER_ID, OPTION_BIT_E, INITIALIZE, 5000),
+        );
+        assert_eq!(status(&interface, HIGH_ROUTER_ID), NeighborStatus::Exchange);
+        assert!(!neighbor(&interface, HIGH_ROUTER_ID, |neighbor| neighbor.is_master));
+        // the slave echoes the sequence number of the master, with MS clear.
+        assert_eq!(sent_dd_packets(&mut send_packet_rx), vec![(0, 5000)]);
+        (interface, send_packet_rx)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn negotiation_makes_us_slave_of_a_higher_router_id() {
+        exchange_as_slave();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn negotiation_makes_us_master_of_a_lower_router_id() {
+        let (interface, mut send_packet_rx) = exstart(HIGH_ROUTER_ID, LOW_ROUTER_ID);
+        let sequence_number = neighbor(&interface, LOW_ROUTER_ID, |neighbor| {
+            neighbor.dd_sequence_number
+        });
+        // the initial packet of the neighbor loses, it is not acted upon.
+        receive(
+            &interface,
+            &dd_packet(LOW_ROUTER_ID, OPTION_BIT_E, INITIALIZE, 77),
+        );
+        assert_eq!(status(&interface, LOW_ROUTER_ID), NeighborStatus::ExStart);
+        assert!(sent_dd_packets(&mut send_packet_rx).is_empty());
+        // the neighbor answers as the slave, with our sequence number.
+        receive(
+            &interface,
+            &dd_packet(LOW_ROUTER_ID, OPTION_BIT_E, FEATURE_BIT_M, sequence_number),
+        );
+        assert_eq!(status(&interface, LOW_ROUTER_ID), NeighborStatus::Exchange);
+        assert!(neighbor(&interface, LOW_ROUTER_ID, |neighbor| neighbor.is_master));
+        assert_eq!(
+            sent_dd_packets(&mut send_packet_rx),
+            vec![(FEATURE_BIT_MS, sequence_number + 1)]
+        );
+        // M still set by the slave, the exchange goes on.
+        receive(
+            &interface,
+            &dd_packet(
+                LOW_ROUTER_ID,
+                OPTION_BIT_E,
+                FEATURE_BIT_M,
+                sequence_number + 1,
+            ),
+        );
+        assert_eq!(status(&interface, LOW_ROUTER_ID), NeighborStatus::Exchange);
+        assert_eq!(
+            sent_dd_packets(&mut send_packet_rx),
+            vec![(FEATURE_BIT_MS, sequence_number + 2)]
+        );
+        // M clear on both sides ends the exchange, there is nothing to request.
+        receive(
+            &interface,
+            &dd_packet(LOW_ROUTER_ID, OPTION_BIT_E, 0, sequence_number + 2),
+        );
+        assert_eq!(status(&interface, LOW_ROUTER_ID), NeighborStatus::Full);
+        assert!(sent_dd_packets(&mut send_packet_rx).is_empty());
+        assert!(neighbor(&interface, LOW_ROUTER_ID, |neighbor| neighbor
+            .dd_retransmission_timer
+            .is_none()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slave_ends_the_exchange_when_m_is_clear_on_both_sides() {
+        let (interface, mut send_packet_rx) = exchange_as_slave();
+        receive(
+            &interface,
+            &dd_packet(HIGH_ROUTER_ID, OPTION_BIT_E, FEATURE_BIT_MS, 5001),
+        );
+        assert_eq!(sent_dd_packets(&mut send_packet_rx), vec![(0, 5001)]);
+        assert_eq!(status(&interface, HIGH_ROUTER_ID), NeighborStatus::Full);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slave_resends_its_last_packet_on_a_duplicate() {
+        let (interface, mut send_packet_rx) = exchange_as_slave();
+        receive(
+            &interface,
+            &dd_packet(HIGH_ROUTER_ID, OPTION_BIT_E, INITIALIZE, 5000),
+        );
+        assert_eq!(status(&interface, HIGH_ROUTER_ID), NeighborStatus::Exchange);
+        assert_eq!(sent_dd_packets(&mut send_packet_rx), vec![(0, 5000)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn master_ignores_a_duplicate() {
+        let (interface, mut send_packet_rx) = exstart(HIGH_ROUTER_ID, LOW_ROUTER_ID);
+        let sequence_number = neighbor(&interface, LOW_ROUTER_ID, |neighbor| {
+            neighbor.dd_sequence_number
+        });
+        let answer = dd_packet(LOW_ROUTER_ID, OPTION_BIT_E, FEATURE_BIT_M, sequence_number);
+        receive(&interface, &answer);
+        assert_eq!(sent_dd_packets(&mut send_packet_rx).len(), 1);
+        receive(&interface, &answer);
+        assert_eq!(status(&interface, LOW_ROUTER_ID), NeighborStatus::Exchange);
+        assert!(sent_dd_packets(&mut send_packet_rx).is_empty());
+        assert_eq!(
+            neighbor(&interface, LOW_ROUTER_ID, |neighbor| neighbor
+                .dd_sequence_number),
+            sequence_number + 1
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn out_of_sequence_packets_restart_the_exchange() {
+        let mismatches = [
+            // a sequence number skipped.
+            dd_packet(HIGH_ROUTER_ID, OPTION_BIT_E, FEATURE_BIT_MS, 5002),
+            // the I bit set again.
+            dd_packet(
+                HIGH_ROUTER_ID,
+                OPTION_BIT_E,
+                FEATURE_BIT_I | FEATURE_BIT_MS,
+                5001,
+            ),
+            // the options changed.
+            dd_packet(HIGH_ROUTER_ID, 0, FEATURE_BIT_MS, 5001),
+            // the master claiming to be the slave.
+            dd_packet(HIGH_ROUTER_ID, OPTION_BIT_E, 0, 5001),
+        ];
+        for dd_packet in mismatches {
+            let (interface, mut send_packet_rx) = exchange_as_slave();
+            receive(&interface, &dd_packet);
+            // SeqNumberMismatch: back to ExStart, claiming to be the master again.
+            assert_eq!(status(&interface, HIGH_ROUTER_ID), NeighborStatus::ExStart);
+            assert!(neighbor(&interface, HIGH_ROUTER_ID, |neighbor| neighbor.is_master));
+            let sent = sent_dd_packets(&mut send_packet_rx);
+            assert_eq!(sent.len(), 1);
+            assert_eq!(sent[0].0, INITIALIZE);
+        }
+    }
+}
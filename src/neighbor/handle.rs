@@ -0,0 +1,302 @@
+use std::{
+    net,
+    sync::{Arc, Mutex},
+};
+
+use tokio::time;
+
+use crate::{interface::Interface, lsdb::LinkStateAdvertisementKey};
+
+use super::status::NeighborStatus;
+
+/// the inactivity timer of a neighbor, fires InactivityTimer when no hello
+/// was heard from it for RouterDeadInterval. a hello restarts the timer by replacing it,
+/// a timer that expired while being replaced finds it is no longer armed and does nothing.
+pub async fn inactivity_timer_raw_handle(
+    interface: Arc<Mutex<Interface>>,
+    neighbor_key: net::Ipv4Addr,
+    router_dead_interval: u32,
+    generation: u64,
+) {
+    time::sleep(time::Duration::from_secs(router_dead_interval as u64)).await;
+    let mut locked_interface = interface.lock().unwrap();
+    let is_armed = locked_interface
+        .neighbors
+        .lock()
+        .unwrap()
+        .get(&neighbor_key)
+        .is_some_and(|neighbor| {
+            neighbor.dead_timer.is_some() && neighbor.dead_timer_generation == generation
+        });
+    if !is_armed {
+        return;
+    }
+    crate::log(&format!(
+        "neighbor {} not heard for {} seconds, declared dead.",
+        neighbor_key, router_dead_interval
+    ));
+    super::apply_event(
+        &interface,
+        &mut locked_interface,
+        neighbor_key,
+        super::event::NeighborEvent::InactivityTimer,
+    );
+}
+
+/// the retransmission timer of the DD packets of the master (RFC 2328 10.8), sends the
+/// last DD packet again every RxmtInterval until it is answered and a new one replaces it.
+/// the slave never retransmits, it only answers the duplicates the master sends.
+pub async fn dd_retransmission_raw_handle(
+    interface: Arc<Mutex<Interface>>,
+    neighbor_key: net::Ipv4Addr,
+    rxmt_interval: u32,
+) {
+    loop {
+        time::sleep(time::Duration::from_secs(rxmt_interval as u64)).await;
+        let locked_interface = interface.lock().unwrap();
+        let neighbors = locked_interface.neighbors.clone();
+        let neighbors = neighbors.lock().unwrap();
+        let neighbor = match neighbors.get(&neighbor_key) {
+            Some(neighbor) => neighbor,
+            None => return,
+        };
+        match neighbor.status {
+            NeighborStatus::ExStart => {}
+            NeighborStatus::Exchange if neighbor.is_master => {}
+            _ => return,
+        }
+        crate::debug(&format!(
+            "neighbor {} did not answer, dd packet {} sent again.",
+            neighbor_key, neighbor.dd_sequence_number
+        ));
+        super::exchange::resend_last_dd_packet(&locked_interface, neighbor);
+    }
+}
+
+/// the retransmission timer of the LSR packets (RFC 2328 10.9), requests the lsas
+/// still awaited again every RxmtInterval while the neighbor is Loading.
+pub async fn lsr_retransmission_raw_handle(
+    interface: Arc<Mutex<Interface>>,
+    neighbor_key: net::Ipv4Addr,
+    rxmt_interval: u32,
+) {
+    loop {
+        time::sleep(time::Duration::from_secs(rxmt_interval as u64)).await;
+        let locked_interface = interface.lock().unwrap();
+        let neighbors = locked_interface.neighbors.clone();
+        let mut neighbors = neighbors.lock().unwrap();
+        let neighbor = match neighbors.get_mut(&neighbor_key) {
+            Some(neighbor) => neighbor,
+            None => return,
+        };
+        if neighbor.status != NeighborStatus::Loading || neighbor.link_state_request_list.is_empty()
+        {
+            return;
+        }
+        crate::debug(&format!(
+            "neighbor {} did not answer, lsr packet sent again.",
+            neighbor_key
+        ));
+        super::request::send_lsr_packet(&locked_interface, neighbor);
+    }
+}
+
+/// the retransmission timer of the flooded lsas (RFC 2328 13.6), sends the lsas of
+/// the link state retransmission list again every RxmtInterval until they are
+/// acknowledged, always directly to the neighbor.
+pub async fn lsu_retransmission_raw_handle(
+    interface: Arc<Mutex<Interface>>,
+    neighbor_key: net::Ipv4Addr,
+    rxmt_interval: u32,
+) {
+    loop {
+        time::sleep(time::Duration::from_secs(rxmt_interval as u64)).await;
+        let locked_interface = interface.lock().unwrap();
+        let neighbors = locked_interface.neighbors.clone();
+        let neighbors = neighbors.lock().unwrap();
+        let neighbor = match neighbors.get(&neighbor_key) {
+            Some(neighbor) => neighbor,
+            None => return,
+        };
+        if neighbor.status < NeighborStatus::Exchange
+            || neighbor.link_state_retransmission_list.is_empty()
+        {
+            return;
+        }
+        let lsas: Vec<_> = neighbor
+            .link_state_retransmission_list
+            .iter()
+            .filter_map(|lsa_header| {
+                locked_interface.get_lsa(&LinkStateAdvertisementKey::from_header(lsa_header))
+            })
+            .collect();
+        let destination = locked_interface.get_neighbor_destination(neighbor);
+        drop(neighbors);
+        crate::debug(&format!(
+            "neighbor {} did not acknowledge {} lsa(s), sent again.",
+            neighbor_key,
+            lsas.len()
+        ));
+        super::request::send_lsu_packets(&locked_interface, destination, lsas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net,
+        sync::{Arc, Mutex},
+    };
+
+    use tokio::time;
+
+    use crate::{
+        interface::{
+            self, Interface, InterfaceNetworkType, DEFAULT_HELLO_INTERVAL,
+            DEFAULT_ROUTER_DEAD_INTERVAL,
+        },
+        lsa::LinkStateAdvertisementHeader,
+        neighbor::status::NeighborStatus,
+        packet::hello::HELLO_PACKET_TYPE,
+        testing,
+    };
+
+    const IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 1);
+    const ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(1, 1, 1, 1);
+    const NEIGHBOR_IP_ADDR: net::Ipv4Addr = net::Ipv4Addr::new(192, 0, 2, 2);
+    const NEIGHBOR_ROUTER_ID: net::Ipv4Addr = net::Ipv4Addr::new(2, 2, 2, 2);
+
+    fn receive_hello(interface: &Arc<Mutex<Interface>>) {
+        let hello_packet = testing::new_hello_packet(
+            NEIGHBOR_ROUTER_ID,
+            0,
+            net::Ipv4Addr::UNSPECIFIED,
+            net::Ipv4Addr::UNSPECIFIED,
+            Vec::new(),
+        );
+        interface::handle_hello_packet(interface, NEIGHBOR_IP_ADDR, &hello_packet).unwrap();
+    }
+
+    fn router_dead_interval() -> time::Duration {
+        time::Duration::from_secs(DEFAULT_ROUTER_DEAD_INTERVAL as u64)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hello_restarts_the_timer() {
+        let (interface, _send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        receive_hello(&interface);
+        time::sleep(router_dead_interval() - time::Duration::from_secs(1)).await;
+        receive_hello(&interface);
+        // the first timer would have expired by now.
+        time::sleep(time::Duration::from_secs(2)).await;
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        assert_eq!(
+            neighbors.lock().unwrap()[&NEIGHBOR_IP_ADDR].status,
+            NeighborStatus::Init
+        );
+        time::sleep(router_dead_interval()).await;
+        assert!(neighbors.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expiry_removes_the_neighbor() {
+        let (interface, _send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::Broadcast);
+        receive_hello(&interface);
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        assert!(neighbors.lock().unwrap().contains_key(&NEIGHBOR_IP_ADDR));
+        time::sleep(router_dead_interval() + time::Duration::from_millis(1)).await;
+        assert!(neighbors.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expiry_takes_a_full_neighbor_down() {
+        let (interface, _send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::NBMA);
+        receive_hello(&interface);
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        let area = interface.lock().unwrap().area.clone().unwrap();
+        let router_lsa_trigger = area.lock().unwrap().get_router_lsa_trigger();
+        {
+            let mut locked_neighbors = neighbors.lock().unwrap();
+            let neighbor = locked_neighbors.get_mut(&NEIGHBOR_IP_ADDR).unwrap();
+            let lsa_header = LinkStateAdvertisementHeader::new(1, 0, 1, 1, 1, 0x80000001, 0, 0);
+            neighbor.status = NeighborStatus::Full;
+            neighbor.link_state_retransmission_list.push(lsa_header);
+            neighbor.database_summary_list.push(lsa_header);
+            neighbor.link_state_request_list.push(lsa_header);
+        }
+        assert!(
+            time::timeout(time::Duration::ZERO, router_lsa_trigger.notified())
+                .await
+                .is_err()
+        );
+        time::sleep(router_dead_interval() + time::Duration::from_millis(1)).await;
+        {
+            // the neighbors of NBMA networks are kept while down.
+            let locked_neighbors = neighbors.lock().unwrap();
+            let neighbor = &locked_neighbors[&NEIGHBOR_IP_ADDR];
+            assert_eq!(neighbor.status, NeighborStatus::Down);
+            assert!(neighbor.link_state_retransmission_list.is_empty());
+            assert!(neighbor.database_summary_list.is_empty());
+            assert!(neighbor.link_state_request_list.is_empty());
+            assert!(neighbor.dead_timer.is_none());
+        }
+        // the router-lsa no longer lists the adjacency.
+        assert!(
+            time::timeout(time::Duration::ZERO, router_lsa_trigger.notified())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn configured_neighbor_is_attempted_when_the_interface_comes_up() {
+        let (interface, mut send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::NBMA);
+        interface.lock().unwrap().nbma_neighbors = vec![NEIGHBOR_IP_ADDR];
+        interface::handle_event(&interface, interface::event::InterfaceEvent::InterfaceUp);
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        assert_eq!(
+            neighbors.lock().unwrap()[&NEIGHBOR_IP_ADDR].status,
+            NeighborStatus::Attempt
+        );
+        let is_hello_to_neighbor =
+            |(destination, packet): &(net::Ipv4Addr, Box<dyn crate::packet::OspfPacket + Send>)| {
+                *destination == NEIGHBOR_IP_ADDR && packet.get_type() == HELLO_PACKET_TYPE
+            };
+        let sent_packets = testing::sent_packets(&mut send_packet_rx);
+        assert_eq!(sent_packets.len(), 1);
+        assert!(is_hello_to_neighbor(&sent_packets[0]));
+        // the neighbor cannot be reached by multicast, the hellos keep going to it.
+        time::sleep(
+            time::Duration::from_secs(DEFAULT_HELLO_INTERVAL as u64)
+                + time::Duration::from_millis(1),
+        )
+        .await;
+        let sent_packets = testing::sent_packets(&mut send_packet_rx);
+        assert_eq!(sent_packets.len(), 1);
+        assert!(is_hello_to_neighbor(&sent_packets[0]));
+        // its answer moves it on.
+        receive_hello(&interface);
+        assert_eq!(
+            neighbors.lock().unwrap()[&NEIGHBOR_IP_ADDR].status,
+            NeighborStatus::Init
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn silent_configured_neighbor_goes_back_down() {
+        let (interface, _send_packet_rx) =
+            testing::new_interface(IP_ADDR, ROUTER_ID, InterfaceNetworkType::NBMA);
+        interface.lock().unwrap().nbma_neighbors = vec![NEIGHBOR_IP_ADDR];
+        interface::handle_event(&interface, interface::event::InterfaceEvent::InterfaceUp);
+        let neighbors = interface.lock().unwrap().get_neighbors();
+        time::sleep(router_dead_interval() + time::Duration::from_millis(1)).await;
+        assert_eq!(
+            neighbors.lock().unwrap()[&NEIGHBOR_IP_ADDR].status,
+            NeighborStatus::Down
+        );
+    }
+}
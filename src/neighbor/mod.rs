@@ -1,15 +1,82 @@
 use core::net;
+use std::{
+    cmp::Ordering,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use tokio::task::JoinHandle;
 
+use crate::{
+    interface::{self, Interface},
+    lsa::LinkStateAdvertisementHeader,
+    packet::dd::DataDescriptionPacket,
+};
+
 pub mod event;
+pub mod exchange;
+pub mod handle;
+pub mod request;
 pub mod status;
 
+/// # struct LastDatabaseDescription
+/// what the last DD packet received from a neighbor carried, to tell duplicates apart.
+/// - features : its I, M and MS bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LastDatabaseDescription {
+    pub features: u8,
+    pub options: u8,
+    pub dd_sequence_number: u32,
+}
 
+impl LastDatabaseDescription {
+    pub fn from_packet(dd_packet: &DataDescriptionPacket) -> Self {
+        Self {
+            features: dd_packet.features,
+            options: dd_packet.options,
+            dd_sequence_number: dd_packet.dd_sequence_number,
+        }
+    }
+}
+
+/// # struct Neighbor
+/// the conversation between the router and one of its neighbors on an interface (RFC 2328 10).
+/// - router_id : the neighbor id, the router id the neighbor puts in its packets.
+/// - designated_router, backup_designated_router : the DR and BDR the neighbor declares in its hellos.
+/// - is_master : whether we are the master of the database exchange with the neighbor.
+/// - dd_sequence_number : the sequence number of the DD packet being sent to the neighbor.
+/// - last_received_dd : the last DD packet received from the neighbor, none when the exchange starts.
+/// - last_sent_dd : the last DD packet sent to the neighbor, as an ip packet, for retransmissions.
+/// - last_sent_dd_features : the I, M and MS bits of the last DD packet sent.
+/// - outstanding_request_count : how many entries at the head of the link state request list
+///   were asked for in the last LSR packet and are still awaited.
+/// - dead_timer_generation : bumped each time the inactivity timer is restarted.
+/// - link_state_retransmission_list : lsas flooded to the neighbor but not acknowledged yet.
+/// - lsu_retransmission_timer : sends the lsas of the retransmission list again, running while it is not empty.
+/// - database_summary_list : the database headers still to describe in DD packets.
+/// - link_state_request_list : the lsas to request from the neighbor, newer than ours.
 pub struct Neighbor {
     pub ipv4_addr: net::Ipv4Addr,
+    pub router_id: net::Ipv4Addr,
+    pub priority: u8,
+    pub options: u8,
+    pub designated_router: net::Ipv4Addr,
+    pub backup_designated_router: net::Ipv4Addr,
     pub status: status::NeighborStatus,
+    pub is_master: bool,
+    pub dd_sequence_number: u32,
+    pub last_received_dd: Option<LastDatabaseDescription>,
+    pub last_sent_dd: Option<bytes::Bytes>,
+    pub last_sent_dd_features: u8,
     pub dead_timer: Option<JoinHandle<()>>,
+    pub dead_timer_generation: u64,
+    pub dd_retransmission_timer: Option<JoinHandle<()>>,
+    pub link_state_retransmission_list: Vec<LinkStateAdvertisementHeader>,
+    pub database_summary_list: Vec<LinkStateAdvertisementHeader>,
+    pub link_state_request_list: Vec<LinkStateAdvertisementHeader>,
+    pub outstanding_request_count: usize,
+    pub lsr_retransmission_timer: Option<JoinHandle<()>>,
+    pub lsu_retransmission_timer: Option<JoinHandle<()>>,
 }
 
 unsafe impl Send for Neighbor {}
@@ -19,8 +86,265 @@ impl Neighbor {
     pub fn new(ipv4_addr: net::Ipv4Addr) -> Self {
         Self {
             ipv4_addr,
+            router_id: net::Ipv4Addr::UNSPECIFIED,
+            priority: 0,
+            options: 0,
+            designated_router: net::Ipv4Addr::UNSPECIFIED,
+            backup_designated_router: net::Ipv4Addr::UNSPECIFIED,
             status: status::NeighborStatus::Down,
+            is_master: false,
+            dd_sequence_number: 0,
+            last_received_dd: None,
+            last_sent_dd: None,
+            last_sent_dd_features: 0,
             dead_timer: None,
+            dead_timer_generation: 0,
+            dd_retransmission_timer: None,
+            link_state_retransmission_list: Vec::new(),
+            database_summary_list: Vec::new(),
+            link_state_request_list: Vec::new(),
+            outstanding_request_count: 0,
+            lsr_retransmission_timer: None,
+            lsu_retransmission_timer: None,
+        }
+    }
+
+    /// enter ExStart with the neighbor (RFC 2328 10.3): claim to be the master
+    /// and bump the DD sequence number, picking a fresh one the first time.
+    pub fn start_database_exchange(&mut self) {
+        self.is_master = true;
+        self.dd_sequence_number = if self.dd_sequence_number == 0 {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as u32)
+                .unwrap_or(1)
+                .max(1)
+        } else {
+            self.dd_sequence_number.wrapping_add(1)
+        };
+        self.last_received_dd = None;
+    }
+
+    /// forget the state of the adjacency once the neighbor is down: the lists,
+    /// the inactivity timer and the database exchange.
+    pub fn tear_down(&mut self) {
+        self.clear_lists();
+        self.dead_timer = None;
+        self.is_master = false;
+        self.last_received_dd = None;
+        self.last_sent_dd = None;
+        if let Some(handle) = self.dd_retransmission_timer.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.lsr_retransmission_timer.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.lsu_retransmission_timer.take() {
+            handle.abort();
+        }
+    }
+
+    /// put `lsa_header` in the link state request list, replacing an older request
+    /// for the same lsa.
+    pub fn add_link_state_request(&mut self, lsa_header: LinkStateAdvertisementHeader) {
+        match self
+            .link_state_request_list
+            .iter_mut()
+            .find(|request| request.is_same_lsa(&lsa_header))
+        {
+            Some(request) => {
+                if lsa_header.compare(request) == Ordering::Greater {
+                    *request = lsa_header;
+                }
+            }
+            None => self.link_state_request_list.push(lsa_header),
+        }
+    }
+
+    /// remove the request the received instance `lsa_header` answers from the
+    /// link state request list, an older instance answers nothing.
+    /// returns whether a request was removed.
+    pub fn satisfy_link_state_request(
+        &mut self,
+        lsa_header: &LinkStateAdvertisementHeader,
+    ) -> bool {
+        let index = match self
+            .link_state_request_list
+            .iter()
+            .position(|request| request.is_same_lsa(lsa_header))
+        {
+            Some(index) => index,
+            None => return false,
+        };
+        if lsa_header.compare(&self.link_state_request_list[index]) == Ordering::Less {
+            return false;
+        }
+        self.link_state_request_list.remove(index);
+        if index < self.outstanding_request_count {
+            self.outstanding_request_count -= 1;
         }
+        true
+    }
+
+    /// put `lsa_header` in the link state retransmission list, replacing the instance
+    /// of the same lsa flooded before.
+    pub fn add_link_state_retransmission(&mut self, lsa_header: LinkStateAdvertisementHeader) {
+        self.remove_link_state_retransmission(&lsa_header);
+        self.link_state_retransmission_list.push(lsa_header);
+    }
+
+    /// take any instance of the lsa described by `lsa_header` off the link state
+    /// retransmission list. returns whether one was removed.
+    pub fn remove_link_state_retransmission(
+        &mut self,
+        lsa_header: &LinkStateAdvertisementHeader,
+    ) -> bool {
+        let length = self.link_state_retransmission_list.len();
+        self.link_state_retransmission_list
+            .retain(|retransmission| !retransmission.is_same_lsa(lsa_header));
+        self.link_state_retransmission_list.len() != length
+    }
+
+    pub fn clear_lists(&mut self) {
+        self.link_state_retransmission_list.clear();
+        self.database_summary_list.clear();
+        self.link_state_request_list.clear();
+        self.outstanding_request_count = 0;
+    }
+}
+
+/// feed `event` to the state machine of the neighbor `neighbor_key` of `interface`
+/// and take the actions of the transition. the interface must not be locked by the caller.
+/// returns the state the neighbor ends in, or none if there is no such neighbor.
+pub fn handle_event(
+    interface: &Arc<Mutex<Interface>>,
+    neighbor_key: net::Ipv4Addr,
+    event: event::NeighborEvent,
+) -> Option<status::NeighborStatus> {
+    let mut locked_interface = interface.lock().unwrap();
+    apply_event(interface, &mut locked_interface, neighbor_key, event)
+}
+
+/// like `handle_event`, for callers already holding the lock of the interface.
+pub(crate) fn apply_event(
+    interface: &Arc<Mutex<Interface>>,
+    locked_interface: &mut Interface,
+    neighbor_key: net::Ipv4Addr,
+    event: event::NeighborEvent,
+) -> Option<status::NeighborStatus> {
+    let neighbors = locked_interface.neighbors.clone();
+    let mut neighbors = neighbors.lock().unwrap();
+    let neighbor = neighbors.get_mut(&neighbor_key)?;
+    let old_status = neighbor.status;
+    let (new_status, actions) = match old_status.transition(
+        event,
+        locked_interface.is_adjacency_wanted(neighbor),
+        neighbor.link_state_request_list.is_empty(),
+    ) {
+        Some(transition) => transition,
+        None => {
+            crate::debug(&format!(
+                "neighbor {} ignored event {:?} in state {:?}.",
+                neighbor_key, event, old_status
+            ));
+            return Some(old_status);
+        }
+    };
+    neighbor.status = new_status;
+    // the hello lists the neighbors, it is sent once their lock is released.
+    let mut send_hello_to = None;
+    for action in actions {
+        match action {
+            event::NeighborAction::SendHello => send_hello_to = Some(neighbor.ipv4_addr),
+            event::NeighborAction::StartInactivityTimer => {
+                neighbor.dead_timer_generation = neighbor.dead_timer_generation.wrapping_add(1);
+                let handle = tokio::spawn(handle::inactivity_timer_raw_handle(
+                    interface.clone(),
+                    neighbor_key,
+                    locked_interface.router_dead_interval,
+                    neighbor.dead_timer_generation,
+                ));
+                if let Some(old_handle) = neighbor.dead_timer.replace(handle) {
+                    old_handle.abort();
+                }
+            }
+            event::NeighborAction::StopInactivityTimer => {
+                if let Some(handle) = neighbor.dead_timer.take() {
+                    handle.abort();
+                }
+            }
+            event::NeighborAction::StartDatabaseExchange => {
+                neighbor.start_database_exchange();
+                crate::debug(&format!(
+                    "neighbor {} starts the database exchange with dd sequence number {}.",
+                    neighbor_key, neighbor.dd_sequence_number
+                ));
+                exchange::send_next_dd_packet(interface, locked_interface, neighbor_key, neighbor);
+            }
+            event::NeighborAction::ListDatabaseSummary => {
+                neighbor.database_summary_list = locked_interface.get_lsa_headers();
+            }
+            event::NeighborAction::SendLinkStateRequest => {
+                crate::debug(&format!(
+                    "neighbor {} has {} lsa(s) to request.",
+                    neighbor_key,
+                    neighbor.link_state_request_list.len()
+                ));
+                request::send_link_state_request(
+                    interface,
+                    locked_interface,
+                    neighbor_key,
+                    neighbor,
+                );
+            }
+            event::NeighborAction::ClearLists => neighbor.clear_lists(),
+        }
+    }
+    if new_status == status::NeighborStatus::Down {
+        neighbor.tear_down();
+        // a neighbor coming back may restart its cryptographic sequence numbers.
+        if let Some(crate::auth::Authentication::Cryptographic(crypto)) =
+            &locked_interface.authentication
+        {
+            crypto.forget_neighbor(neighbor.ipv4_addr);
+        }
+        // only the neighbors configured on NBMA networks are kept while down.
+        if !matches!(
+            locked_interface.network_type,
+            interface::InterfaceNetworkType::NBMA
+        ) {
+            neighbors.remove(&neighbor_key);
+        }
+    }
+    drop(neighbors);
+    if let Some(destination) = send_hello_to {
+        locked_interface.send_hello(destination);
+    }
+    crate::log(&format!(
+        "neighbor {} {:?}: {:?} -> {:?}.",
+        neighbor_key, event, old_status, new_status
+    ));
+    // the router-lsa and the network-lsa describe the fully adjacent neighbors (RFC 2328 12.4).
+    if (old_status == status::NeighborStatus::Full) != (new_status == status::NeighborStatus::Full)
+    {
+        if let Some(area) = &locked_interface.area {
+            area.lock().unwrap().trigger_router_lsa_origination();
+        }
+        // a full virtual link sets the V bit in the router-lsa of the area it crosses.
+        if let Some(transit_area) = &locked_interface.transit_area {
+            transit_area
+                .lock()
+                .unwrap()
+                .trigger_router_lsa_origination();
+        }
+        locked_interface.trigger_network_lsa_origination();
+    }
+    if old_status.is_two_way() != new_status.is_two_way() {
+        interface::apply_event(
+            interface,
+            locked_interface,
+            interface::event::InterfaceEvent::NeighborChange,
+        );
     }
+    Some(new_status)
 }
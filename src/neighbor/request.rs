@@ -0,0 +1,196 @@
+use std::{
+    net,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    error::ExchangeError,
+    interface::Interface,
+    lsa::{LinkStateAdvertisement, LinkStateAdvertisementHeader, MaxAge},
+    packet::{
+        lsr::{LinkStateRequestPacket, LinkStatusRequest, LINK_STATE_REQUEST_PACKET_TYPE},
+        lsu::{LinkStateUpdatePacket, LINK_STATE_UPDATE_TYPE},
+    },
+    MTU,
+};
+
+use super::{event::NeighborEvent, handle, status::NeighborStatus, Neighbor};
+
+/// the room left for the body of a packet in the mtu, once the ip header,
+/// the ospf header and the longest authentication digest are taken out.
+const MAX_PACKET_BODY_LENGTH: usize = MTU - 20 - 24 - 64;
+
+/// the most requests put in one LSR packet.
+pub const MAX_LSR_REQUESTS: usize = MAX_PACKET_BODY_LENGTH / 12;
+
+/// build an LSR packet for the first entries of the link state request list
+/// of `neighbor` and send it, without touching the retransmission timer.
+pub(crate) fn send_lsr_packet(locked_interface: &Interface, neighbor: &mut Neighbor) {
+    let count = neighbor.link_state_request_list.len().min(MAX_LSR_REQUESTS);
+    if count == 0 {
+        return;
+    }
+    let lsrs = neighbor.link_state_request_list[..count]
+        .iter()
+        .map(|lsa_header| Box::new(LinkStatusRequest::from_lsa_header(lsa_header)))
+        .collect();
+    let mut lsr_packet = LinkStateRequestPacket::new(
+        locked_interface.new_packet_header(LINK_STATE_REQUEST_PACKET_TYPE),
+        lsrs,
+    );
+    let destination = locked_interface.get_neighbor_destination(neighbor);
+    match locked_interface.create_ip_packet(destination, &mut lsr_packet) {
+        Ok(ip_packet) => {
+            neighbor.outstanding_request_count = count;
+            if !locked_interface.send_ip_packet(ip_packet) {
+                crate::error("send lsr packet failed.");
+            }
+        }
+        Err(e) => crate::error(&format!("create lsr packet failed: {}.", e)),
+    }
+}
+
+/// request the next lsas of the link state request list from `neighbor` (RFC 2328 10.9),
+/// the request is sent again every RxmtInterval until it is answered.
+pub(crate) fn send_link_state_request(
+    interface: &Arc<Mutex<Interface>>,
+    locked_interface: &Interface,
+    neighbor_key: net::Ipv4Addr,
+    neighbor: &mut Neighbor,
+) {
+    send_lsr_packet(locked_interface, neighbor);
+    let handle = tokio::spawn(handle::lsr_retransmission_raw_handle(
+        interface.clone(),
+        neighbor_key,
+        locked_interface.rxmt_interval,
+    ));
+    if let Some(old_handle) = neighbor.lsr_retransmission_timer.replace(handle) {
+        old_handle.abort();
+    }
+}
+
+/// the lsas of `lsas` sent to `destination` in as many LSU packets as the mtu requires.
+/// their age is incremented by InfTransDelay on the way out (RFC 2328 13.3).
+pub fn send_lsu_packets(
+    locked_interface: &Interface,
+    destination: net::Ipv4Addr,
+    lsas: Vec<Box<dyn LinkStateAdvertisement + Send>>,
+) {
+    let mut packets: Vec<Vec<Box<dyn LinkStateAdvertisement + Send>>> = Vec::new();
+    let mut length = 0;
+    for mut lsa in lsas {
+        let header = lsa.get_header_mut();
+        header.age = (header.age as u32 + locked_interface.inf_trans_delay).min(MaxAge) as u16;
+        let lsa_length = lsa.length();
+        match packets.last_mut() {
+            Some(packet) if length + lsa_length <= MAX_PACKET_BODY_LENGTH - 4 => {
+                packet.push(lsa);
+                length += lsa_length;
+            }
+            _ => {
+                packets.push(vec![lsa]);
+                length = lsa_length;
+            }
+        }
+    }
+    for lsas in packets {
+        let mut lsu_packet = LinkStateUpdatePacket::new(
+            locked_interface.new_packet_header(LINK_STATE_UPDATE_TYPE),
+            lsas.len() as u32,
+            lsas,
+        );
+        match locked_interface.create_ip_packet(destination, &mut lsu_packet) {
+            Ok(ip_packet) => {
+                if !locked_interface.send_ip_packet(ip_packet) {
+                    crate::error("send lsu packet failed.");
+                }
+            }
+            Err(e) => crate::error(&format!("create lsu packet failed: {}.", e)),
+        }
+    }
+}
+
+/// the receive procedure of an LSR packet sent by `source` on the interface (RFC 2328 10.7).
+/// each requested lsa is looked up in the database and sent back in LSU packets,
+/// a request for an lsa we do not have is an error of the neighbor and raises BadLSReq.
+pub fn handle_lsr_packet(
+    interface: &Arc<Mutex<Interface>>,
+    source: net::Ipv4Addr,
+    lsr_packet: &LinkStateRequestPacket,
+) -> Result<(), ExchangeError> {
+    let mut locked_interface = interface.lock().unwrap();
+    let router_id = net::Ipv4Addr::from(lsr_packet.header.router_id);
+    let neighbor_key = locked_interface.get_neighbor_key(source, router_id);
+    let neighbors = locked_interface.neighbors.clone();
+    let locked_neighbors = neighbors.lock().unwrap();
+    let neighbor = locked_neighbors
+        .get(&neighbor_key)
+        .ok_or(ExchangeError::UnknownNeighbor(source))?;
+    if neighbor.status < NeighborStatus::Exchange {
+        return Err(ExchangeError::NeighborNotReady);
+    }
+    let destination = locked_interface.get_neighbor_destination(neighbor);
+    drop(locked_neighbors);
+    let mut lsas = Vec::with_capacity(lsr_packet.lsrs.len());
+    for lsr in &lsr_packet.lsrs {
+        match locked_interface.lookup_lsa(lsr) {
+            Some(lsa) => lsas.push(lsa),
+            None => {
+                crate::error(&format!(
+                    "neighbor {} requested lsa type {} id {} from {} which is not in the database.",
+                    neighbor_key,
+                    lsr.ls_type,
+                    net::Ipv4Addr::from(lsr.link_state_id),
+                    net::Ipv4Addr::from(lsr.advertising_router)
+                ));
+                super::apply_event(
+                    interface,
+                    &mut locked_interface,
+                    neighbor_key,
+                    NeighborEvent::BadLSReq,
+                );
+                return Ok(());
+            }
+        }
+    }
+    send_lsu_packets(&locked_interface, destination, lsas);
+    Ok(())
+}
+
+/// take the lsas received from the neighbor `neighbor_key` off its link state request list.
+/// once the list is empty in Loading the neighbor becomes Full, once the lsas of the last
+/// LSR packet have all arrived the next ones are requested.
+pub(crate) fn handle_requested_lsas(
+    interface: &Arc<Mutex<Interface>>,
+    locked_interface: &mut Interface,
+    neighbor_key: net::Ipv4Addr,
+    lsa_headers: &[LinkStateAdvertisementHeader],
+) {
+    let neighbors = locked_interface.neighbors.clone();
+    let mut locked_neighbors = neighbors.lock().unwrap();
+    let neighbor = match locked_neighbors.get_mut(&neighbor_key) {
+        Some(neighbor) => neighbor,
+        None => return,
+    };
+    let mut satisfied = false;
+    for lsa_header in lsa_headers {
+        satisfied |= neighbor.satisfy_link_state_request(lsa_header);
+    }
+    if !satisfied || neighbor.status != NeighborStatus::Loading {
+        return;
+    }
+    if neighbor.link_state_request_list.is_empty() {
+        if let Some(handle) = neighbor.lsr_retransmission_timer.take() {
+            handle.abort();
+        }
+        drop(locked_neighbors);
+        super::apply_event(
+            interface,
+            locked_interface,
+            neighbor_key,
+            NeighborEvent::LoadingDone,
+        );
+    } else if neighbor.outstanding_request_count == 0 {
+        send_link_state_request(interface, locked_interface, neighbor_key, neighbor);
+    }
+}
@@ -1,5 +1,9 @@
+use super::event::{NeighborAction, NeighborEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum NeighborStatus {
     Down,
+    Attempt,
     Init,
     TwoWay,
     ExStart,
@@ -9,5 +13,86 @@ pub enum NeighborStatus {
 }
 
 impl NeighborStatus {
-    
+    /// the neighbor state machine of RFC 2328 10.3.
+    /// `adjacency_wanted` is the answer to "should an adjacency be established" of 10.4,
+    /// `request_list_empty` whether the link state request list is empty at ExchangeDone.
+    /// returns the next state and the actions to take, or none if `event` means nothing
+    /// in the current state.
+    pub fn transition(
+        self,
+        event: NeighborEvent,
+        adjacency_wanted: bool,
+        request_list_empty: bool,
+    ) -> Option<(NeighborStatus, Vec<NeighborAction>)> {
+        use NeighborAction::*;
+        use NeighborStatus::*;
+        match (self, event) {
+            (Down, NeighborEvent::Start) => Some((Attempt, vec![SendHello, StartInactivityTimer])),
+            (Down, NeighborEvent::HelloReceived) | (Attempt, NeighborEvent::HelloReceived) => {
+                Some((Init, vec![StartInactivityTimer]))
+            }
+            (_, NeighborEvent::HelloReceived) => Some((self, vec![StartInactivityTimer])),
+            (Init, NeighborEvent::TwoWayReceived) => {
+                if adjacency_wanted {
+                    Some((ExStart, vec![StartDatabaseExchange]))
+                } else {
+                    Some((TwoWay, vec![]))
+                }
+            }
+            (ExStart, NeighborEvent::NegotiationDone) => {
+                Some((Exchange, vec![ListDatabaseSummary]))
+            }
+            (Exchange, NeighborEvent::ExchangeDone) => {
+                if request_list_empty {
+                    Some((Full, vec![]))
+                } else {
+                    Some((Loading, vec![SendLinkStateRequest]))
+                }
+            }
+            (Loading, NeighborEvent::LoadingDone) => Some((Full, vec![])),
+            (TwoWay, NeighborEvent::AdjOk) => {
+                if adjacency_wanted {
+                    Some((ExStart, vec![StartDatabaseExchange]))
+                } else {
+                    None
+                }
+            }
+            (ExStart, NeighborEvent::AdjOk)
+            | (Exchange, NeighborEvent::AdjOk)
+            | (Loading, NeighborEvent::AdjOk)
+            | (Full, NeighborEvent::AdjOk) => {
+                if adjacency_wanted {
+                    None
+                } else {
+                    Some((TwoWay, vec![ClearLists]))
+                }
+            }
+            (Exchange, NeighborEvent::SeqNumberMismatch)
+            | (Loading, NeighborEvent::SeqNumberMismatch)
+            | (Full, NeighborEvent::SeqNumberMismatch)
+            | (Exchange, NeighborEvent::BadLSReq)
+            | (Loading, NeighborEvent::BadLSReq)
+            | (Full, NeighborEvent::BadLSReq) => {
+                Some((ExStart, vec![ClearLists, StartDatabaseExchange]))
+            }
+            (Down, NeighborEvent::KillNbr)
+            | (Down, NeighborEvent::LLDown)
+            | (Down, NeighborEvent::InactivityTimer) => None,
+            (_, NeighborEvent::KillNbr) | (_, NeighborEvent::LLDown) => {
+                Some((Down, vec![ClearLists, StopInactivityTimer]))
+            }
+            (_, NeighborEvent::InactivityTimer) => Some((Down, vec![ClearLists])),
+            (TwoWay, NeighborEvent::OneWayReceived)
+            | (ExStart, NeighborEvent::OneWayReceived)
+            | (Exchange, NeighborEvent::OneWayReceived)
+            | (Loading, NeighborEvent::OneWayReceived)
+            | (Full, NeighborEvent::OneWayReceived) => Some((Init, vec![ClearLists])),
+            _ => None,
+        }
+    }
+
+    /// the states in which bidirectional communication with the neighbor is established.
+    pub fn is_two_way(&self) -> bool {
+        *self >= NeighborStatus::TwoWay
+    }
 }
@@ -1,5 +1,6 @@
 use pnet::packet::ipv4::Ipv4Packet;
 
+use crate::error::OspfDecodeError;
 use crate::lsa::LinkStateAdvertisementHeader;
 
 use super::{OspfPacket, OspfPacketHeader};
@@ -32,10 +33,9 @@ impl OspfPacket for DataDescriptionPacket {
         length += self.lsa_headers.len() * LinkStateAdvertisementHeader::length();
         length
     }
-    fn ipv4packet(&self) -> Result<Ipv4Packet, &'static str> {
+    fn ipv4packet(&self) -> Result<Ipv4Packet<'_>, &'static str> {
         Err("not an ipv4 packet")
     }
-    fn calculate_checksum(&mut self) {}
 
     fn to_bytes(&self) -> Vec<u8> {
         let mut result = vec![];
@@ -64,9 +64,21 @@ impl OspfPacket for DataDescriptionPacket {
     fn get_type(&self) -> u8 {
         DATA_DESCRIPTION_PACKET_TYPE
     }
+    fn get_header(&self) -> &OspfPacketHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut OspfPacketHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 impl DataDescriptionPacket {
+    /// the length of a dd packet without any lsa header.
+    pub const MIN_LENGTH: usize = 32;
+
     pub fn new(
         header: OspfPacketHeader,
         interface_mtu: u16,
@@ -84,27 +96,32 @@ impl DataDescriptionPacket {
             lsa_headers,
         }
     }
-    pub fn from_be_bytes(data: &[u8]) -> Self {
-        let header = OspfPacketHeader::from_be_bytes(&data[0..24]);
+    pub fn from_be_bytes(data: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = OspfPacketHeader::from_be_bytes(data)?;
+        let data = &data[..header.packet_length as usize];
+        OspfDecodeError::ensure_length("dd packet", data, Self::MIN_LENGTH)?;
+        if !(data.len() - Self::MIN_LENGTH).is_multiple_of(LinkStateAdvertisementHeader::length()) {
+            return Err(OspfDecodeError::LengthMismatch {
+                what: "dd packet lsa headers",
+                length: data.len() - Self::MIN_LENGTH,
+                entry_size: LinkStateAdvertisementHeader::length(),
+            });
+        }
         let interface_mtu = u16::from_be_bytes([data[24], data[25]]);
         let options = data[26];
         let features = data[27];
         let dd_sequence_number = u32::from_be_bytes([data[28], data[29], data[30], data[31]]);
         let mut lsa_headers = vec![];
-        let mut index = 32;
-        while index < data.len() {
-            lsa_headers.push(LinkStateAdvertisementHeader::from_be_bytes(
-                &data[index..index + 20],
-            ));
-            index += 20;
+        for chunk in data[Self::MIN_LENGTH..].chunks_exact(LinkStateAdvertisementHeader::length()) {
+            lsa_headers.push(LinkStateAdvertisementHeader::from_be_bytes(chunk)?);
         }
-        Self {
+        Ok(Self {
             header,
             interface_mtu,
             options,
             features,
             dd_sequence_number,
             lsa_headers,
-        }
+        })
     }
 }
@@ -1,16 +1,15 @@
 use pnet::packet::ipv4::Ipv4Packet;
 
-use crate::neighbor::Neighbor;
-use crate::{interface, neighbor, OSPF_VERSION_2};
+use crate::error::OspfDecodeError;
+use crate::{interface, OSPF_VERSION_2};
 
 use super::{OspfPacket, OspfPacketHeader};
 
-use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::{mem, net};
 /// # struct HelloPacket
 /// - header : the ospf packet header
-/// doc to be implemented
+/// - neighbors : the router ids of the neighbors whose hellos were seen recently on the network.
 pub struct HelloPacket {
     pub header: OspfPacketHeader,
     pub network_mask: net::Ipv4Addr,
@@ -20,7 +19,7 @@ pub struct HelloPacket {
     pub router_dead_interval: u32,
     pub designated_router: u32,
     pub backup_designated_router: u32,
-    pub neighbors: Arc<Mutex<HashMap<net::Ipv4Addr, neighbor::Neighbor>>>,
+    pub neighbors: Vec<net::Ipv4Addr>,
 }
 
 pub const HELLO_PACKET_TYPE: u8 = 1;
@@ -38,17 +37,12 @@ impl OspfPacket for HelloPacket {
         bytes.extend_from_slice(&self.router_dead_interval.to_be_bytes());
         bytes.extend_from_slice(&self.designated_router.to_be_bytes());
         bytes.extend_from_slice(&self.backup_designated_router.to_be_bytes());
-        let neighbors = self.neighbors.lock().unwrap();
-        for (neighbor_ip, _) in neighbors.iter() {
-            bytes.extend_from_slice(&neighbor_ip.octets());
+        for neighbor_id in &self.neighbors {
+            bytes.extend_from_slice(&neighbor_id.octets());
         }
         bytes
     }
 
-    fn calculate_checksum(&mut self) {
-        self.header.checksum = 0;
-    }
-
     fn to_be_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::with_capacity(self.length());
         bytes.extend_from_slice(&self.header.to_be_bytes());
@@ -59,32 +53,43 @@ impl OspfPacket for HelloPacket {
         bytes.extend_from_slice(&self.router_dead_interval.to_be_bytes());
         bytes.extend_from_slice(&self.designated_router.to_be_bytes());
         bytes.extend_from_slice(&self.backup_designated_router.to_be_bytes());
-        let neighbors = self.neighbors.lock().unwrap();
-        for (neighbor_ip, _) in neighbors.iter() {
-            bytes.extend_from_slice(&neighbor_ip.octets());
+        for neighbor_id in &self.neighbors {
+            bytes.extend_from_slice(&neighbor_id.octets());
         }
         bytes
     }
     fn get_type(&self) -> u8 {
         HELLO_PACKET_TYPE
     }
-    fn ipv4packet(&self) -> Result<Ipv4Packet, &'static str> {
+    fn get_header(&self) -> &OspfPacketHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut OspfPacketHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn ipv4packet(&self) -> Result<Ipv4Packet<'_>, &'static str> {
         Err("not an ipv4 packet")
     }
     fn length(&self) -> usize {
         let mut length = 0;
-        length += mem::size_of::<OspfPacketHeader>();
+        length += OspfPacketHeader::length();
         length += mem::size_of::<net::Ipv4Addr>();
         length += mem::size_of::<u16>();
         length += mem::size_of::<u8>() * 2;
         length += mem::size_of::<u32>() * 3;
-        length += mem::size_of::<net::Ipv4Addr>() * self.neighbors.lock().unwrap().len();
+        length += mem::size_of::<net::Ipv4Addr>() * self.neighbors.len();
         length
     }
 }
 
 impl HelloPacket {
-    pub fn set_auth_type(&mut self, auth_type: u8) {
+    /// the length of a hello packet without any neighbor.
+    pub const MIN_LENGTH: usize = 44;
+
+    pub fn set_auth_type(&mut self, auth_type: u16) {
         self.header.auth_type = auth_type;
     }
     pub fn set_auth_key(&mut self, auth_key: u64) {
@@ -111,16 +116,17 @@ impl HelloPacket {
         HelloPacket {
             header,
             network_mask: interface.network_mask,
-            hello_interval: hello_interval,
+            hello_interval,
             options: 0,
             rtr_pri: 0,
             router_dead_interval: 0,
             designated_router: 0,
             backup_designated_router: 0,
-            neighbors: interface.neighbors.clone(),
+            neighbors: interface.get_hello_neighbors(),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network_mask: net::Ipv4Addr,
         hello_interval: u16,
@@ -130,7 +136,7 @@ impl HelloPacket {
         designated_router: u32,
         backup_designated_router: u32,
         header: OspfPacketHeader,
-        neighbors: Arc<Mutex<HashMap<net::Ipv4Addr, neighbor::Neighbor>>>,
+        neighbors: Vec<net::Ipv4Addr>,
     ) -> Self {
         HelloPacket {
             header,
@@ -144,11 +150,17 @@ impl HelloPacket {
             neighbors,
         }
     }
-    pub fn from_be_bytes(
-        bytes: &[u8],
-        neighbors: Arc<Mutex<HashMap<net::Ipv4Addr, neighbor::Neighbor>>>,
-    ) -> Self {
-        let header = OspfPacketHeader::from_be_bytes(&bytes[0..24]);
+    pub fn from_be_bytes(bytes: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = OspfPacketHeader::from_be_bytes(bytes)?;
+        let bytes = &bytes[..header.packet_length as usize];
+        OspfDecodeError::ensure_length("hello packet", bytes, Self::MIN_LENGTH)?;
+        if !(bytes.len() - Self::MIN_LENGTH).is_multiple_of(4) {
+            return Err(OspfDecodeError::LengthMismatch {
+                what: "hello packet neighbors",
+                length: bytes.len() - Self::MIN_LENGTH,
+                entry_size: 4,
+            });
+        }
         let network_mask = net::Ipv4Addr::new(bytes[24], bytes[25], bytes[26], bytes[27]);
         let hello_interval = u16::from_be_bytes([bytes[28], bytes[29]]);
         let options = bytes[30];
@@ -157,21 +169,11 @@ impl HelloPacket {
         let designated_router = u32::from_be_bytes([bytes[36], bytes[37], bytes[38], bytes[39]]);
         let backup_designated_router =
             u32::from_be_bytes([bytes[40], bytes[41], bytes[42], bytes[43]]);
-        let mut locked_neighbors = neighbors.lock().unwrap();
-        for i in 44..bytes.len() {
-            // locked_neighbors.push(net::Ipv4Addr::new(
-            //     bytes[i],
-            //     bytes[i + 1],
-            //     bytes[i + 2],
-            //     bytes[i + 3],
-            // ));
-            let neighbor_ip: net::Ipv4Addr =
-                net::Ipv4Addr::new(bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]);
-            let neighbor: neighbor::Neighbor = neighbor::Neighbor::new(neighbor_ip);
-            locked_neighbors.insert(neighbor_ip, neighbor);
-        }
-        drop(locked_neighbors);
-        Self {
+        let neighbors = bytes[Self::MIN_LENGTH..]
+            .chunks_exact(4)
+            .map(|chunk| net::Ipv4Addr::new(chunk[0], chunk[1], chunk[2], chunk[3]))
+            .collect();
+        Ok(Self {
             header,
             network_mask,
             hello_interval,
@@ -181,6 +183,6 @@ impl HelloPacket {
             designated_router,
             backup_designated_router,
             neighbors,
-        }
+        })
     }
 }
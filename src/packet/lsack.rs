@@ -1,5 +1,6 @@
 use pnet::packet::ipv4::Ipv4Packet;
 
+use crate::error::OspfDecodeError;
 use crate::lsa::LinkStateAdvertisementHeader;
 
 use super::{OspfPacket, OspfPacketHeader};
@@ -22,7 +23,7 @@ impl OspfPacket for LinkStateAcknowledgementPacket {
         }
         result
     }
-    fn ipv4packet(&self) -> Result<Ipv4Packet, &'static str> {
+    fn ipv4packet(&self) -> Result<Ipv4Packet<'_>, &'static str> {
         Err("not an ipv4 packet")
     }
     fn length(&self) -> usize {
@@ -34,7 +35,15 @@ impl OspfPacket for LinkStateAcknowledgementPacket {
     fn get_type(&self) -> u8 {
         LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE
     }
-    fn calculate_checksum(&mut self) {}
+    fn get_header(&self) -> &OspfPacketHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut OspfPacketHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
     fn to_be_bytes(&self) -> Vec<u8> {
         let mut result = vec![];
         result.extend(self.header.to_be_bytes());
@@ -52,20 +61,31 @@ impl LinkStateAcknowledgementPacket {
     ) -> Self {
         Self {
             header,
-            lsa_headers: lsa_headers,
+            lsa_headers,
         }
     }
-    pub fn from_be_bytes(data : &[u8]) -> Self {
-        let header = OspfPacketHeader::from_be_bytes(&data[0..24]);
+    pub fn from_be_bytes(data: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = OspfPacketHeader::from_be_bytes(data)?;
+        let data = &data[OspfPacketHeader::length()..header.packet_length as usize];
+        if !data
+            .len()
+            .is_multiple_of(LinkStateAdvertisementHeader::length())
+        {
+            return Err(OspfDecodeError::LengthMismatch {
+                what: "lsack packet lsa headers",
+                length: data.len(),
+                entry_size: LinkStateAdvertisementHeader::length(),
+            });
+        }
         let mut lsa_headers = vec![];
-        let mut index = 24;
-        while index < data.len() {
-            lsa_headers.push(Box::new(LinkStateAdvertisementHeader::from_be_bytes(&data[index..index+20])));
-            index += 20;
+        for chunk in data.chunks_exact(LinkStateAdvertisementHeader::length()) {
+            lsa_headers.push(Box::new(LinkStateAdvertisementHeader::from_be_bytes(
+                chunk,
+            )?));
         }
-        Self {
+        Ok(Self {
             header,
-            lsa_headers: lsa_headers,
-        }
+            lsa_headers,
+        })
     }
 }
@@ -1,6 +1,8 @@
 use pnet::packet::ipv4::Ipv4Packet;
 
 use super::{OspfPacket, OspfPacketHeader};
+use crate::error::OspfDecodeError;
+use crate::lsa::LinkStateAdvertisementHeader;
 
 pub struct LinkStatusRequest {
     pub ls_type: u32,
@@ -29,6 +31,20 @@ impl LinkStatusRequest {
     pub fn length() -> usize {
         12
     }
+    /// the request for the lsa described by `lsa_header`.
+    pub fn from_lsa_header(lsa_header: &LinkStateAdvertisementHeader) -> Self {
+        Self::new(
+            lsa_header.lsa_type as u32,
+            lsa_header.link_state_id,
+            lsa_header.advertising_router,
+        )
+    }
+    /// whether `lsa_header` is of the lsa requested.
+    pub fn is_requesting(&self, lsa_header: &LinkStateAdvertisementHeader) -> bool {
+        self.ls_type == lsa_header.lsa_type as u32
+            && self.link_state_id == lsa_header.link_state_id
+            && self.advertising_router == lsa_header.advertising_router
+    }
 }
 
 impl OspfPacket for LinkStateRequestPacket {
@@ -38,7 +54,7 @@ impl OspfPacket for LinkStateRequestPacket {
         length += self.lsrs.len() * LinkStatusRequest::length();
         length
     }
-    fn ipv4packet(&self) -> Result<Ipv4Packet, &'static str> {
+    fn ipv4packet(&self) -> Result<Ipv4Packet<'_>, &'static str> {
         Err("not an ipv4 packet")
     }
     fn to_bytes(&self) -> Vec<u8> {
@@ -51,7 +67,6 @@ impl OspfPacket for LinkStateRequestPacket {
         }
         result
     }
-    fn calculate_checksum(&mut self) {}
     fn to_be_bytes(&self) -> Vec<u8> {
         let mut result = vec![];
         result.extend(self.header.to_be_bytes());
@@ -65,39 +80,39 @@ impl OspfPacket for LinkStateRequestPacket {
     fn get_type(&self) -> u8 {
         LINK_STATE_REQUEST_PACKET_TYPE
     }
+    fn get_header(&self) -> &OspfPacketHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut OspfPacketHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 impl LinkStateRequestPacket {
     pub fn new(header: OspfPacketHeader, lsrs: Vec<Box<LinkStatusRequest>>) -> Self {
         Self { header, lsrs }
     }
-    pub fn from_be_bytes(data: &[u8]) -> Self {
-        let header = OspfPacketHeader::from_be_bytes(&data[0..24]);
+    pub fn from_be_bytes(data: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = OspfPacketHeader::from_be_bytes(data)?;
+        let data = &data[OspfPacketHeader::length()..header.packet_length as usize];
+        if !data.len().is_multiple_of(LinkStatusRequest::length()) {
+            return Err(OspfDecodeError::LengthMismatch {
+                what: "lsr packet requests",
+                length: data.len(),
+                entry_size: LinkStatusRequest::length(),
+            });
+        }
         let mut lsrs = vec![];
-        let mut index = 24;
-        while index < data.len() {
+        for chunk in data.chunks_exact(LinkStatusRequest::length()) {
             lsrs.push(Box::new(LinkStatusRequest {
-                ls_type: u32::from_be_bytes([
-                    data[index],
-                    data[index + 1],
-                    data[index + 2],
-                    data[index + 3],
-                ]),
-                link_state_id: u32::from_be_bytes([
-                    data[index + 4],
-                    data[index + 5],
-                    data[index + 6],
-                    data[index + 7],
-                ]),
-                advertising_router: u32::from_be_bytes([
-                    data[index + 8],
-                    data[index + 9],
-                    data[index + 10],
-                    data[index + 11],
-                ]),
+                ls_type: u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                link_state_id: u32::from_be_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+                advertising_router: u32::from_be_bytes([chunk[8], chunk[9], chunk[10], chunk[11]]),
             }));
-            index += 12;
         }
-        Self { header, lsrs }
+        Ok(Self { header, lsrs })
     }
 }
@@ -1,12 +1,16 @@
 use pnet::packet::ipv4::Ipv4Packet;
 
 use super::{OspfPacket, OspfPacketHeader};
-use crate::lsa::{get_lsa_from_be_bytes, LinkStateAdvertisement};
+use crate::error::OspfDecodeError;
+use crate::lsa::{get_lsa_from_be_bytes, is_lsa_checksum_valid, LinkStateAdvertisement};
 
 pub struct LinkStateUpdatePacket {
     pub header: OspfPacketHeader,
     pub lsa_count: u32,
-    pub lsas: Vec<Box<dyn LinkStateAdvertisement>>,
+    pub lsas: Vec<Box<dyn LinkStateAdvertisement + Send>>,
+    /// how many received lsas were dropped by the decoding because their checksum
+    /// did not verify on the received bytes.
+    pub bad_checksum_count: usize,
 }
 
 pub const LINK_STATE_UPDATE_TYPE: u8 = 4;
@@ -23,10 +27,9 @@ impl OspfPacket for LinkStateUpdatePacket {
         }
         result
     }
-    fn ipv4packet(&self) -> Result<Ipv4Packet, &'static str> {
+    fn ipv4packet(&self) -> Result<Ipv4Packet<'_>, &'static str> {
         Err("not an ipv4 packet")
     }
-    fn calculate_checksum(&mut self) {}
     fn length(&self) -> usize {
         let mut length = 0;
         length += OspfPacketHeader::length();
@@ -39,6 +42,15 @@ impl OspfPacket for LinkStateUpdatePacket {
     fn get_type(&self) -> u8 {
         LINK_STATE_UPDATE_TYPE
     }
+    fn get_header(&self) -> &OspfPacketHeader {
+        &self.header
+    }
+    fn get_header_mut(&mut self) -> &mut OspfPacketHeader {
+        &mut self.header
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
     fn to_bytes(&self) -> Vec<u8> {
         let mut result = vec![];
         result.extend(self.header.to_bytes());
@@ -51,31 +63,123 @@ impl OspfPacket for LinkStateUpdatePacket {
 }
 
 impl LinkStateUpdatePacket {
+    /// the length of a lsu packet without any lsa.
+    pub const MIN_LENGTH: usize = 28;
+
     pub fn new(
         header: OspfPacketHeader,
         lsa_count: u32,
-        lsas: Vec<Box<dyn LinkStateAdvertisement>>,
+        lsas: Vec<Box<dyn LinkStateAdvertisement + Send>>,
     ) -> Self {
         Self {
             header,
             lsa_count,
             lsas,
+            bad_checksum_count: 0,
         }
     }
-    pub fn from_be_bytes(data: &[u8]) -> Self {
-        let header = OspfPacketHeader::from_be_bytes(&data[0..24]);
+    pub fn from_be_bytes(data: &[u8]) -> Result<Self, OspfDecodeError> {
+        let header = OspfPacketHeader::from_be_bytes(data)?;
+        let data = &data[..header.packet_length as usize];
+        OspfDecodeError::ensure_length("lsu packet", data, Self::MIN_LENGTH)?;
         let lsa_count = u32::from_be_bytes([data[24], data[25], data[26], data[27]]);
         let mut lsas = vec![];
-        let mut index = 28;
+        let mut bad_checksum_count = 0;
+        let mut index = Self::MIN_LENGTH;
         while index < data.len() {
-            let lsa = get_lsa_from_be_bytes(&data[index..]);
-            index += lsa.length() as usize;
-            lsas.push(lsa);
+            let lsa = get_lsa_from_be_bytes(&data[index..])?;
+            let length = lsa.get_header().length as usize;
+            // the checksum is verified on the received bytes, not on a re-encoding.
+            if is_lsa_checksum_valid(&data[index..index + length]) {
+                lsas.push(lsa);
+            } else {
+                bad_checksum_count += 1;
+            }
+            index += length;
         }
-        Self {
+        if lsas.len() + bad_checksum_count != lsa_count as usize {
+            return Err(OspfDecodeError::CountMismatch {
+                what: "lsu packet",
+                expected: lsa_count as usize,
+                actual: lsas.len() + bad_checksum_count,
+            });
+        }
+        Ok(Self {
             header,
             lsa_count,
             lsas,
-        }
+            bad_checksum_count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsa::{
+        router::{RouterLink, RouterLinkStateAdvertisement, ROUTER_LINK_TYPE_STUB},
+        LinkStateAdvertisementHeader,
+    };
+    use crate::OSPF_VERSION_2;
+
+    fn router_lsa(advertising_router: u32) -> Box<dyn LinkStateAdvertisement + Send> {
+        let mut lsa = RouterLinkStateAdvertisement::new(
+            LinkStateAdvertisementHeader::new(
+                1,
+                0,
+                1,
+                advertising_router,
+                advertising_router,
+                0x80000001,
+                0,
+                0,
+            ),
+            0,
+            1,
+            vec![Box::new(RouterLink::new(
+                0x0a000000,
+                0xffffff00,
+                ROUTER_LINK_TYPE_STUB,
+                1,
+            ))],
+        );
+        lsa.calculate_checksum();
+        Box::new(lsa)
+    }
+
+    fn encode(lsas: Vec<Box<dyn LinkStateAdvertisement + Send>>) -> Vec<u8> {
+        let header =
+            OspfPacketHeader::new(OSPF_VERSION_2, LINK_STATE_UPDATE_TYPE, 0, 1, 0, 0, 0, 0);
+        let mut lsu_packet = LinkStateUpdatePacket::new(header, lsas.len() as u32, lsas);
+        lsu_packet.calculate_checksum();
+        lsu_packet.to_be_bytes()
+    }
+
+    #[test]
+    fn decode_keeps_valid_lsas() {
+        let bytes = encode(vec![router_lsa(1), router_lsa(2)]);
+        let lsu_packet = LinkStateUpdatePacket::from_be_bytes(&bytes).unwrap();
+        assert_eq!(lsu_packet.lsas.len(), 2);
+        assert_eq!(lsu_packet.bad_checksum_count, 0);
+    }
+
+    #[test]
+    fn decode_drops_lsas_with_bad_checksum() {
+        let mut bytes = encode(vec![router_lsa(1), router_lsa(2)]);
+        // flip a bit of the link data of the first lsa.
+        let first_link = LinkStateUpdatePacket::MIN_LENGTH + 20 + 4;
+        bytes[first_link + 7] ^= 0x01;
+        let lsu_packet = LinkStateUpdatePacket::from_be_bytes(&bytes).unwrap();
+        assert_eq!(lsu_packet.bad_checksum_count, 1);
+        assert_eq!(lsu_packet.lsas.len(), 1);
+        assert_eq!(lsu_packet.lsas[0].get_header().advertising_router, 2);
+    }
+
+    #[test]
+    fn age_is_not_covered_by_checksum() {
+        let mut bytes = encode(vec![router_lsa(1)]);
+        bytes[LinkStateUpdatePacket::MIN_LENGTH + 1] = 0xff;
+        let lsu_packet = LinkStateUpdatePacket::from_be_bytes(&bytes).unwrap();
+        assert_eq!(lsu_packet.lsas.len(), 1);
     }
 }
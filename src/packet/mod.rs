@@ -4,16 +4,13 @@ pub mod lsack;
 pub mod lsr;
 pub mod lsu;
 
-use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ip::IpNextHeaderProtocol;
 use pnet::packet::ipv4::{Ipv4Packet, MutableIpv4Packet};
 use pnet::packet::Packet;
-use pnet::transport;
-use std::collections::HashMap;
 use std::net;
-use std::sync::{Arc, Mutex};
 
-use crate::neighbor::Neighbor;
-use crate::{OSPF_IP_PROTOCOL_NUMBER, OSPF_VERSION_2};
+use crate::error::OspfDecodeError;
+use crate::{OSPF_IP_DSCP, OSPF_IP_PROTOCOL_NUMBER, OSPF_IP_TTL, OSPF_VERSION_2};
 
 #[derive(Clone, Copy)]
 pub struct OspfPacketHeader {
@@ -23,12 +20,16 @@ pub struct OspfPacketHeader {
     pub router_id: u32,
     pub area_id: u32,
     pub checksum: u16,
-    pub auth_type: u8,
+    pub auth_type: u16,
     pub authentication: [u8; 8],
 }
 
 unsafe impl Send for OspfPacketHeader {}
 
+pub const AUTH_TYPE_NULL: u16 = 0;
+pub const AUTH_TYPE_SIMPLE_PASSWORD: u16 = 1;
+pub const AUTH_TYPE_CRYPTOGRAPHIC: u16 = 2;
+
 pub fn calculate_checksum(data: &[u16]) -> u16 {
     let mut sum: u32 = 0;
     for &word in data {
@@ -42,6 +43,24 @@ pub fn calculate_checksum(data: &[u16]) -> u16 {
     !(sum as u16)
 }
 
+/// the checksum of an encoded ospf packet as described in RFC 2328 D.4:
+/// the standard ip checksum of the whole packet, with the checksum field zeroed
+/// and the 64-bit authentication field left out. an odd trailing byte is padded with zero.
+pub fn ospf_checksum(packet: &[u8]) -> u16 {
+    let words = packet
+        .chunks(2)
+        .enumerate()
+        .map(|(index, chunk)| match index {
+            // the checksum field
+            6 => 0,
+            // the authentication field
+            8..=11 => 0,
+            _ => u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]),
+        })
+        .collect::<Vec<u16>>();
+    calculate_checksum(&words)
+}
+
 impl OspfPacketHeader {
     pub fn to_be_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::with_capacity(24);
@@ -51,25 +70,40 @@ impl OspfPacketHeader {
         bytes.extend_from_slice(&self.router_id.to_be_bytes());
         bytes.extend_from_slice(&self.area_id.to_be_bytes());
         bytes.extend_from_slice(&self.checksum.to_be_bytes());
-        bytes.push(self.auth_type);
+        bytes.extend_from_slice(&self.auth_type.to_be_bytes());
         bytes.extend_from_slice(&self.authentication);
         bytes
     }
-    pub fn from_be_bytes(bytes: &[u8]) -> Self {
-        Self {
+    /// decode the header and check its version and packet length field against `bytes`,
+    /// so the caller can safely slice the packet body with `packet_length`.
+    pub fn from_be_bytes(bytes: &[u8]) -> Result<Self, OspfDecodeError> {
+        OspfDecodeError::ensure_length("ospf packet header", bytes, Self::length())?;
+        if bytes[0] != OSPF_VERSION_2 {
+            return Err(OspfDecodeError::BadVersion(bytes[0]));
+        }
+        let packet_length = u16::from_be_bytes([bytes[2], bytes[3]]);
+        if (packet_length as usize) < Self::length() || packet_length as usize > bytes.len() {
+            return Err(OspfDecodeError::BadLengthField {
+                what: "ospf packet header",
+                length: packet_length as usize,
+                available: bytes.len(),
+            });
+        }
+        Ok(Self {
             version: bytes[0],
             packet_type: bytes[1],
-            packet_length: u16::from_be_bytes([bytes[2], bytes[3]]),
+            packet_length,
             router_id: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
             area_id: u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
             checksum: u16::from_be_bytes([bytes[12], bytes[13]]),
-            auth_type: bytes[14],
+            auth_type: u16::from_be_bytes([bytes[14], bytes[15]]),
             authentication: [
-                bytes[15], bytes[16], bytes[17], bytes[18], bytes[19], bytes[20], bytes[21],
-                bytes[22],
+                bytes[16], bytes[17], bytes[18], bytes[19], bytes[20], bytes[21], bytes[22],
+                bytes[23],
             ],
-        }
+        })
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         version: u8,
         packet_type: u8,
@@ -77,7 +111,7 @@ impl OspfPacketHeader {
         router_id: u32,
         area_id: u32,
         checksum: u16,
-        auth_type: u8,
+        auth_type: u16,
         auth_key: u64,
     ) -> Self {
         Self {
@@ -99,7 +133,7 @@ impl OspfPacketHeader {
         bytes.extend_from_slice(&self.router_id.to_be_bytes());
         bytes.extend_from_slice(&self.area_id.to_be_bytes());
         bytes.extend_from_slice(&self.checksum.to_be_bytes());
-        bytes.push(self.auth_type);
+        bytes.extend_from_slice(&self.auth_type.to_be_bytes());
         bytes.extend_from_slice(&self.authentication);
         bytes
     }
@@ -114,7 +148,7 @@ pub fn new_ip_packet(
     src_ip: net::Ipv4Addr,
     dst_ip: net::Ipv4Addr,
     packet: Vec<u8>,
-) -> Result<Box<MutableIpv4Packet>, &'static str> {
+) -> Result<Box<MutableIpv4Packet<'_>>, &'static str> {
     if packet.len() >= 1500 - 20 {
         return Err("packet too big");
     }
@@ -122,8 +156,9 @@ pub fn new_ip_packet(
     ip_packet.set_version(4);
     ip_packet.set_header_length(5);
     ip_packet.set_total_length(20 + packet.len() as u16);
-    ip_packet.set_ttl(1);
-    ip_packet.set_next_level_protocol(IpNextHeaderProtocols::Udp);
+    ip_packet.set_dscp(OSPF_IP_DSCP);
+    ip_packet.set_ttl(OSPF_IP_TTL);
+    ip_packet.set_next_level_protocol(IpNextHeaderProtocol::new(OSPF_IP_PROTOCOL_NUMBER));
     ip_packet.set_source(src_ip);
     ip_packet.set_destination(dst_ip);
     ip_packet.set_payload(&packet);
@@ -132,36 +167,29 @@ pub fn new_ip_packet(
 
 pub fn try_get_from_ipv4_packet(
     ip_packet: &Ipv4Packet,
-    hello_neighbors: Arc<Mutex<HashMap<net::Ipv4Addr, Neighbor>>>,
-) -> Result<Box<dyn OspfPacket + Send>, &'static str> {
-    if ip_packet.get_dscp() != OSPF_IP_PROTOCOL_NUMBER {
-        return Err("not an ospf packet");
+) -> Result<Box<dyn OspfPacket + Send>, OspfDecodeError> {
+    if ip_packet.get_next_level_protocol() != IpNextHeaderProtocol::new(OSPF_IP_PROTOCOL_NUMBER) {
+        return Err(OspfDecodeError::NotOspf);
     }
     let ospf_packet = ip_packet.payload();
-    if ospf_packet.len() < OspfPacketHeader::length() {
-        return Err("packet too small");
-    }
-    if ospf_packet[0] != OSPF_VERSION_2 {
-        return Err("not an ospf version 2 packet");
-    }
-    let ospf_packet: Box<dyn OspfPacket + Send> = match ospf_packet[1] {
-        crate::packet::hello::HELLO_PACKET_TYPE => Box::new(hello::HelloPacket::from_be_bytes(
-            ospf_packet,
-            hello_neighbors,
-        )),
+    let header = OspfPacketHeader::from_be_bytes(ospf_packet)?;
+    let ospf_packet: Box<dyn OspfPacket + Send> = match header.packet_type {
+        crate::packet::hello::HELLO_PACKET_TYPE => {
+            Box::new(hello::HelloPacket::from_be_bytes(ospf_packet)?)
+        }
         crate::packet::dd::DATA_DESCRIPTION_PACKET_TYPE => {
-            Box::new(dd::DataDescriptionPacket::from_be_bytes(ospf_packet))
+            Box::new(dd::DataDescriptionPacket::from_be_bytes(ospf_packet)?)
         }
         crate::packet::lsr::LINK_STATE_REQUEST_PACKET_TYPE => {
-            Box::new(lsr::LinkStateRequestPacket::from_be_bytes(ospf_packet))
+            Box::new(lsr::LinkStateRequestPacket::from_be_bytes(ospf_packet)?)
         }
         crate::packet::lsack::LINK_STATE_ACKNOWLEDGEMENT_PACKET_TYPE => Box::new(
-            lsack::LinkStateAcknowledgementPacket::from_be_bytes(ospf_packet),
+            lsack::LinkStateAcknowledgementPacket::from_be_bytes(ospf_packet)?,
         ),
         crate::packet::lsu::LINK_STATE_UPDATE_TYPE => {
-            Box::new(lsu::LinkStateUpdatePacket::from_be_bytes(ospf_packet))
+            Box::new(lsu::LinkStateUpdatePacket::from_be_bytes(ospf_packet)?)
         }
-        _ => return Err("unknown packet type"),
+        packet_type => return Err(OspfDecodeError::UnknownPacketType(packet_type)),
     };
     Ok(ospf_packet)
 }
@@ -170,25 +198,42 @@ pub trait OspfPacket {
     fn length(&self) -> usize;
     fn to_bytes(&self) -> Vec<u8>;
     fn to_be_bytes(&self) -> Vec<u8>;
-    fn calculate_checksum(&mut self);
     fn get_type(&self) -> u8;
-    fn ipv4packet(&self) -> Result<Ipv4Packet, &'static str>;
+    fn ipv4packet(&self) -> Result<Ipv4Packet<'_>, &'static str>;
+    fn get_header(&self) -> &OspfPacketHeader;
+    fn get_header_mut(&mut self) -> &mut OspfPacketHeader;
+    /// used by the receive handlers to get the concrete packet back.
+    fn as_any(&self) -> &dyn std::any::Any;
+    /// fill in the packet length and the checksum of the header before sending.
+    /// with cryptographic authentication the checksum is not calculated and left zero.
+    fn calculate_checksum(&mut self) {
+        let length = self.length() as u16;
+        let header = self.get_header_mut();
+        header.packet_length = length;
+        header.checksum = 0;
+        if header.auth_type == AUTH_TYPE_CRYPTOGRAPHIC {
+            return;
+        }
+        let checksum = ospf_checksum(&self.to_be_bytes());
+        self.get_header_mut().checksum = checksum;
+    }
 }
 
-pub fn is_ip_packet_valid(packet: &Ipv4Packet) -> bool {
+pub fn is_ip_packet_valid(_packet: &Ipv4Packet) -> bool {
     true
 }
 
-pub fn is_ospf_packet_valid(ospf_packet: &dyn OspfPacket) -> bool {
-    let mut ospf_packet = ospf_packet.to_bytes();
-    let checksum = u16::from_be_bytes([ospf_packet[12], ospf_packet[13]]);
-    ospf_packet[12] = 0;
-    ospf_packet[13] = 0;
-    let calculated_checksum = calculate_checksum(
-        &ospf_packet
-            .chunks(2)
-            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
-            .collect::<Vec<u16>>(),
-    );
-    checksum == calculated_checksum
+/// verify the checksum of a received ospf packet on its raw bytes,
+/// the header must have been decoded successfully before.
+/// packets using cryptographic authentication carry no checksum.
+pub fn is_ospf_packet_valid(ospf_packet: &[u8]) -> bool {
+    let header = match OspfPacketHeader::from_be_bytes(ospf_packet) {
+        Ok(header) => header,
+        Err(_) => return false,
+    };
+    if header.auth_type == AUTH_TYPE_CRYPTOGRAPHIC {
+        return true;
+    }
+    let ospf_packet = &ospf_packet[..header.packet_length as usize];
+    header.checksum == ospf_checksum(ospf_packet)
 }
@@ -1,13 +1,19 @@
+use crate::auth::keychain::KeyChain;
+use crate::r#as::{self, AutomonySystem};
 use crate::rtable;
-use crate::{debug, interface};
+use crate::{area, debug, interface, lsdb};
 use std::collections::HashMap;
 use std::net;
 use std::sync::{Arc, Mutex};
 
 pub struct Router {
+    #[allow(dead_code)]
     router_table: Vec<rtable::RouteTable>,
     router_id: net::Ipv4Addr,
     interfaces: HashMap<String, Arc<Mutex<interface::Interface>>>,
+    areas: HashMap<net::Ipv4Addr, Arc<Mutex<area::Area>>>,
+    key_chains: HashMap<String, Arc<Mutex<KeyChain>>>,
+    autonomous_system: Arc<Mutex<AutomonySystem>>,
 }
 
 pub fn create_simulated_router(
@@ -33,7 +39,7 @@ pub fn create_simulated_router(
         ((router_id >> 8) & 0xff) as u8,
         (router_id & 0xff) as u8,
     ))));
-    for (_, interface) in &interfaces {
+    for interface in interfaces.values() {
         let mut interface = interface.lock().unwrap();
         interface.router = router.clone();
     }
@@ -50,19 +56,90 @@ impl Router {
     }
     pub async fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         debug("Router initialized.");
-        for (_, interface) in &self.interfaces {
-            let mut interface = interface.lock().unwrap();
-            interface.init_handlers(self.router_id).await?;
+        for interface in self.get_interfaces() {
+            let mut locked_interface = interface.lock().unwrap();
+            locked_interface.area = Some(self.get_or_create_area(locked_interface.get_area_id()));
+            locked_interface.transit_area = locked_interface
+                .transit_area_id
+                .map(|transit_area_id| self.get_or_create_area(transit_area_id));
+            drop(locked_interface);
+            interface::Interface::init_handlers(&interface, self.router_id)?;
+            interface::handle_event(&interface, interface::event::InterfaceEvent::InterfaceUp);
         }
+        let is_area_border = self.areas.len() > 1;
+        for area in self.areas.values() {
+            tokio::spawn(area::handle::check_lsa_checksum_raw_handle(area.clone()));
+            tokio::spawn(area::handle::router_lsa_origination_raw_handle(
+                area.clone(),
+                self.get_interfaces(),
+                self.router_id,
+                is_area_border,
+            ));
+        }
+        for interface in self.interfaces.values() {
+            tokio::spawn(interface::handle::network_lsa_origination_raw_handle(
+                interface.clone(),
+                self.get_interfaces(),
+            ));
+        }
+        tokio::spawn(r#as::handle::check_lsa_checksum_raw_handle(
+            self.autonomous_system.clone(),
+        ));
+        tokio::spawn(lsdb::handle::lsa_aging_raw_handle(
+            self.get_interfaces(),
+            self.get_areas(),
+            self.autonomous_system.clone(),
+        ));
         Ok(())
     }
+    /// the area `area_id`, created the first time an interface refers to it.
+    fn get_or_create_area(&mut self, area_id: net::Ipv4Addr) -> Arc<Mutex<area::Area>> {
+        let autonomous_system = &self.autonomous_system;
+        self.areas
+            .entry(area_id)
+            .or_insert_with(|| {
+                let mut area = area::Area::new(false, true, 0, area_id, Vec::new());
+                area.set_autonomous_system(autonomous_system.clone());
+                Arc::new(Mutex::new(area))
+            })
+            .clone()
+    }
     pub fn new(router_id: net::Ipv4Addr) -> Self {
         Self {
             router_table: Vec::new(),
             interfaces: HashMap::new(),
+            areas: HashMap::new(),
+            key_chains: HashMap::new(),
+            autonomous_system: Arc::new(Mutex::new(AutomonySystem::new())),
             router_id,
         }
     }
+    pub fn get_interface(&self, name: &str) -> Option<Arc<Mutex<interface::Interface>>> {
+        self.interfaces.get(name).cloned()
+    }
+    pub fn get_interfaces(&self) -> Vec<Arc<Mutex<interface::Interface>>> {
+        self.interfaces.values().cloned().collect()
+    }
+    pub fn get_area(&self, area_id: net::Ipv4Addr) -> Option<Arc<Mutex<area::Area>>> {
+        self.areas.get(&area_id).cloned()
+    }
+    pub fn get_areas(&self) -> Vec<Arc<Mutex<area::Area>>> {
+        self.areas.values().cloned().collect()
+    }
+    pub fn get_autonomous_system(&self) -> Arc<Mutex<AutomonySystem>> {
+        self.autonomous_system.clone()
+    }
+    /// get the key chain named `name`, creating an empty one if it does not exist yet.
+    /// the chain is shared, keys added to it later are used by every interface referring to it.
+    pub fn get_key_chain(&mut self, name: &str) -> Arc<Mutex<KeyChain>> {
+        self.key_chains
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(KeyChain::new(name))))
+            .clone()
+    }
+    pub fn get_key_chains(&self) -> Vec<Arc<Mutex<KeyChain>>> {
+        self.key_chains.values().cloned().collect()
+    }
     pub fn add_interface(&mut self, name: String, interface: Arc<Mutex<interface::Interface>>) {
         self.interfaces.insert(name, interface);
     }
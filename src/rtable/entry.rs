@@ -1,8 +1,11 @@
+// the routing table calculation is not implemented yet.
+#[allow(dead_code)]
 enum RouteDestinationType {
     Network,
     Host,
 }
 
+#[allow(dead_code)]
 pub struct RouteTableEntry {
     destination_type: RouteDestinationType,
 }
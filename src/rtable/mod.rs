@@ -1,6 +1,9 @@
 pub mod entry;
 
+#[derive(Default)]
 pub struct RouteTable {
+    // filled in by the routing table calculation, not implemented yet.
+    #[allow(dead_code)]
     entries: Vec<entry::RouteTableEntry>,
 }
 
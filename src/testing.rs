@@ -0,0 +1,175 @@
+use std::{
+    net,
+    sync::{Arc, Mutex},
+};
+
+use pnet::packet::ipv4::Ipv4Packet;
+use tokio::sync::broadcast;
+
+use crate::{
+    area::Area,
+    auth::Authentication,
+    interface::{Interface, InterfaceConfig, InterfaceNetworkType},
+    lsa::{
+        router::{RouterLinkStateAdvertisement, ROUTER_LINK_STATE_TYPE},
+        LinkStateAdvertisement, LinkStateAdvertisementHeader,
+    },
+    neighbor::{status::NeighborStatus, Neighbor},
+    packet::{
+        self,
+        hello::{HelloPacket, HELLO_PACKET_TYPE},
+        OspfPacket, OspfPacketHeader,
+    },
+    router::Router,
+    OPTION_BIT_E, OSPF_VERSION_2,
+};
+
+pub const NETWORK_MASK: net::Ipv4Addr = net::Ipv4Addr::new(255, 255, 255, 0);
+
+/// an interface with the default configuration, as `init_handlers` would leave it
+/// but without a socket: the packets it sends are read with `sent_packets`.
+pub fn new_interface(
+    ip_addr: net::Ipv4Addr,
+    router_id: net::Ipv4Addr,
+    network_type: InterfaceNetworkType,
+) -> (Arc<Mutex<Interface>>, broadcast::Receiver<bytes::Bytes>) {
+    let router = Arc::new(Mutex::new(Router::new(router_id)));
+    let area = Area::new(
+        false,
+        true,
+        0,
+        InterfaceConfig::default().area_id,
+        Vec::new(),
+    );
+    add_interface(&router, Arc::new(Mutex::new(area)), ip_addr, network_type)
+}
+
+/// another interface of the router of `interface`, in the same area.
+pub fn new_sibling_interface(
+    interface: &Arc<Mutex<Interface>>,
+    ip_addr: net::Ipv4Addr,
+    network_type: InterfaceNetworkType,
+) -> (Arc<Mutex<Interface>>, broadcast::Receiver<bytes::Bytes>) {
+    let locked_interface = interface.lock().unwrap();
+    let router = locked_interface.router.clone();
+    let area = locked_interface.area.clone().unwrap();
+    drop(locked_interface);
+    add_interface(&router, area, ip_addr, network_type)
+}
+
+fn add_interface(
+    router: &Arc<Mutex<Router>>,
+    area: Arc<Mutex<Area>>,
+    ip_addr: net::Ipv4Addr,
+    network_type: InterfaceNetworkType,
+) -> (Arc<Mutex<Interface>>, broadcast::Receiver<bytes::Bytes>) {
+    let mut locked_router = router.lock().unwrap();
+    let name = format!("eth{}", locked_router.get_interfaces().len());
+    let mut interface = Interface::new(
+        name.clone(),
+        ip_addr,
+        NETWORK_MASK,
+        network_type,
+        InterfaceConfig::default(),
+        router.clone(),
+    );
+    let (send_packet_tx, send_packet_rx) = broadcast::channel(128);
+    interface.router_id = locked_router.get_router_id();
+    interface.area = Some(area);
+    interface.authentication = Some(Authentication::Null);
+    interface.send_packet_tx = Some(send_packet_tx);
+    let interface = Arc::new(Mutex::new(interface));
+    locked_router.add_interface(name, interface.clone());
+    (interface, send_packet_rx)
+}
+
+/// put a neighbor in `status` on the interface as if the hellos and the database exchange
+/// had brought it there, returns its key.
+pub fn add_neighbor(
+    interface: &Arc<Mutex<Interface>>,
+    ip_addr: net::Ipv4Addr,
+    router_id: net::Ipv4Addr,
+    status: NeighborStatus,
+) -> net::Ipv4Addr {
+    let locked_interface = interface.lock().unwrap();
+    let neighbor_key = locked_interface.get_neighbor_key(ip_addr, router_id);
+    let mut neighbor = Neighbor::new(ip_addr);
+    neighbor.router_id = router_id;
+    neighbor.status = status;
+    neighbor.options = OPTION_BIT_E;
+    locked_interface
+        .neighbors
+        .lock()
+        .unwrap()
+        .insert(neighbor_key, neighbor);
+    neighbor_key
+}
+
+/// a router-lsa of `advertising_router` without links.
+pub fn new_router_lsa(
+    advertising_router: net::Ipv4Addr,
+    age: u16,
+    sequence_number: u32,
+) -> Box<dyn LinkStateAdvertisement + Send> {
+    let mut router_lsa = RouterLinkStateAdvertisement::new(
+        LinkStateAdvertisementHeader::new(
+            age,
+            OPTION_BIT_E,
+            ROUTER_LINK_STATE_TYPE,
+            advertising_router.into(),
+            advertising_router.into(),
+            sequence_number,
+            0,
+            0,
+        ),
+        0,
+        0,
+        Vec::new(),
+    );
+    router_lsa.calculate_checksum();
+    Box::new(router_lsa)
+}
+
+/// a hello matching the default configuration of an interface, sent by `router_id`.
+pub fn new_hello_packet(
+    router_id: net::Ipv4Addr,
+    priority: u8,
+    designated_router: net::Ipv4Addr,
+    backup_designated_router: net::Ipv4Addr,
+    neighbors: Vec<net::Ipv4Addr>,
+) -> HelloPacket {
+    let config = InterfaceConfig::default();
+    HelloPacket::new(
+        NETWORK_MASK,
+        config.hello_interval as u16,
+        OPTION_BIT_E,
+        priority,
+        config.router_dead_interval,
+        designated_router.into(),
+        backup_designated_router.into(),
+        OspfPacketHeader::new(
+            OSPF_VERSION_2,
+            HELLO_PACKET_TYPE,
+            0,
+            router_id.into(),
+            config.area_id.into(),
+            0,
+            0,
+            0,
+        ),
+        neighbors,
+    )
+}
+
+/// the packets sent since the last call, with their destination.
+pub fn sent_packets(
+    send_packet_rx: &mut broadcast::Receiver<bytes::Bytes>,
+) -> Vec<(net::Ipv4Addr, Box<dyn OspfPacket + Send>)> {
+    let mut packets = Vec::new();
+    while let Ok(bytes) = send_packet_rx.try_recv() {
+        let ip_packet = Ipv4Packet::new(&bytes).unwrap();
+        let ospf_packet = packet::try_get_from_ipv4_packet(&ip_packet).unwrap();
+        packets.push((ip_packet.get_destination(), ospf_packet));
+    }
+    packets
+}